@@ -0,0 +1,104 @@
+//! 流式与一次性加载大型 CSV 的峰值内存对比
+//! 
+//! 运行：`cargo run --release --example bench_csv_streaming -- [streaming|eager|eager-select] [行数] [列数] [文件路径]`，
+//! 默认 streaming、200 万行、200 列，写到系统临时目录（约 3.5GB）。
+//! 三种模式得到的都是时间列与前 `N_STOCKS` 只股票、后一半时间的数据：
+//! `streaming` 用 [`WideTable::from_csv_streaming`] 在扫描时完成投影与过滤；
+//! `eager-select` 先用 [`WideTable::from_csv`] 加载整个文件再筛选；`eager` 只加载整个文件，作为参照。
+//! 内存取自 `/proc/self/status`，只在 Linux 上可用：`VmHWM` 为峰值常驻内存，
+//! 其中包含 CSV 读取时 mmap 进来的文件页（可被系统回收）；`RssAnon` 为加载完成时的匿名内存。
+//! 各模式要分别运行，否则同一进程的峰值会互相影响。
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use quant_factor::WideTable;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// 要加载的股票数
+const N_STOCKS: usize = 5;
+
+/// `/proc/self/status` 中某一项内存统计（MB），如 `VmHWM`、`RssAnon`
+fn proc_status_mb(key: &str) -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.strip_prefix(key).is_some_and(|rest| rest.starts_with(':')))?;
+    let kb: f64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024.0)
+}
+
+/// 逐行写出 CSV，不在内存中保留整张表
+fn generate_csv(path: &PathBuf, n_rows: usize, n_cols: usize) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(writer, "date")?;
+    for c in 0..n_cols {
+        write!(writer, ",{:06}", c)?;
+    }
+    writeln!(writer)?;
+    
+    let start = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+    for i in 0..n_rows {
+        write!(writer, "{}", start + chrono::Duration::days(i as i64))?;
+        for c in 0..n_cols {
+            let price = 100.0 + ((i * 7 + c * 13) % 29) as f64 - 14.0 + (i % 1000) as f64 * 0.05;
+            write!(writer, ",{:.4}", price)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let mode = args.next().unwrap_or_else(|| "streaming".to_string());
+    let n_rows: usize = args.next().map(|s| s.parse()).transpose()?.unwrap_or(2_000_000);
+    let n_cols: usize = args.next().map(|s| s.parse()).transpose()?.unwrap_or(200);
+    let path = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("quant_factor_bench_{}x{}.csv", n_rows, n_cols)));
+    
+    if !path.exists() {
+        let t = Instant::now();
+        generate_csv(&path, n_rows, n_cols)?;
+        println!("生成 {}：{:.1} s", path.display(), t.elapsed().as_secs_f64());
+    }
+    let file_mb = std::fs::metadata(&path)?.len() as f64 / 1024.0 / 1024.0;
+    let before = proc_status_mb("VmHWM");
+    
+    let names: Vec<String> = (0..n_cols.min(N_STOCKS)).map(|c| format!("{:06}", c)).collect();
+    let stocks: Vec<&str> = names.iter().map(String::as_str).collect();
+    // 生成的日期从 1900-01-01 起逐日递增，保留后一半时间；时间列按字符串读入，
+    // 行数很多时日期会超出 polars 时间类型的范围，因此直接比较 ISO 格式的字符串
+    let midpoint = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap() + chrono::Duration::days(n_rows as i64 / 2);
+    let filter = col("date").gt_eq(lit(midpoint.to_string()));
+    
+    let t = Instant::now();
+    let table = match mode.as_str() {
+        "streaming" => WideTable::from_csv_streaming(&path, "date", &stocks, Some(filter))?,
+        "eager" => WideTable::from_csv(&path, "date")?,
+        "eager-select" => {
+            let full = WideTable::from_csv(&path, "date")?;
+            let mut columns = vec![col("date")];
+            columns.extend(stocks.iter().map(|&name| col(name)));
+            WideTable::from_lazy(full.lazy().select(columns).filter(filter), "date")?
+        }
+        other => anyhow::bail!("未知模式 '{}'，可选 streaming、eager 或 eager-select", other),
+    };
+    let elapsed = t.elapsed().as_secs_f64();
+    
+    let result_mb = table.df().estimated_size() as f64 / 1024.0 / 1024.0;
+    println!("{} 列 x {} 行，文件 {:.0} MB", n_cols, n_rows, file_mb);
+    println!("{:<12} {:>8.1} s  结果 {} 行 x {} 列，{:.0} MB", mode, elapsed, table.df().height(), table.df().width(), result_mb);
+    match (before, proc_status_mb("VmHWM"), proc_status_mb("RssAnon")) {
+        (Some(before), Some(peak), Some(anon)) => println!(
+            "峰值 RSS：加载前 {:.0} MB，加载后 {:.0} MB；加载完成时匿名内存 {:.0} MB",
+            before, peak, anon
+        ),
+        _ => println!("当前平台无法读取进程内存"),
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! 宽表列数很多时的因子计算耗时
+//! 
+//! 运行：`cargo run --release --example bench_wide_columns -- [列数] [行数]`，默认 5000 列、250 行。
+//! 同时计时旧的逐列链式 `with_columns` 写法与当前一次 `with_columns` 的写法，并断言两者结果一致。
+//! 链式写法的查询计划深度与列数相同，默认栈大小下几百列就会栈溢出，
+//! 因此对比在一个栈大小为 `STACK_MB` 的线程中运行。
+
+use chrono::NaiveDate;
+use polars::prelude::*;
+use quant_factor::WideTable;
+use std::time::Instant;
+
+/// 运行对比的线程栈大小（MB），足以容纳 5000 列的链式查询计划
+const STACK_MB: usize = 4096;
+
+/// 旧写法：每只股票单独调用一次 `with_columns`，与 `pct_change(periods)` 输出相同的列
+fn chained_pct_change(table: &WideTable, periods: i32) -> anyhow::Result<WideTable> {
+    let time_col = table.time_col();
+    let mut lazy = table.df().clone().lazy().sort([time_col], Default::default());
+    for name in table.df().get_column_names() {
+        if name == time_col {
+            continue;
+        }
+        let prev = col(name).shift(lit(periods));
+        lazy = lazy.with_columns([((col(name) - prev.clone()) / prev * lit(100.0))
+            .alias(&format!("{}_pct_change_{}", name, periods))]);
+    }
+    Ok(WideTable::from_lazy(lazy, time_col)?)
+}
+
+/// 旧写法：逐列链式计算 `momentum(periods)`，先追加小数收益率列，再追加其滚动求和
+fn chained_momentum(table: &WideTable, periods: i32) -> anyhow::Result<WideTable> {
+    let time_col = table.time_col();
+    let names: Vec<&str> = table.df().get_column_names().into_iter().filter(|&name| name != time_col).collect();
+    let mut lazy = table.df().clone().lazy().sort([time_col], Default::default());
+    for name in &names {
+        let prev = col(name).shift(lit(1));
+        lazy = lazy.with_columns([((col(name) - prev.clone()) / prev * lit(1.0)).alias(&format!("{}_return_1", name))]);
+    }
+    for name in &names {
+        let sum = col(&format!("{}_return_1", name)).rolling_sum(RollingOptionsFixedWindow {
+            window_size: periods as usize,
+            min_periods: 1,
+            center: false,
+        });
+        lazy = lazy.with_columns([(sum * lit(1.0)).alias(&format!("{}_momentum_{}", name, periods))]);
+    }
+    Ok(WideTable::from_lazy(lazy, time_col)?)
+}
+
+fn main() -> anyhow::Result<()> {
+    std::thread::Builder::new()
+        .stack_size(STACK_MB * 1024 * 1024)
+        .spawn(run)?
+        .join()
+        .map_err(|_| anyhow::anyhow!("对比线程 panic"))?
+}
+
+fn run() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let n_cols: usize = args.next().map(|s| s.parse()).transpose()?.unwrap_or(5000);
+    let n_rows: usize = args.next().map(|s| s.parse()).transpose()?.unwrap_or(250);
+    
+    let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    let dates: Vec<NaiveDate> = (0..n_rows)
+        .map(|i| start + chrono::Duration::days(i as i64))
+        .collect();
+    let mut columns = vec![Series::new("date", dates)];
+    for c in 0..n_cols {
+        let prices: Vec<f64> = (0..n_rows)
+            .map(|i| 100.0 + ((i * 7 + c * 13) % 29) as f64 - 14.0 + i as f64 * 0.05)
+            .collect();
+        columns.push(Series::new(&format!("{:06}", c), prices));
+    }
+    let table = WideTable::new(DataFrame::new(columns)?, "date")?;
+    println!("{} 列 x {} 行", n_cols, n_rows);
+    
+    let timed = |name: &str, f: &dyn Fn() -> anyhow::Result<WideTable>| -> anyhow::Result<WideTable> {
+        let t = Instant::now();
+        let result = f()?;
+        println!("{:<20} {:>8.1} ms  ({} 列)", name, t.elapsed().as_secs_f64() * 1e3, result.df().width());
+        Ok(result)
+    };
+    
+    let single = timed("pct_change", &|| Ok(table.pct_change(1)?))?;
+    let chained = timed("pct_change (链式)", &|| chained_pct_change(&table, 1))?;
+    assert!(single.df().equals_missing(chained.df()), "pct_change 两种写法结果不一致");
+    
+    let single = timed("momentum", &|| Ok(table.momentum(20)?))?;
+    let chained = timed("momentum (链式)", &|| chained_momentum(&table, 20))?;
+    assert!(single.df().equals_missing(chained.df()), "momentum 两种写法结果不一致");
+    
+    Ok(())
+}
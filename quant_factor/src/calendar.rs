@@ -0,0 +1,157 @@
+//! 交易日历：按市场的节假日表判断交易日
+
+use crate::error::WideTableError;
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+type Result<T, E = WideTableError> = std::result::Result<T, E>;
+
+/// 内置的各市场休市日数据，每行一个 `YYYY-MM-DD`，`#` 开头为注释
+const HOLIDAY_FILES: &[(&str, &str)] = &[
+    ("CN", include_str!("../data/holidays/CN.txt")),
+    ("US", include_str!("../data/holidays/US.txt")),
+];
+
+/// 某个市场的交易日历
+/// 
+/// 交易日 = 非周末且不在休市日表中的日期。内置休市日只覆盖部分年份（目前为 2023–2025 年，
+/// 见 [`TradingCalendar::covered_years`]）：超出覆盖范围时无法判断节假日，
+/// [`TradingCalendar::trading_days`] 会返回错误，而不是把节假日当作交易日。
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    country: String,
+    holidays: HashSet<NaiveDate>,
+    covered_years: RangeInclusive<i32>,
+}
+
+impl TradingCalendar {
+    /// 加载内置的休市日数据
+    /// 
+    /// # Arguments
+    /// * `country` - 市场代码，目前支持 `CN`（A 股）和 `US`（NYSE），不区分大小写
+    /// 
+    /// # Errors
+    /// 市场代码不支持时返回 [`WideTableError::InvalidArgument`]
+    pub fn new(country: &str) -> Result<Self> {
+        let code = country.to_uppercase();
+        let (_, data) = HOLIDAY_FILES
+            .iter()
+            .find(|(name, _)| *name == code)
+            .ok_or_else(|| WideTableError::InvalidArgument(format!(
+                "不支持的交易日历 '{}'，可选：{}",
+                country,
+                HOLIDAY_FILES.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+            )))?;
+        
+        let holidays = data
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| NaiveDate::parse_from_str(line, "%Y-%m-%d")
+                .map_err(|e| WideTableError::InvalidArgument(format!("休市日 '{}' 无法解析：{}", line, e))))
+            .collect::<Result<HashSet<_>>>()?;
+        
+        // 休市日数据按整年维护，覆盖范围取其中最早与最晚的年份
+        let first = holidays.iter().map(|d| d.year()).min().unwrap_or(1);
+        let last = holidays.iter().map(|d| d.year()).max().unwrap_or(0);
+        
+        Ok(TradingCalendar { country: code, holidays, covered_years: first..=last })
+    }
+    
+    /// 市场代码（大写）
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+    
+    /// 休市日数据覆盖的年份（闭区间）
+    pub fn covered_years(&self) -> RangeInclusive<i32> {
+        self.covered_years.clone()
+    }
+    
+    /// 判断某天是否在休市日数据覆盖的年份内
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.covered_years.contains(&date.year())
+    }
+    
+    /// 判断某天是否为交易日
+    /// 
+    /// 不检查覆盖范围：对 [`TradingCalendar::covers`] 为 false 的日期只排除周末
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+    
+    /// `[start, end]` 闭区间内的所有交易日，按时间升序
+    /// 
+    /// `start` 晚于 `end` 时返回空列表
+    /// 
+    /// # Errors
+    /// 区间超出 [`TradingCalendar::covered_years`] 时返回 [`WideTableError::CalendarNotCovered`]
+    pub fn trading_days(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<NaiveDate>> {
+        if start > end {
+            return Ok(Vec::new());
+        }
+        if !self.covers(start) || !self.covers(end) {
+            return Err(WideTableError::CalendarNotCovered(format!(
+                "{} 的休市日数据只覆盖 {}–{} 年，请求区间为 {} 至 {}",
+                self.country,
+                self.covered_years.start(),
+                self.covered_years.end(),
+                start,
+                end
+            )));
+        }
+        
+        Ok(start
+            .iter_days()
+            .take_while(|date| *date <= end)
+            .filter(|date| self.is_trading_day(*date))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+    
+    #[test]
+    fn test_trading_days_skip_weekends_and_holidays() {
+        let cn = TradingCalendar::new("CN").unwrap();
+        // 2024 年国庆：10-01 至 10-07 休市，10-08 开市
+        let days = cn.trading_days(date(2024, 9, 27), date(2024, 10, 9)).unwrap();
+        assert_eq!(days, vec![date(2024, 9, 27), date(2024, 9, 30), date(2024, 10, 8), date(2024, 10, 9)]);
+        
+        let us = TradingCalendar::new("us").unwrap();
+        assert_eq!(us.country(), "US");
+        assert!(!us.is_trading_day(date(2024, 7, 4)));
+        assert!(us.is_trading_day(date(2024, 7, 5)));
+        assert!(us.trading_days(date(2024, 7, 5), date(2024, 7, 1)).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_uncovered_years() {
+        let cn = TradingCalendar::new("CN").unwrap();
+        assert_eq!(cn.covered_years(), 2023..=2025);
+        assert!(cn.covers(date(2025, 12, 31)));
+        assert!(!cn.covers(date(2026, 1, 1)));
+        
+        // 2026 年春节没有数据，不能静默当作交易日
+        assert!(matches!(
+            cn.trading_days(date(2025, 12, 29), date(2026, 2, 20)),
+            Err(WideTableError::CalendarNotCovered(_))
+        ));
+        assert!(matches!(
+            cn.trading_days(date(2022, 12, 1), date(2023, 1, 10)),
+            Err(WideTableError::CalendarNotCovered(_))
+        ));
+    }
+    
+    #[test]
+    fn test_unknown_country() {
+        assert!(matches!(TradingCalendar::new("XX"), Err(WideTableError::InvalidArgument(_))));
+    }
+}
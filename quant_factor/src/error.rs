@@ -0,0 +1,52 @@
+//! 宽表操作的错误类型
+
+use polars::prelude::PolarsError;
+use thiserror::Error;
+
+/// [`WideTable`](crate::WideTable) 方法返回的错误
+/// 
+/// 调用方可按变体区分失败原因（例如映射为不同的 HTTP 状态码）。
+/// 实现了 `std::error::Error`，在返回 `anyhow::Result` 的函数中可直接用 `?` 转换。
+#[derive(Debug, Error)]
+pub enum WideTableError {
+    /// 时间列不存在
+    #[error("时间列 '{0}' 不存在")]
+    TimeColumnMissing(String),
+    /// 指定的列不存在
+    #[error("列 '{0}' 不存在")]
+    ColumnNotFound(String),
+    /// 时间列中有重复的时间
+    #[error("时间 '{0}' 重复")]
+    DuplicateDate(String),
+    /// 列名重复
+    #[error("列名 '{0}' 重复")]
+    DuplicateColumn(String),
+    /// 参数不合法，如窗口长度为 0
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// 列集合或数据类型与预期不一致
+    #[error("schema 不一致：{0}")]
+    SchemaMismatch(String),
+    /// 数据中包含 NaN
+    #[error("以下列包含 NaN：{0}")]
+    ContainsNan(String),
+    /// 日期超出交易日历休市日数据覆盖的年份
+    #[error("交易日历未覆盖：{0}")]
+    CalendarNotCovered(String),
+    /// 因子列疑似使用了未来数据
+    #[error("以下列疑似使用了未来数据：{0}")]
+    FutureLeakage(String),
+    /// 文件读写出错
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// JSON 解析或序列化出错，如元数据文件格式不正确
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// polars 计算出错
+    #[error(transparent)]
+    Polars(#[from] PolarsError),
+    /// 异步任务执行失败
+    #[cfg(feature = "tokio")]
+    #[error(transparent)]
+    Task(#[from] tokio::task::JoinError),
+}
@@ -1,6 +1,45 @@
-use crate::wide_table::WideTable;
+use crate::error::WideTableError;
+use crate::price_set::PriceSet;
+use crate::regression::{ols_multi, ols_residuals, rolling_ols, rolling_ols_where, OlsFit};
+use crate::stats::{self, ZERO_TOLERANCE};
+use crate::wide_table::{ema_expr, RankMethod, ReturnScale, WideTable};
+use polars::prelude::*;
 use anyhow::Result;
 
+/// 每年交易日数，用于日频数据的年化
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// 在险价值（VaR）的计算方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VarMethod {
+    /// 历史模拟：收益率的经验分位数（线性插值，与 numpy 默认一致）（默认）
+    #[default]
+    Historical,
+    /// 参数法：假设收益率服从正态分布
+    Parametric,
+    /// Cornish-Fisher 展开：用样本偏度与超额峰度修正正态分位数
+    CornishFisher,
+}
+
+/// 季节性因子的分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeasonGroup {
+    /// 按星期几分组，输出 `{code}_seas_wd`
+    Weekday,
+    /// 按自然月分组，输出 `{code}_seas_m`
+    Month,
+}
+
+/// 多空组合的加权方式
+#[derive(Clone, Copy, Default)]
+pub enum PortfolioWeighting<'a> {
+    /// 等权（默认）
+    #[default]
+    Equal,
+    /// 按市值加权，市值宽表的股票列名须与因子宽表一致（按时间列对齐）
+    ValueWeighted(&'a WideTable),
+}
+
 /// 因子计算器
 pub struct FactorCalculator;
 
@@ -14,7 +53,7 @@ impl FactorCalculator {
     /// # Returns
     /// 包含动量因子的宽表
     pub fn calculate_momentum(table: &WideTable, periods: i32) -> Result<WideTable> {
-        table.momentum(periods)
+        Ok(table.momentum(periods)?)
     }
     
     /// 计算收益率
@@ -26,6 +65,3415 @@ impl FactorCalculator {
     /// # Returns
     /// 包含收益率的宽表
     pub fn calculate_returns(table: &WideTable, periods: i32) -> Result<WideTable> {
-        table.pct_change(periods)
+        Ok(table.pct_change(periods)?)
+    }
+    
+    /// 计算动量加速度
+    /// 
+    /// 当期 `periods` 动量减去 `periods` 期之前的 `periods` 动量，衡量趋势的加速或减速
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `periods` - 动量周期，同时也是比较的间隔
+    /// 
+    /// # Returns
+    /// 包含 `{code}_mom_accel_{periods}` 列的宽表
+    pub fn calculate_momentum_accel(table: &WideTable, periods: i32) -> Result<WideTable> {
+        let mom_table = table.momentum(periods)?;
+        let exprs: Vec<Expr> = table
+            .stock_cols()
+            .iter()
+            .map(|code| {
+                let mom_col = format!("{}_momentum_{}", code, periods);
+                (col(&mom_col) - col(&mom_col).shift(lit(periods))).alias(&format!("{}_mom_accel_{}", code, periods))
+            })
+            .collect();
+        
+        Ok(WideTable::from_lazy(mom_table.lazy().with_columns(exprs), table.time_col())?)
+    }
+    
+    /// 计算跳过近期的动量（剔除短期反转）
+    /// 
+    /// `pct_change_fraction(short_period + skip_period) - pct_change_fraction(skip_period)`（小数），
+    /// 即中期动量扣除最近 `skip_period` 期容易反转的收益。例如日频数据上
+    /// `short_period = 220, skip_period = 20` 近似经典的 12-1 月动量。
+    /// 前 `short_period + skip_period` 行为 null。
+    /// 
+    /// 单位与 [`WideTable::momentum_skip`] 相同（小数）。两者的区别是：后者为跳过区间的复利收益率
+    /// `P_{t-skip} / P_{t-lookback} - 1`，这里为两段收益率之差。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `short_period` - 动量区间期数，必须为正数
+    /// * `skip_period` - 跳过的最近期数，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_mom_rev_{short_period}_{skip_period}` 列的宽表
+    pub fn calculate_momentum_reversal(table: &WideTable, short_period: i32, skip_period: i32) -> Result<WideTable> {
+        if short_period <= 0 || skip_period <= 0 {
+            return Err(anyhow::anyhow!(
+                "动量区间与跳过期数必须为正数，当前为 {} 与 {}",
+                short_period,
+                skip_period
+            ));
+        }
+        
+        let total = short_period + skip_period;
+        let mut exprs = vec![col(table.time_col())];
+        for code in table.stock_cols() {
+            let price = col(&code).cast(DataType::Float64);
+            let long_change = price.clone() / price.clone().shift(lit(total)) - lit(1.0);
+            let skip_change = price.clone() / price.shift(lit(skip_period)) - lit(1.0);
+            exprs.push(
+                (long_change - skip_change)
+                    .alias(&format!("{}_mom_rev_{}_{}", code, short_period, skip_period)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(table.sorted_df()?.lazy().select(exprs), table.time_col())?)
+    }
+    
+    /// 计算动量的截面排名因子
+    /// 
+    /// 先计算 `periods` 期动量，再在每个时间截面上对股票做平均排名并除以有效股票数，
+    /// 取值范围为 `(0, 1]`。只对完整窗口排名：前 `periods` 行或 `periods` 期前价格缺失时，
+    /// 该股票当期为 null，不参与当期排名，避免部分窗口的动量与完整窗口混排。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `periods` - 动量周期
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_momrank_{periods}` 列的宽表
+    pub fn calculate_momentum_rank(table: &WideTable, periods: i32) -> Result<WideTable> {
+        let mom_table = table.momentum(periods)?;
+        
+        let mut exprs = vec![col(table.time_col())];
+        for code in table.stock_cols() {
+            exprs.push(
+                when(col(&code).shift(lit(periods)).is_null())
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise(col(&format!("{}_momentum_{}", code, periods)))
+                    .alias(&format!("{}_momrank_{}", code, periods)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(mom_table.lazy().select(exprs), table.time_col())?
+            .rank_cross_section(RankMethod::Average, true)?)
+    }
+    
+    /// 计算窗口内最大单期收益率（MAX 彩票偏好因子）
+    /// 
+    /// 对 1 期小数收益率取 `window` 期滚动最大值，窗口未满时为 null
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 滚动窗口，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_maxret_{window}` 列的宽表
+    pub fn calculate_max_return(table: &WideTable, window: usize) -> Result<WideTable> {
+        rolling_return_extreme(table, window, "maxret", Expr::rolling_max)
+    }
+    
+    /// 计算窗口内最小单期收益率，与 [`FactorCalculator::calculate_max_return`] 对称
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_minret_{window}` 列的宽表
+    pub fn calculate_min_return(table: &WideTable, window: usize) -> Result<WideTable> {
+        rolling_return_extreme(table, window, "minret", Expr::rolling_min)
+    }
+    
+    /// 计算收益一致性因子（平滑动量）
+    /// 
+    /// 把最近 `window` 期切分为连续的 `window / sub_period` 个子区间，
+    /// 结果为累计收益率为正的子区间占比，取值在 `[0, 1]` 内。
+    /// 稳步上涨的股票为 1，靠单次跳涨拉高动量的股票得分较低。
+    /// 任一子区间端点价格缺失或窗口未满（前 `window` 行）时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 回看期数，必须是 `sub_period` 的正整数倍
+    /// * `sub_period` - 子区间期数，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_consist_{window}_{sub_period}` 列的宽表
+    pub fn calculate_consistency(table: &WideTable, window: usize, sub_period: usize) -> Result<WideTable> {
+        if sub_period == 0 || window == 0 || !window.is_multiple_of(sub_period) {
+            return Err(anyhow::anyhow!(
+                "回看期数 {} 必须是子区间期数 {} 的正整数倍",
+                window,
+                sub_period
+            ));
+        }
+        
+        let n_blocks = window / sub_period;
+        let mut exprs = vec![col(table.time_col())];
+        for code in table.stock_cols() {
+            let price = col(&code).cast(DataType::Float64);
+            // 第 k 个子区间为 [t - (k + 1) * sub_period, t - k * sub_period]，端点缺失时比较结果为 null
+            let positive = (0..n_blocks)
+                .map(|k| {
+                    let end = price.clone().shift(lit((k * sub_period) as i64));
+                    let start = price.clone().shift(lit(((k + 1) * sub_period) as i64));
+                    end.gt(start).cast(DataType::Float64)
+                })
+                .reduce(|acc, e| acc + e)
+                .expect("n_blocks >= 1");
+            exprs.push((positive / lit(n_blocks as f64)).alias(&format!("{}_consist_{}_{}", code, window, sub_period)));
+        }
+        
+        Ok(WideTable::from_lazy(table.sorted_df()?.lazy().select(exprs), table.time_col())?)
+    }
+    
+    /// 计算季节性因子：同星期几 / 同月份的历史平均收益率
+    /// 
+    /// 对每个时间 t，取 t 之前（不含 t）所有与 t 属于同一星期几（或同一自然月）的
+    /// 单期小数收益率的均值，即分组内的扩张均值，不使用未来数据。
+    /// 缺失的收益率不计入；同组还没有历史收益率时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），时间列须为日期或时间类型
+    /// * `group` - 分组方式
+    /// 
+    /// # Errors
+    /// 时间列不是日期或时间类型（如整数序号）时返回错误
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_seas_wd`（按星期几）或 `{code}_seas_m`（按月份）列的宽表
+    pub fn calculate_seasonality(table: &WideTable, group: SeasonGroup) -> Result<WideTable> {
+        const SEASON: &str = "__season";
+        let time_col = table.time_col();
+        let dtype = table.df().column(time_col)?.dtype().clone();
+        if !dtype.is_temporal() {
+            return Err(anyhow::anyhow!("季节性因子要求时间列为日期或时间类型，当前为 {}", dtype));
+        }
+        let (key, suffix) = match group {
+            SeasonGroup::Weekday => (col(time_col).dt().weekday(), "seas_wd"),
+            SeasonGroup::Month => (col(time_col).dt().month(), "seas_m"),
+        };
+        
+        let codes = table.stock_cols();
+        let mut prepared = vec![key.alias(SEASON)];
+        let mut exprs = vec![col(time_col)];
+        for code in &codes {
+            let ret_col = format!("__ret_{}", code);
+            let price = col(code).cast(DataType::Float64);
+            prepared.push((price.clone() / price.shift(lit(1)) - lit(1.0)).alias(&ret_col));
+            
+            // 组内累计和与累计个数都减去当期，只保留严格早于 t 的观测
+            let ret = col(&ret_col);
+            let valid = ret.clone().is_not_null().cast(DataType::Float64);
+            let past_sum = ret.clone().fill_null(lit(0.0)).cum_sum(false).over([col(SEASON)]) - ret.fill_null(lit(0.0));
+            let past_count = valid.clone().cum_sum(false).over([col(SEASON)]) - valid;
+            exprs.push(
+                when(past_count.clone().gt(lit(0.0)))
+                    .then(past_sum / past_count)
+                    .otherwise(lit(NULL).cast(DataType::Float64))
+                    .alias(&format!("{}_{}", code, suffix)),
+            );
+        }
+        
+        let lf = table.sorted_df()?.lazy().with_columns(prepared).select(exprs);
+        Ok(WideTable::from_lazy(lf, time_col)?)
+    }
+    
+    /// 计算滚动波动率
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 滚动窗口长度
+    /// 
+    /// # Returns
+    /// 包含 `{code}_volatility_{window}` 列的宽表
+    pub fn calculate_volatility(table: &WideTable, window: usize) -> Result<WideTable> {
+        Ok(table.volatility(window)?)
+    }
+    
+    /// 计算波动率调整动量
+    /// 
+    /// `momentum(mom_window) / volatility(vol_window)`：动量为 `mom_window` 期小数收益率之和，
+    /// 波动率为 `vol_window` 期小数收益率的样本标准差，两者在同一个 lazy 查询中计算。
+    /// 任一部分窗口未满（此处动量同样要求完整窗口）或波动率为 0 时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `mom_window` - 动量窗口，必须为正数
+    /// * `vol_window` - 波动率窗口，至少为 2
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_ramom_{mom_window}_{vol_window}` 列的宽表
+    pub fn calculate_risk_adj_momentum(table: &WideTable, mom_window: usize, vol_window: usize) -> Result<WideTable> {
+        if mom_window == 0 {
+            return Err(anyhow::anyhow!("动量窗口必须为正数"));
+        }
+        if vol_window < 2 {
+            return Err(anyhow::anyhow!("波动率窗口至少为 2，当前为 {}", vol_window));
+        }
+        
+        let mut exprs = vec![col(table.time_col())];
+        for code in table.stock_cols() {
+            let price = col(&code).cast(DataType::Float64);
+            let ret = price.clone() / price.shift(lit(1)) - lit(1.0);
+            let momentum = ret.clone().rolling_sum(RollingOptionsFixedWindow {
+                window_size: mom_window,
+                min_periods: mom_window,
+                center: false,
+            });
+            let volatility = ret.rolling_std(RollingOptionsFixedWindow {
+                window_size: vol_window,
+                min_periods: vol_window,
+                center: false,
+            });
+            exprs.push(
+                when(volatility.clone().lt_eq(lit(ZERO_TOLERANCE)))
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise(momentum / volatility)
+                    .alias(&format!("{}_ramom_{}_{}", code, mom_window, vol_window)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(table.sorted_df()?.lazy().select(exprs), table.time_col())?)
+    }
+    
+    /// 计算波动率的波动率（vol of vol）
+    /// 
+    /// 先计算 `vol_window` 期滚动波动率，再对波动率序列计算 `vov_window` 期滚动标准差。
+    /// 两个阶段都要求完整窗口，第一阶段的预热期 null 不会混入第二阶段，
+    /// 因此前 `vol_window + vov_window - 1` 行为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `vol_window` - 波动率窗口
+    /// * `vov_window` - 波动率的波动率窗口，至少为 2
+    /// 
+    /// # Returns
+    /// 包含 `{code}_vov_{vol_window}_{vov_window}` 列的宽表
+    pub fn calculate_vov(table: &WideTable, vol_window: usize, vov_window: usize) -> Result<WideTable> {
+        if vov_window < 2 {
+            return Err(anyhow::anyhow!("vov 窗口至少为 2，当前为 {}", vov_window));
+        }
+        
+        let vol_table = table.volatility(vol_window)?;
+        let exprs: Vec<Expr> = table
+            .stock_cols()
+            .iter()
+            .map(|code| {
+                col(&format!("{}_volatility_{}", code, vol_window))
+                    .rolling_std(RollingOptionsFixedWindow {
+                        window_size: vov_window,
+                        min_periods: vov_window,
+                        center: false,
+                    })
+                    .alias(&format!("{}_vov_{}_{}", code, vol_window, vov_window))
+            })
+            .collect();
+        
+        Ok(WideTable::from_lazy(vol_table.lazy().with_columns(exprs), table.time_col())?)
+    }
+    
+    /// 计算乖离率（BIAS）
+    /// 
+    /// `(close - SMA(window)) / SMA(window) * 100`，SMA 为 0 时结果为 null
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 均线窗口
+    /// 
+    /// # Returns
+    /// 包含 `{code}_bias_{window}` 列的宽表
+    pub fn calculate_bias(table: &WideTable, window: usize) -> Result<WideTable> {
+        let sma_table = table.sma(window)?;
+        let exprs: Vec<Expr> = table
+            .stock_cols()
+            .iter()
+            .map(|code| {
+                let sma_col = format!("{}_sma_{}", code, window);
+                when(col(&sma_col).eq(lit(0.0)))
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise((col(code) - col(&sma_col)) / col(&sma_col) * lit(100.0))
+                    .alias(&format!("{}_bias_{}", code, window))
+            })
+            .collect();
+        
+        Ok(WideTable::from_lazy(sma_table.lazy().with_columns(exprs), table.time_col())?)
+    }
+    
+    /// 计算钱德动量摆动指标（CMO）
+    /// 
+    /// `100 * (sum_gains - sum_losses) / (sum_gains + sum_losses)`，其中涨幅、跌幅取自最近 `window` 个
+    /// 1 期价格变动（未平滑，与 RSI 相对）。窗口未满或窗口内价格没有变动时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 价格变动的个数
+    /// 
+    /// # Returns
+    /// 包含 `{code}_cmo_{window}` 列的宽表
+    pub fn calculate_cmo(table: &WideTable, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("CMO 窗口必须为正数"));
+        }
+        
+        let rolling_sum = |e: Expr| {
+            e.rolling_sum(RollingOptionsFixedWindow {
+                window_size: window,
+                min_periods: window,
+                center: false,
+            })
+        };
+        
+        let mut exprs = Vec::new();
+        for code in table.stock_cols() {
+            let diff = col(&code).cast(DataType::Float64) - col(&code).cast(DataType::Float64).shift(lit(1));
+            // (|d| + d) / 2 与 (|d| - d) / 2 分别为涨幅与跌幅，首行的 null 会保留下来
+            let gains = rolling_sum((diff.clone().abs() + diff.clone()) / lit(2.0));
+            let losses = rolling_sum((diff.clone().abs() - diff) / lit(2.0));
+            let total = gains.clone() + losses.clone();
+            exprs.push(
+                when(total.clone().eq(lit(0.0)))
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise((gains - losses) / total * lit(100.0))
+                    .alias(&format!("{}_cmo_{}", code, window)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(table.sorted_df()?.lazy().with_columns(exprs), table.time_col())?)
+    }
+    
+    /// 计算 TRIX 三重指数平滑动量指标
+    /// 
+    /// 对收盘价连续做三次 [`WideTable::ema`]，再取三重 EMA 的 1 期变化率（乘以 100），
+    /// 与 `ta` 库的 `TRIXIndicator` 一致。三次 EMA 都满足最少有效值后才有结果，
+    /// 即前 `3 * (span - 1) + 1` 行为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `span` - 每次 EMA 的平滑跨度
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_trix_{span}` 列的宽表
+    pub fn calculate_trix(table: &WideTable, span: usize) -> Result<WideTable> {
+        let time_col = table.time_col();
+        let codes = table.stock_cols();
+        
+        // 每次 EMA 后把 `{code}_ema_{span}` 改回股票代码，作为下一次平滑的输入
+        let mut smoothed = table.clone();
+        for _ in 0..3 {
+            let ema = smoothed.ema(span)?;
+            let mut exprs = vec![col(time_col)];
+            exprs.extend(codes.iter().map(|code| col(&format!("{}_ema_{}", code, span)).alias(code)));
+            smoothed = WideTable::new(ema.df().clone().lazy().select(exprs).collect()?, time_col)?;
+        }
+        
+        let change = smoothed.pct_change(1)?;
+        let mut exprs = vec![col(time_col)];
+        exprs.extend(
+            codes
+                .iter()
+                .map(|code| col(&format!("{}_pct_change_1", code)).alias(&format!("{}_trix_{}", code, span))),
+        );
+        
+        Ok(WideTable::new(change.df().clone().lazy().select(exprs).collect()?, time_col)?)
+    }
+    
+    /// 计算 Aroon 指标
+    /// 
+    /// `Aroon-Up = (window - 距窗口最高价的期数) / window * 100`，
+    /// `Aroon-Down = (window - 距窗口最低价的期数) / window * 100`。
+    /// 窗口包含当前行共 `window` 行，相同极值取最近一次出现的位置；
+    /// 窗口未满或窗口内有缺失值时为 null。
+    /// 
+    /// # Arguments
+    /// * `set` - high / low / close 价格集合
+    /// * `window` - 回看窗口，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列以及每只股票 `{code}_aroon_up_{window}`、`{code}_aroon_down_{window}` 两列的宽表
+    pub fn calculate_aroon(set: &PriceSet, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("Aroon 窗口必须为正数"));
+        }
+        
+        let time_col = set.close().time_col();
+        let df = set.aligned_lazy().collect()?;
+        let to_aroon = |periods: Vec<Option<usize>>| -> Vec<Option<f64>> {
+            periods
+                .into_iter()
+                .map(|p| p.map(|p| (window - p) as f64 / window as f64 * 100.0))
+                .collect()
+        };
+        
+        let mut columns = vec![df.column(time_col)?.clone()];
+        for code in set.close().stock_cols() {
+            let high: Vec<Option<f64>> = df.column(&format!("__high_{}", code))?.f64()?.into_iter().collect();
+            // 取相反数后，最低价即为最大值
+            let neg_low: Vec<Option<f64>> = df
+                .column(&format!("__low_{}", code))?
+                .f64()?
+                .into_iter()
+                .map(|v| v.map(|v| -v))
+                .collect();
+            columns.push(Series::new(
+                &format!("{}_aroon_up_{}", code, window),
+                to_aroon(stats::rolling_periods_since_max(&high, window)),
+            ));
+            columns.push(Series::new(
+                &format!("{}_aroon_down_{}", code, window),
+                to_aroon(stats::rolling_periods_since_max(&neg_low, window)),
+            ));
+        }
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, time_col)?)
+    }
+    
+    /// 计算 DMI/ADX 趋势强度指标
+    /// 
+    /// 按 Wilder 方法计算：
+    /// 1. 方向变动 `+DM`、`-DM` 与真实波幅 `TR`
+    /// 2. 用 Wilder 平滑（alpha = 1/window 的指数平滑，与 pandas-ta 的 RMA 一致）
+    ///    得到 `+DI = 100 * RMA(+DM) / RMA(TR)`、`-DI = 100 * RMA(-DM) / RMA(TR)`
+    /// 3. `DX = 100 * |+DI - -DI| / (+DI + -DI)`，`ADX = RMA(DX)`
+    /// 
+    /// 每次平滑至少需要 `window` 个有效值，之前为 null；分母为 0 时结果为 null。
+    /// 
+    /// # Arguments
+    /// * `set` - high / low / close 价格集合
+    /// * `window` - 平滑窗口
+    /// 
+    /// # Returns
+    /// 只包含时间列以及每只股票 `{code}_plus_di_{window}`、`{code}_minus_di_{window}`、
+    /// `{code}_adx_{window}` 三列的宽表，中间列不会出现在结果中
+    pub fn calculate_adx(set: &PriceSet, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("ADX 窗口必须为正数"));
+        }
+        
+        let time_col = set.close().time_col();
+        let codes = set.close().stock_cols();
+        let rma = |e: Expr| wilder_rma(e, window);
+        let null = || lit(NULL).cast(DataType::Float64);
+        
+        let mut lazy_df = set.aligned_lazy();
+        
+        // 第一步：方向变动与真实波幅
+        let mut stage = Vec::with_capacity(codes.len() * 3);
+        for code in &codes {
+            let high = col(&format!("__high_{}", code));
+            let low = col(&format!("__low_{}", code));
+            let up = high.clone() - high.clone().shift(lit(1));
+            let down = low.clone().shift(lit(1)) - low.clone();
+            
+            // 首行没有前值，up/down 为 null，结果也保持 null
+            stage.push(
+                when(up.clone().is_null())
+                    .then(null())
+                    .when(up.clone().gt(down.clone()).and(up.clone().gt(lit(0.0))))
+                    .then(up.clone())
+                    .otherwise(lit(0.0))
+                    .alias(&format!("__plus_dm_{}", code)),
+            );
+            stage.push(
+                when(down.clone().is_null())
+                    .then(null())
+                    .when(down.clone().gt(up.clone()).and(down.clone().gt(lit(0.0))))
+                    .then(down)
+                    .otherwise(lit(0.0))
+                    .alias(&format!("__minus_dm_{}", code)),
+            );
+            stage.push(true_range_expr(code).alias(&format!("__tr_{}", code)));
+        }
+        lazy_df = lazy_df.with_columns(stage);
+        
+        // 第二步：平滑得到 +DI / -DI
+        let mut stage = Vec::with_capacity(codes.len() * 2);
+        for code in &codes {
+            let atr = rma(col(&format!("__tr_{}", code)));
+            let di = |dm: Expr| {
+                when(atr.clone().eq(lit(0.0)))
+                    .then(null())
+                    .otherwise(lit(100.0) * rma(dm) / atr.clone())
+            };
+            stage.push(di(col(&format!("__plus_dm_{}", code))).alias(&format!("{}_plus_di_{}", code, window)));
+            stage.push(di(col(&format!("__minus_dm_{}", code))).alias(&format!("{}_minus_di_{}", code, window)));
+        }
+        lazy_df = lazy_df.with_columns(stage);
+        
+        // 第三步：DX 与 ADX
+        let mut stage = Vec::with_capacity(codes.len());
+        let mut output = vec![col(time_col)];
+        for code in &codes {
+            let plus_di = col(&format!("{}_plus_di_{}", code, window));
+            let minus_di = col(&format!("{}_minus_di_{}", code, window));
+            let di_sum = plus_di.clone() + minus_di.clone();
+            let dx = when(di_sum.clone().eq(lit(0.0)))
+                .then(null())
+                .otherwise(lit(100.0) * (plus_di.clone() - minus_di.clone()).abs() / di_sum);
+            let adx_col = format!("{}_adx_{}", code, window);
+            stage.push(rma(dx).alias(&adx_col));
+            output.extend([plus_di, minus_di, col(&adx_col)]);
+        }
+        lazy_df = lazy_df.with_columns(stage).select(output);
+        
+        Ok(WideTable::new(lazy_df.collect()?, time_col)?)
+    }
+    
+    /// 计算平均真实波幅（ATR）
+    /// 
+    /// 对真实波幅做 Wilder 平滑（与 pandas-ta 的 `atr(mamode="rma")` 一致），
+    /// 有效真实波幅不足 `window` 个时为 null
+    /// 
+    /// # Arguments
+    /// * `set` - high / low / close 价格集合
+    /// * `window` - 平滑窗口，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_atr_{window}` 列的宽表
+    pub fn calculate_atr(set: &PriceSet, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("ATR 窗口必须为正数"));
+        }
+        
+        let time_col = set.close().time_col();
+        let mut exprs = vec![col(time_col)];
+        exprs.extend(set.close().stock_cols().iter().map(|code| {
+            wilder_rma(true_range_expr(code), window).alias(&format!("{}_atr_{}", code, window))
+        }));
+        
+        Ok(WideTable::from_lazy(set.aligned_lazy().select(exprs), time_col)?)
+    }
+    
+    /// 计算收盘价在肯特纳通道中的位置
+    /// 
+    /// 中轨为典型价格 `(high + low + close) / 3` 的 `window` 期 EMA（见 [`WideTable::ema`]），
+    /// 上下轨为中轨 `± atr_mult * ATR(window)`（见 [`FactorCalculator::calculate_atr`]），
+    /// 位置为 `(close - lower) / (upper - lower)`：0 在下轨，1 在上轨，超出通道时小于 0 或大于 1。
+    /// EMA 或 ATR 尚未预热完成时为 null，通道宽度为 0 时同样为 null。
+    /// 
+    /// # Arguments
+    /// * `set` - high / low / close 价格集合
+    /// * `window` - EMA 与 ATR 的窗口，必须为正数
+    /// * `atr_mult` - ATR 倍数，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_keltner_pos_{window}` 列的宽表
+    pub fn calculate_keltner(set: &PriceSet, window: usize, atr_mult: f64) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("肯特纳通道窗口必须为正数"));
+        }
+        if atr_mult.is_nan() || atr_mult <= 0.0 {
+            return Err(anyhow::anyhow!("ATR 倍数必须为正数，当前为 {}", atr_mult));
+        }
+        
+        let time_col = set.close().time_col();
+        let mut exprs = vec![col(time_col)];
+        for code in set.close().stock_cols() {
+            let close = col(&format!("__close_{}", code));
+            let typical = (col(&format!("__high_{}", code)) + col(&format!("__low_{}", code)) + close.clone()) / lit(3.0);
+            let mid = ema_expr(typical, window);
+            let band = lit(atr_mult) * wilder_rma(true_range_expr(&code), window);
+            let lower = mid - band.clone();
+            let width = band * lit(2.0);
+            exprs.push(
+                when(width.clone().eq(lit(0.0)))
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise((close - lower) / width)
+                    .alias(&format!("{}_keltner_pos_{}", code, window)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(set.aligned_lazy().select(exprs), time_col)?)
+    }
+    
+    /// 计算滚动 alpha / beta
+    /// 
+    /// 在每个滚动窗口内把股票的小数收益率对基准收益率做一元回归 `r = alpha + beta * r_b`。
+    /// 窗口内任一收益率缺失或基准没有波动时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），`benchmark_col` 也在其中
+    /// * `benchmark_col` - 基准（如指数）价格列名，不会出现在输出中
+    /// * `window` - 回归窗口，至少为 3
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_alpha_{window}`、`{code}_beta_{window}` 列的宽表
+    pub fn calculate_rolling_beta(table: &WideTable, benchmark_col: &str, window: usize) -> Result<WideTable> {
+        rolling_regression_factor(
+            table,
+            benchmark_col,
+            window,
+            |x, y| rolling_ols(x, y, window),
+            &[("alpha", |f| f.alpha), ("beta", |f| f.beta)],
+        )
+    }
+    
+    /// 计算特质波动率（IVOL）
+    /// 
+    /// 与 [`FactorCalculator::calculate_rolling_beta`] 相同的滚动回归，
+    /// 取残差标准差（自由度 `n - 2`）。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），`benchmark_col` 也在其中
+    /// * `benchmark_col` - 基准（如指数）价格列名，不会出现在输出中
+    /// * `window` - 回归窗口，至少为 3
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_ivol_{window}` 列的宽表
+    pub fn calculate_ivol(table: &WideTable, benchmark_col: &str, window: usize) -> Result<WideTable> {
+        rolling_regression_factor(
+            table,
+            benchmark_col,
+            window,
+            |x, y| rolling_ols(x, y, window),
+            &[("ivol", |f| f.resid_std)],
+        )
+    }
+    
+    /// 由收益率直接计算特质波动率（IVOL）
+    /// 
+    /// 在每个滚动窗口内把股票收益率对市场收益率做带截距的一元回归 `r = alpha + beta * r_m + e`，
+    /// 取残差 `e` 的标准差（自由度 `n - 2`），即剔除系统性风险后的波动。
+    /// 与 [`FactorCalculator::calculate_ivol`] 的区别只在于输入已经是收益率，不再由价格计算；
+    /// 结果与输入收益率同单位。窗口内任一收益率缺失或市场收益率没有波动时为 null。
+    /// 
+    /// # Arguments
+    /// * `stock_returns` - 收益率宽表（应已按时间排序），所有非时间列都视为收益率列
+    /// * `market_returns` - 市场收益率，按行与 `stock_returns` 对齐，长度必须一致
+    /// * `window` - 回归窗口，至少为 3
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{stock}_ivol_{window}` 列的宽表
+    pub fn calculate_idiosyncratic_volatility(
+        stock_returns: &WideTable,
+        market_returns: &Series,
+        window: usize,
+    ) -> Result<WideTable> {
+        if window < 3 {
+            return Err(anyhow::anyhow!("回归窗口至少为 3，当前为 {}", window));
+        }
+        if market_returns.len() != stock_returns.df().height() {
+            return Err(anyhow::anyhow!(
+                "市场收益率长度 {} 与宽表行数 {} 不一致",
+                market_returns.len(),
+                stock_returns.df().height()
+            ));
+        }
+        
+        let time_col = stock_returns.time_col();
+        let market: Vec<Option<f64>> = market_returns.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+        let mut columns = vec![stock_returns.df().column(time_col)?.clone()];
+        for code in stock_returns.stock_cols() {
+            let values: Vec<Option<f64>> = stock_returns
+                .df()
+                .column(&code)?
+                .cast(&DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .collect();
+            let ivol: Vec<Option<f64>> = rolling_ols(&market, &values, window)
+                .iter()
+                .map(|fit| fit.as_ref().map(|f| f.resid_std))
+                .collect();
+            columns.push(Series::new(&format!("{}_ivol_{}", code, window), ivol));
+        }
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, time_col)?)
+    }
+    
+    /// 计算滚动下行 beta
+    ///
+    /// 等价于 `calculate_downside_beta_with(table, benchmark_col, window, 3)`
+    pub fn calculate_downside_beta(table: &WideTable, benchmark_col: &str, window: usize) -> Result<WideTable> {
+        Self::calculate_downside_beta_with(table, benchmark_col, window, 3)
+    }
+    
+    /// 计算滚动下行 beta，可指定最少下跌天数
+    ///
+    /// 在每个滚动窗口内只取基准收益率为负的交易日，把股票的小数收益率对基准收益率做一元回归，
+    /// 取斜率。窗口内任一收益率缺失、下跌天数少于 `min_down_days` 或下跌日基准没有波动时为 null。
+    ///
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），`benchmark_col` 也在其中
+    /// * `benchmark_col` - 基准（如指数）价格列名，不会出现在输出中
+    /// * `window` - 回归窗口，至少为 3
+    /// * `min_down_days` - 窗口内最少的基准下跌天数，不能超过 `window`；回归本身至少需要 3 个样本
+    ///
+    /// # Returns
+    /// 只包含时间列与 `{code}_dbeta_{window}` 列的宽表
+    pub fn calculate_downside_beta_with(
+        table: &WideTable,
+        benchmark_col: &str,
+        window: usize,
+        min_down_days: usize,
+    ) -> Result<WideTable> {
+        if min_down_days > window {
+            return Err(anyhow::anyhow!(
+                "最少下跌天数 {} 不能超过回归窗口 {}",
+                min_down_days,
+                window
+            ));
+        }
+    
+        rolling_regression_factor(
+            table,
+            benchmark_col,
+            window,
+            |x, y| rolling_ols_where(x, y, window, min_down_days, |r| r < 0.0),
+            &[("dbeta", |f| f.beta)],
+        )
+    }
+    
+    /// 计算领先-滞后相关性因子
+    /// 
+    /// 在每个滚动窗口内计算股票小数收益率 `r_t` 与滞后 `lag` 期的基准收益率 `r_b(t - lag)` 的皮尔逊相关系数。
+    /// 正值表示股票滞后于指数反应（信息扩散较慢）。`lag` 为负时比较的是之后的基准收益率（股票领先），
+    /// 此时 `t` 行的值用到了 `t` 之后的数据，不能直接作为 `t` 时刻的交易信号。
+    /// 窗口内须有 `window` 对有效收益率，否则为 null；任一序列没有波动时同样为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），`benchmark_col` 也在其中
+    /// * `benchmark_col` - 基准价格列名，不会出现在输出中
+    /// * `window` - 滚动窗口，至少为 2
+    /// * `lag` - 基准收益率的滞后期数，可为负
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_leadlag_{window}_{lag}` 列的宽表
+    pub fn calculate_leadlag(table: &WideTable, benchmark_col: &str, window: usize, lag: i32) -> Result<WideTable> {
+        if window < 2 {
+            return Err(anyhow::anyhow!("滚动窗口至少为 2，当前为 {}", window));
+        }
+        
+        let codes = non_benchmark_cols(table, benchmark_col)?;
+        let ret_table = table.pct_change_fraction(1)?;
+        let returns = |code: &str| -> Result<Vec<Option<f64>>> {
+            Ok(ret_table
+                .df()
+                .column(&format!("{}_return_1", code))?
+                .f64()?
+                .into_iter()
+                .map(|v| v.filter(|x| !x.is_nan()))
+                .collect())
+        };
+        let bench = returns(benchmark_col)?;
+        let height = bench.len();
+        // 第 i 行对应的滞后基准收益率，超出范围为 None
+        let lagged: Vec<Option<f64>> = (0..height as i64)
+            .map(|i| {
+                let j = i - lag as i64;
+                if (0..height as i64).contains(&j) {
+                    bench[j as usize]
+                } else {
+                    None
+                }
+            })
+            .collect();
+        
+        let mut columns = vec![ret_table.df().column(table.time_col())?.clone()];
+        let (mut xs, mut ys) = (Vec::with_capacity(window), Vec::with_capacity(window));
+        for code in &codes {
+            let stock = returns(code)?;
+            let values: Vec<Option<f64>> = (0..height)
+                .map(|i| {
+                    if i + 1 < window {
+                        return None;
+                    }
+                    xs.clear();
+                    ys.clear();
+                    for j in i + 1 - window..=i {
+                        xs.push(stock[j]?);
+                        ys.push(lagged[j]?);
+                    }
+                    stats::pearson(&xs, &ys)
+                })
+                .collect();
+            columns.push(Series::new(&format!("{}_leadlag_{}_{}", code, window, lag), values));
+        }
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, table.time_col())?)
+    }
+    
+    /// 计算异常成交量因子（成交量 z-score）
+    /// 
+    /// `(volume - rolling_mean(volume, window)) / rolling_std(volume, window)`，窗口包含当期。
+    /// 整数成交量列会先转换为 f64；窗口未满或标准差为 0 时为 null。
+    /// 
+    /// # Arguments
+    /// * `volume` - 成交量宽表
+    /// * `window` - 滚动窗口，至少为 2
+    /// 
+    /// # Returns
+    /// 包含 `{code}_avol_{window}` 列的宽表
+    pub fn calculate_abnormal_volume(volume: &WideTable, window: usize) -> Result<WideTable> {
+        if window < 2 {
+            return Err(anyhow::anyhow!("滚动窗口至少为 2，当前为 {}", window));
+        }
+        
+        let options = RollingOptionsFixedWindow {
+            window_size: window,
+            min_periods: window,
+            center: false,
+        };
+        
+        let mut exprs = Vec::new();
+        for code in volume.stock_cols() {
+            let v = col(&code).cast(DataType::Float64);
+            let mean = v.clone().rolling_mean(options.clone());
+            let std = v.clone().rolling_std(options.clone());
+            exprs.push(
+                when(std.clone().lt_eq(lit(ZERO_TOLERANCE)))
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise((v - mean) / std)
+                    .alias(&format!("{}_avol_{}", code, window)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(volume.sorted_df()?.lazy().with_columns(exprs), volume.time_col())?)
+    }
+    
+    /// 计算换手率因子
+    /// 
+    /// 日换手率为 `volume / float_shares`（小数形式），再取 `window` 期滚动均值。
+    /// 流通股本变动不频繁，先用 [`WideTable::align_ffill`] 按日期前向填充到成交量的时间轴；
+    /// 股本为 0 或缺失时当日换手率为 null，窗口内有 null 时结果为 null。
+    /// 
+    /// # Arguments
+    /// * `volume` - 成交量宽表
+    /// * `float_shares` - 流通股本宽表，须包含 `volume` 的全部股票列，行数可以少于 `volume`
+    /// * `window` - 滚动窗口，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_turn_{window}` 列的宽表
+    pub fn calculate_turnover(volume: &WideTable, float_shares: &WideTable, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("换手率窗口必须为正数"));
+        }
+        
+        let codes = volume.stock_cols();
+        let share_cols = float_shares.stock_cols();
+        if let Some(missing) = codes.iter().find(|code| !share_cols.contains(code)) {
+            return Err(anyhow::anyhow!("流通股本宽表中缺少股票列 '{}'", missing));
+        }
+        
+        let time_col = volume.time_col();
+        let shares = volume.align_ffill(float_shares)?;
+        let mut share_exprs = vec![col(time_col)];
+        share_exprs.extend(codes.iter().map(|code| col(code).alias(&format!("__shares_{}", code))));
+        
+        let mut exprs = vec![col(time_col)];
+        for code in &codes {
+            let shares = col(&format!("__shares_{}", code)).cast(DataType::Float64);
+            let daily = when(shares.clone().eq(lit(0.0)))
+                .then(lit(NULL).cast(DataType::Float64))
+                .otherwise(col(code).cast(DataType::Float64) / shares);
+            exprs.push(
+                daily
+                    .rolling_mean(RollingOptionsFixedWindow {
+                        window_size: window,
+                        min_periods: window,
+                        center: false,
+                    })
+                    .alias(&format!("{}_turn_{}", code, window)),
+            );
+        }
+        
+        let lazy_df = volume
+            .sorted_df()?
+            .lazy()
+            .join(
+                shares.df().clone().lazy().select(share_exprs),
+                [col(time_col)],
+                [col(time_col)],
+                JoinArgs::new(polars::prelude::JoinType::Left),
+            )
+            .select(exprs);
+        
+        Ok(WideTable::from_lazy(lazy_df, time_col)?)
+    }
+    
+    /// 计算 Amihud（2002）非流动性因子
+    /// 
+    /// 日非流动性为 `|return| / volume`，再取 `window` 期滚动均值；成交量为 0 或缺失时当日为 null，
+    /// 窗口内有 null 时结果为 null。该比率严重右偏，实践中常乘以 1e6 或取对数后再使用。
+    /// 
+    /// # Arguments
+    /// * `return_table` - 收益率宽表（小数形式）
+    /// * `volume_table` - 成交量宽表，时间须与 `return_table` 完全一致，且包含其全部股票列
+    /// * `window` - 滚动窗口，必须为正数
+    /// * `log_transform` - 为 true 时输出 `ln(1 + amihud)`，压缩右偏且保留 0 值
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_amihud_{window}` 列的宽表
+    /// 
+    /// # Errors
+    /// 两表的时间不一致或成交量宽表缺少股票列时返回错误
+    pub fn calculate_amihud(
+        return_table: &WideTable,
+        volume_table: &WideTable,
+        window: usize,
+        log_transform: bool,
+    ) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("Amihud 窗口必须为正数"));
+        }
+        
+        let codes = return_table.stock_cols();
+        let volume_cols = volume_table.stock_cols();
+        if let Some(missing) = codes.iter().find(|code| !volume_cols.contains(code)) {
+            return Err(anyhow::anyhow!("成交量宽表中缺少股票列 '{}'", missing));
+        }
+        
+        let time_col = return_table.time_col();
+        let returns = return_table.sorted_df()?;
+        let volumes = volume_table.sorted_df()?;
+        let (return_times, volume_times) = (returns.column(time_col)?, volumes.column(volume_table.time_col())?);
+        if !return_times.equals(volume_times) {
+            return Err(anyhow::anyhow!(
+                "收益率与成交量宽表的时间不一致（{} 行与 {} 行），请先对齐",
+                return_times.len(),
+                volume_times.len()
+            ));
+        }
+        
+        let mut exprs = vec![col(time_col)];
+        let mut volume_series = Vec::with_capacity(codes.len());
+        for code in &codes {
+            let volume_col = format!("__volume_{}", code);
+            volume_series.push(volumes.column(code)?.clone().with_name(&volume_col));
+            
+            let volume = col(&volume_col).cast(DataType::Float64);
+            let daily = when(volume.clone().eq(lit(0.0)))
+                .then(lit(NULL).cast(DataType::Float64))
+                .otherwise(col(code).cast(DataType::Float64).abs() / volume);
+            let amihud = daily.rolling_mean(RollingOptionsFixedWindow {
+                window_size: window,
+                min_periods: window,
+                center: false,
+            });
+            exprs.push(amihud.alias(&format!("{}_amihud_{}", code, window)));
+        }
+        
+        let mut df = returns.hstack(&volume_series)?.lazy().select(exprs).collect()?;
+        if log_transform {
+            for code in &codes {
+                let name = format!("{}_amihud_{}", code, window);
+                let logged = df.column(&name)?.f64()?.apply_values(f64::ln_1p).into_series();
+                df.with_column(logged)?;
+            }
+        }
+        
+        Ok(WideTable::new(df, time_col)?)
+    }
+    
+    /// 计算累计收益（净值）序列
+    /// 
+    /// `starting_value * cumprod(1 + r / scale.factor())`，`scale` 为输入收益率的单位：
+    /// [`WideTable::pct_change`] 的输出用 [`ReturnScale::Percent`]，
+    /// [`WideTable::pct_change_fraction`] 的输出用 [`ReturnScale::Fraction`]。
+    /// 
+    /// 所有非时间列都会被当作收益率累乘。[`WideTable::pct_change`] 的结果仍保留价格列，
+    /// 须先只选出收益率列（如 `select` 时间列与 `{code}_pct_change_1` 列）再传入。
+    /// 
+    /// # Arguments
+    /// * `return_table` - 只包含时间列与收益率列的宽表
+    /// * `starting_value` - 初始净值
+    /// * `scale` - 输入收益率的单位
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{return_col}_cumret` 列的宽表，按时间排序。
+    /// 收益率为 null 或 NaN 的一期按收益为 0 处理，净值沿用上一期（首期收益率通常为 null，净值即 `starting_value`）；
+    /// 因此停牌或缺失数据不会中断净值曲线，但也不会被标记出来，需要区分时应先检查输入的缺失值。
+    pub fn calculate_cumulative_returns(
+        return_table: &WideTable,
+        starting_value: f64,
+        scale: ReturnScale,
+    ) -> Result<WideTable> {
+        let exprs: Vec<Expr> = return_table
+            .stock_cols()
+            .iter()
+            .map(|name| {
+                let growth = lit(1.0)
+                    + col(name)
+                        .cast(DataType::Float64)
+                        .fill_nan(lit(0.0))
+                        .fill_null(lit(0.0))
+                        / lit(scale.factor());
+                (growth.cum_prod(false) * lit(starting_value)).alias(&format!("{}_cumret", name))
+            })
+            .collect();
+        let time_col = return_table.time_col();
+        let mut selected = vec![col(time_col)];
+        selected.extend(exprs);
+        
+        Ok(WideTable::from_lazy(return_table.sorted_df()?.lazy().select(selected), time_col)?)
+    }
+    
+    /// 计算每只股票的几何年化收益率
+    /// 
+    /// `(prod(1 + r))^(periods_per_year / n) - 1`，其中 `n` 为非缺失收益率的个数，
+    /// null 与 NaN 不计入。收益率须为小数形式（与 [`WideTable::pct_change_fraction`] 一致）。
+    /// 累计净值 `prod(1 + r) <= 0`（本金亏光，或单期亏损超过 100%）时几何年化没有意义，结果为 null。
+    /// 
+    /// # Arguments
+    /// * `returns` - 收益率宽表，所有非时间列都视为收益率列
+    /// * `periods_per_year` - 每年的期数，例如日频为 252
+    /// 
+    /// # Returns
+    /// 每只股票一行的 DataFrame，列为 `stock, annualized_return`，没有有效收益率或累计净值不为正时为 null
+    pub fn annualized_return(returns: &WideTable, periods_per_year: f64) -> Result<DataFrame> {
+        if periods_per_year <= 0.0 {
+            return Err(anyhow::anyhow!("每年期数必须为正数，当前为 {}", periods_per_year));
+        }
+        
+        per_stock_stat(returns, "annualized_return", |valid| {
+            let growth: f64 = valid.iter().map(|r| 1.0 + r).product();
+            (growth > 0.0).then(|| growth.powf(periods_per_year / valid.len() as f64) - 1.0)
+        })
+    }
+    
+    /// 计算每只股票的在险价值（VaR）
+    /// 
+    /// 置信水平为 `confidence` 时，损失不超过 VaR 的概率为 `confidence`，
+    /// 即 VaR 为收益率 `1 - confidence` 分位数的相反数（损失为正数）。单位与输入收益率一致。
+    /// null 与 NaN 不计入；有效样本不足时为 null（参数法至少 2 个，Cornish-Fisher 至少 4 个）。
+    /// 
+    /// polars 的 Series 没有索引标签，结果的第 `i` 个元素对应 `return_table` 的第 `i` 个股票列
+    /// （即 [`WideTable::iter_stocks`] 的顺序），与 [`FactorCalculator::calculate_information_ratio`] 一致。
+    /// 
+    /// # Arguments
+    /// * `return_table` - 收益率宽表，所有非时间列都视为收益率列
+    /// * `confidence` - 置信水平，取值范围 `(0, 1)`，例如 0.95、0.99
+    /// * `method` - 计算方法
+    /// 
+    /// # Returns
+    /// 名为 `var` 的 Series，每只股票一个值
+    pub fn calculate_var(return_table: &WideTable, confidence: f64, method: VarMethod) -> Result<Series> {
+        check_confidence(confidence)?;
+        per_stock_series(return_table, "var", |valid| value_at_risk(valid, confidence, method))
+    }
+    
+    /// 计算每只股票的条件在险价值（CVaR / Expected Shortfall）
+    /// 
+    /// 收益率不高于 VaR 阈值（`-VaR`）的样本均值的相反数，VaR 按 `method` 计算。
+    /// 没有样本落在阈值以下时为 null。结果顺序与 [`FactorCalculator::calculate_var`] 相同。
+    /// 
+    /// # Arguments
+    /// * `return_table` - 收益率宽表，所有非时间列都视为收益率列
+    /// * `confidence` - 置信水平，取值范围 `(0, 1)`
+    /// * `method` - VaR 的计算方法
+    /// 
+    /// # Returns
+    /// 名为 `cvar` 的 Series，每只股票一个值
+    pub fn calculate_cvar(return_table: &WideTable, confidence: f64, method: VarMethod) -> Result<Series> {
+        check_confidence(confidence)?;
+        per_stock_series(return_table, "cvar", |valid| {
+            let threshold = -value_at_risk(valid, confidence, method)?;
+            let tail: Vec<f64> = valid.iter().copied().filter(|r| *r <= threshold).collect();
+            if tail.is_empty() {
+                None
+            } else {
+                Some(-tail.iter().sum::<f64>() / tail.len() as f64)
+            }
+        })
+    }
+    
+    /// 计算每只股票相对基准的信息比率
+    /// 
+    /// `mean(active) / std(active) * sqrt(annualization_factor)`，其中
+    /// `active = portfolio_return - benchmark_return`。任一侧为 null 或 NaN 的行成对剔除；
+    /// 有效样本少于 2 个或跟踪误差为 0（不超过 `ZERO_TOLERANCE`）时为 null。
+    /// 
+    /// polars 的 Series 没有索引标签，结果的第 `i` 个元素对应 `portfolio_returns` 的第 `i` 个股票列
+    /// （即 [`WideTable::iter_stocks`] 的顺序）。
+    /// 
+    /// # Arguments
+    /// * `portfolio_returns` - 收益率宽表，所有非时间列都视为收益率列
+    /// * `benchmark_returns` - 基准收益率，按行与 `portfolio_returns` 对齐，长度必须一致
+    /// * `annualization_factor` - 年化系数，例如日频为 252
+    /// 
+    /// # Returns
+    /// 名为 `information_ratio` 的 Series，每只股票一个值
+    /// 
+    /// # Errors
+    /// `benchmark_returns` 的长度与宽表行数不一致时返回错误
+    pub fn calculate_information_ratio(
+        portfolio_returns: &WideTable,
+        benchmark_returns: &Series,
+        annualization_factor: f64,
+    ) -> Result<Series> {
+        active_return_stat(portfolio_returns, benchmark_returns, "information_ratio", |mean, std| {
+            if std <= ZERO_TOLERANCE {
+                None
+            } else {
+                Some(mean / std * annualization_factor.sqrt())
+            }
+        })
+    }
+    
+    /// 计算每只股票相对基准的年化跟踪误差
+    /// 
+    /// `std(active) * sqrt(annualization_factor)`，即信息比率的分母，
+    /// 缺失值处理与结果顺序与 [`FactorCalculator::calculate_information_ratio`] 相同。
+    /// 
+    /// # Returns
+    /// 名为 `tracking_error` 的 Series，每只股票一个值
+    pub fn calculate_tracking_error(
+        portfolio_returns: &WideTable,
+        benchmark_returns: &Series,
+        annualization_factor: f64,
+    ) -> Result<Series> {
+        active_return_stat(portfolio_returns, benchmark_returns, "tracking_error", |_, std| {
+            Some(std * annualization_factor.sqrt())
+        })
+    }
+    
+    /// 计算滚动信息比率
+    /// 
+    /// 超额收益 `r - r_benchmark`（小数收益率）在 `window` 内的均值除以标准差，
+    /// 再乘以 `sqrt(TRADING_DAYS_PER_YEAR)` 年化。窗口未满或跟踪误差为 0 时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），`benchmark_col` 也在其中
+    /// * `benchmark_col` - 基准价格列名，不会出现在输出中
+    /// * `window` - 滚动窗口，至少为 2
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_ir_{window}` 列的宽表
+    pub fn calculate_info_ratio(table: &WideTable, benchmark_col: &str, window: usize) -> Result<WideTable> {
+        if window < 2 {
+            return Err(anyhow::anyhow!("滚动窗口至少为 2，当前为 {}", window));
+        }
+        
+        let codes = non_benchmark_cols(table, benchmark_col)?;
+        let ret_table = table.pct_change_fraction(1)?;
+        let bench = col(&format!("{}_return_1", benchmark_col));
+        let options = RollingOptionsFixedWindow {
+            window_size: window,
+            min_periods: window,
+            center: false,
+        };
+        
+        let mut exprs = vec![col(table.time_col())];
+        for code in &codes {
+            let excess = col(&format!("{}_return_1", code)) - bench.clone();
+            let mean = excess.clone().rolling_mean(options.clone());
+            let std = excess.rolling_std(options.clone());
+            exprs.push(
+                when(std.clone().lt_eq(lit(ZERO_TOLERANCE)))
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise(mean / std * lit(TRADING_DAYS_PER_YEAR.sqrt()))
+                    .alias(&format!("{}_ir_{}", code, window)),
+            );
+        }
+        
+        Ok(WideTable::from_lazy(ret_table.lazy().select(exprs), table.time_col())?)
+    }
+    
+    /// 计算滚动 Calmar 比率
+    /// 
+    /// 窗口内的年化收益率除以窗口内最大回撤的绝对值。年化收益率为
+    /// `(P_end / P_start)^(TRADING_DAYS_PER_YEAR / (n - 1)) - 1`，`n` 为窗口内有效价格数。
+    /// 窗口内有效价格不足 `window` 个或没有回撤时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 滚动窗口，至少为 2
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_calmar_{window}` 列的宽表
+    pub fn calculate_calmar(table: &WideTable, window: usize) -> Result<WideTable> {
+        rolling_price_stat(table, window, "calmar", |prices| {
+            let max_drawdown = stats::drawdowns(prices).into_iter().fold(0.0, f64::min).abs();
+            if max_drawdown == 0.0 {
+                return None;
+            }
+            let periods = (prices.len() - 1) as f64;
+            let total = prices[prices.len() - 1] / prices[0];
+            Some((total.powf(TRADING_DAYS_PER_YEAR / periods) - 1.0) / max_drawdown)
+        })
+    }
+    
+    /// 计算滚动溃疡指数（Ulcer Index）
+    /// 
+    /// 窗口内相对滚动最高点的百分比回撤（乘以 100）的均方根。
+    /// 窗口内有效价格不足 `window` 个时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 滚动窗口，至少为 2
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_ulcer_{window}` 列的宽表
+    pub fn calculate_ulcer(table: &WideTable, window: usize) -> Result<WideTable> {
+        rolling_price_stat(table, window, "ulcer", |prices| {
+            let drawdowns = stats::drawdowns(prices);
+            let mean_sq = drawdowns.iter().map(|d| (d * 100.0).powi(2)).sum::<f64>() / drawdowns.len() as f64;
+            Some(mean_sq.sqrt())
+        })
+    }
+    
+    /// 计算滚动收益率熵
+    /// 
+    /// 对窗口内 `window` 个单期小数收益率做等宽直方图（区间边界取窗口内收益率的最小、最大值），
+    /// 结果为直方图的香农熵（自然对数），取值在 `[0, ln(bins)]` 内。
+    /// 熵低说明收益率集中在少数几档，可用于识别状态切换或被操纵的价格路径。
+    /// 窗口内有效收益率不足 `window` 个或收益率没有波动时为 null。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 滚动窗口（收益率个数），至少为 2
+    /// * `bins` - 直方图区间数，至少为 2
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_entropy_{window}_{bins}` 列的宽表
+    pub fn calculate_return_entropy(table: &WideTable, window: usize, bins: usize) -> Result<WideTable> {
+        if window < 2 {
+            return Err(anyhow::anyhow!("滚动窗口至少为 2，当前为 {}", window));
+        }
+        if bins < 2 {
+            return Err(anyhow::anyhow!("直方图区间数至少为 2，当前为 {}", bins));
+        }
+        
+        let df = table.sorted_df()?;
+        let mut columns = vec![df.column(table.time_col())?.clone()];
+        for code in table.stock_cols() {
+            let prices: Vec<Option<f64>> = df.column(&code)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let returns: Vec<Option<f64>> = (0..prices.len())
+                .map(|i| match (i.checked_sub(1).and_then(|j| prices[j]), prices[i]) {
+                    (Some(prev), Some(curr)) => Some(curr / prev - 1.0),
+                    _ => None,
+                })
+                .collect();
+            let values = stats::rolling_window_apply(&returns, window, window, |w| stats::histogram_entropy(w, bins));
+            columns.push(Series::new(&format!("{}_entropy_{}_{}", code, window, bins), values));
+        }
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, table.time_col())?)
+    }
+    
+    /// 计算回撤持续期（水下时间）
+    /// 
+    /// 当前行距滚动窗口内最高价出现位置的期数，当前价格即为窗口新高时为 0。
+    /// 与 [`FactorCalculator::calculate_aroon`] 共用滚动最大值位置的计算，相同最高价取最近一次出现的位置；
+    /// 窗口未满或窗口内有缺失值时为 null。结果为整数值的 f64。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格）
+    /// * `window` - 回看窗口，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_dd_dur_{window}` 列的宽表
+    pub fn calculate_drawdown_duration(table: &WideTable, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("回撤持续期窗口必须为正数"));
+        }
+        
+        let df = table.sorted_df()?;
+        let mut columns = vec![df.column(table.time_col())?.clone()];
+        for code in table.stock_cols() {
+            let prices: Vec<Option<f64>> = df.column(&code)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let durations: Vec<Option<f64>> = stats::rolling_periods_since_max(&prices, window)
+                .into_iter()
+                .map(|p| p.map(|p| p as f64))
+                .collect();
+            columns.push(Series::new(&format!("{}_dd_dur_{}", code, window), durations));
+        }
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, table.time_col())?)
+    }
+    
+    /// 计算价格相对滚动最高价的比值（George–Hwang 52 周新高因子）
+    /// 
+    /// `close / max(close, window)`，窗口包含当前行。取值在 `(0, 1]` 内，创窗口新高时恰为 1。
+    /// 窗口内的缺失价格（null 或 NaN）直接跳过，不会被当作 0 参与取最大值；
+    /// 开头不足 `window` 行或当前价格缺失时为 null。日频数据上 `window = 252` 即 52 周新高因子。
+    /// 
+    /// # Arguments
+    /// * `table` - 宽表数据（包含 close 价格），价格须为正
+    /// * `window` - 回看窗口，必须为正数
+    /// 
+    /// # Returns
+    /// 只包含时间列与 `{code}_pth_{window}` 列的宽表
+    pub fn calculate_price_to_high(table: &WideTable, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(anyhow::anyhow!("滚动窗口必须为正数"));
+        }
+        
+        let df = table.sorted_df()?;
+        let mut columns = vec![df.column(table.time_col())?.clone()];
+        for code in table.stock_cols() {
+            let prices: Vec<Option<f64>> = df.column(&code)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let highs = stats::rolling_window_apply(&prices, window, 1, |w| w.iter().copied().reduce(f64::max));
+            let ratios: Vec<Option<f64>> = prices
+                .iter()
+                .zip(&highs)
+                .enumerate()
+                .map(|(i, (price, high))| match (price, high) {
+                    (Some(p), Some(h)) if i + 1 >= window && !p.is_nan() && *h > 0.0 => Some(p / h),
+                    _ => None,
+                })
+                .collect();
+            columns.push(Series::new(&format!("{}_pth_{}", code, window), ratios));
+        }
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, table.time_col())?)
+    }
+    
+    /// 计算因子多空组合收益
+    /// 
+    /// 每期按因子值排序，等权做多最高的 `quantile` 比例股票、做空最低的 `quantile` 比例股票，
+    /// 组合收益为两条腿下一期收益的均值之差。每条腿股票数为 `floor(有效股票数 * quantile)`，
+    /// 因子或未来收益缺失的股票不参与；某期凑不齐两条腿时该期为 null。
+    /// 
+    /// # Arguments
+    /// * `factor` - 因子宽表
+    /// * `forward_returns` - 未来收益率宽表，股票列名须与 `factor` 一致（按时间列对齐）
+    /// * `quantile` - 每条腿的比例，取值范围 `(0, 0.5]`
+    /// 
+    /// # Returns
+    /// 按时间排序的 DataFrame，列为时间列与 `long, short, long_short`
+    pub fn long_short_returns(factor: &WideTable, forward_returns: &WideTable, quantile: f64) -> Result<DataFrame> {
+        if !(quantile > 0.0 && quantile <= 0.5) {
+            return Err(anyhow::anyhow!("quantile 必须在 (0, 0.5] 内，当前为 {}", quantile));
+        }
+        
+        let time_col = factor.time_col();
+        let codes = factor.stock_cols();
+        let (joined, factor_values, fwd_values) = join_by_time(factor, forward_returns, &codes)?;
+        
+        let height = joined.height();
+        let mut long = Vec::with_capacity(height);
+        let mut short = Vec::with_capacity(height);
+        let mut spread = Vec::with_capacity(height);
+        let mut row: Vec<(f64, f64)> = Vec::with_capacity(codes.len());
+        
+        for i in 0..height {
+            row.clear();
+            for (f, r) in factor_values.iter().zip(&fwd_values) {
+                if let (Some(f), Some(r)) = (f[i], r[i]) {
+                    if !f.is_nan() && !r.is_nan() {
+                        row.push((f, r));
+                    }
+                }
+            }
+            
+            let k = (row.len() as f64 * quantile).floor() as usize;
+            if k == 0 {
+                long.push(None);
+                short.push(None);
+                spread.push(None);
+                continue;
+            }
+            
+            row.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let leg_mean = |leg: &[(f64, f64)]| leg.iter().map(|(_, r)| r).sum::<f64>() / leg.len() as f64;
+            let short_ret = leg_mean(&row[..k]);
+            let long_ret = leg_mean(&row[row.len() - k..]);
+            long.push(Some(long_ret));
+            short.push(Some(short_ret));
+            spread.push(Some(long_ret - short_ret));
+        }
+        
+        Ok(DataFrame::new(vec![
+            joined.column(time_col)?.clone(),
+            Series::new("long", long),
+            Series::new("short", short),
+            Series::new("long_short", spread),
+        ])?)
+    }
+    
+    /// 计算因子排名的换手率
+    /// 
+    /// 每期对因子做截面百分位排名（名次除以有效股票数，相同值取平均名次），
+    /// 换手率为相邻两期都有排名的股票的百分位排名变化绝对值的均值。
+    /// 排名完全不变时为 0，数值越大说明按该因子调仓的交易成本越高。
+    /// 第一期没有上一期排名，与两期没有共同股票的时间一样为 null。
+    /// 
+    /// # Arguments
+    /// * `factor` - 因子宽表
+    /// 
+    /// # Returns
+    /// 按时间排序的 DataFrame，列为时间列与 `turnover`
+    pub fn rank_turnover(factor: &WideTable) -> Result<DataFrame> {
+        let ranks = factor.rank_cross_section(RankMethod::Average, true)?;
+        let df = ranks.sorted_df()?;
+        let columns = ranks
+            .stock_cols()
+            .iter()
+            .map(|code| Ok(df.column(code)?.f64()?.into_iter().collect::<Vec<_>>()))
+            .collect::<Result<Vec<_>>>()?;
+        
+        let turnover: Vec<Option<f64>> = (0..df.height())
+            .map(|i| {
+                let prev = i.checked_sub(1)?;
+                let changes: Vec<f64> = columns
+                    .iter()
+                    .filter_map(|ranks| Some((ranks[i]? - ranks[prev]?).abs()))
+                    .collect();
+                if changes.is_empty() {
+                    None
+                } else {
+                    Some(changes.iter().sum::<f64>() / changes.len() as f64)
+                }
+            })
+            .collect();
+        
+        Ok(DataFrame::new(vec![
+            df.column(factor.time_col())?.clone(),
+            Series::new("turnover", turnover),
+        ])?)
+    }
+    
+    /// 回归中性化：剔除因子中可由风格暴露解释的部分
+    /// 
+    /// 每个时间截面上，以股票为样本把因子值对各暴露（如对数市值、beta）做带截距的最小二乘回归，
+    /// 残差即中性化后的因子。因子或任一暴露缺失（null、NaN，或暴露表没有该时间）的股票
+    /// 不参与当期回归，结果为 null。有效股票数不超过回归参数个数或设计矩阵奇异
+    /// （如暴露在截面上没有差异）时，该期退化为简单去均值，并通过 `log` 记录一条 warn 级别的日志。
+    /// 
+    /// # Arguments
+    /// * `factor` - 因子宽表
+    /// * `exposures` - 暴露宽表，每张表须包含 `factor` 的全部股票列（按时间列对齐）
+    /// 
+    /// # Returns
+    /// 按时间排序、列名与 `factor` 相同的宽表
+    pub fn neutralize(factor: &WideTable, exposures: &[&WideTable]) -> Result<WideTable> {
+        let time_col = factor.time_col();
+        let codes = factor.stock_cols();
+        let labels: Vec<String> = (1..=exposures.len()).map(|k| format!("第 {} 张暴露宽表", k)).collect();
+        let (df, factor_values, exposure_values) = join_panels(factor, exposures, &labels, &codes)?;
+        
+        let valid = |v: Option<f64>| v.filter(|x| !x.is_nan());
+        let mut outputs: AlignedColumns = vec![vec![None; df.height()]; codes.len()];
+        let times = df.column(time_col)?;
+        for i in 0..df.height() {
+            // 当期因子与全部暴露都有效的股票
+            let members: Vec<usize> = (0..codes.len())
+                .filter(|&j| valid(factor_values[j][i]).is_some() && exposure_values.iter().all(|e| valid(e[j][i]).is_some()))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            
+            let y: Vec<f64> = members.iter().map(|&j| factor_values[j][i].unwrap()).collect();
+            let xs: Vec<Vec<f64>> = exposure_values
+                .iter()
+                .map(|e| members.iter().map(|&j| e[j][i].unwrap()).collect())
+                .collect();
+            let residuals = ols_residuals(&xs, &y).unwrap_or_else(|| {
+                let time = times.get(i).map(|t| t.to_string()).unwrap_or_default();
+                log::warn!("{} 的截面回归无法求解（有效股票 {} 只），退化为去均值", time, y.len());
+                let mean = y.iter().sum::<f64>() / y.len() as f64;
+                y.iter().map(|v| v - mean).collect()
+            });
+            for (&j, r) in members.iter().zip(residuals) {
+                outputs[j][i] = Some(r);
+            }
+        }
+        
+        let mut columns = vec![times.clone()];
+        columns.extend(codes.iter().zip(outputs).map(|(code, values)| Series::new(code, values)));
+        Ok(WideTable::new(DataFrame::new(columns)?, time_col)?)
+    }
+    
+    /// 逐期对多个因子做 Gram–Schmidt 正交化
+    /// 
+    /// 第一个因子原样保留；之后的每个因子在每个时间截面上对此前已正交化的因子做带截距的最小二乘回归，
+    /// 残差即正交化后的因子，使叠加动量、反转、波动率等因子时不重复计入同一暴露。
+    /// 某期在截面上全部缺失的因子，该期原样保留（全为 null），也不作为后续因子的解释变量。
+    /// 因子或任一解释变量缺失（null 或 NaN）的股票不参与当期回归，结果为 null；
+    /// 有效股票数不超过回归参数个数或设计矩阵奇异时，与 [`FactorCalculator::neutralize`] 一样
+    /// 退化为简单去均值，并通过 `log` 记录一条 warn 级别的日志。
+    /// 
+    /// # Arguments
+    /// * `factors` - 按正交化顺序排列的因子宽表，之后的每张表须包含第一张表的全部股票列（按时间列对齐）
+    /// 
+    /// # Returns
+    /// 与 `factors` 一一对应的宽表，时间轴为第一张表按时间排序后的时间，列名与第一张表相同
+    pub fn orthogonalize(factors: &[&WideTable]) -> Result<Vec<WideTable>> {
+        let Some((first, rest)) = factors.split_first() else {
+            return Ok(Vec::new());
+        };
+        let time_col = first.time_col();
+        let codes = first.stock_cols();
+        let labels: Vec<String> = (2..=factors.len()).map(|k| format!("第 {} 张因子宽表", k)).collect();
+        let (df, first_values, rest_values) = join_panels(first, rest, &labels, &codes)?;
+        
+        let valid = |v: Option<f64>| v.filter(|x| !x.is_nan());
+        let mut outputs: Vec<AlignedColumns> = vec![first_values];
+        let times = df.column(time_col)?;
+        for values in rest_values {
+            let mut orthogonal: AlignedColumns = vec![vec![None; df.height()]; codes.len()];
+            for i in 0..df.height() {
+                // 当期有数据的此前因子作为解释变量
+                let regressors: Vec<&AlignedColumns> = outputs
+                    .iter()
+                    .filter(|prev| prev.iter().any(|v| valid(v[i]).is_some()))
+                    .collect();
+                let members: Vec<usize> = (0..codes.len())
+                    .filter(|&j| valid(values[j][i]).is_some() && regressors.iter().all(|r| valid(r[j][i]).is_some()))
+                    .collect();
+                if regressors.is_empty() || members.is_empty() {
+                    for (out, v) in orthogonal.iter_mut().zip(&values) {
+                        out[i] = v[i];
+                    }
+                    continue;
+                }
+                
+                let y: Vec<f64> = members.iter().map(|&j| values[j][i].unwrap()).collect();
+                let xs: Vec<Vec<f64>> = regressors
+                    .iter()
+                    .map(|r| members.iter().map(|&j| r[j][i].unwrap()).collect())
+                    .collect();
+                let residuals = ols_residuals(&xs, &y).unwrap_or_else(|| {
+                    let time = times.get(i).map(|t| t.to_string()).unwrap_or_default();
+                    log::warn!("{} 的正交化回归无法求解（有效股票 {} 只），退化为去均值", time, y.len());
+                    let mean = y.iter().sum::<f64>() / y.len() as f64;
+                    y.iter().map(|v| v - mean).collect()
+                });
+                for (&j, r) in members.iter().zip(residuals) {
+                    orthogonal[j][i] = Some(r);
+                }
+            }
+            outputs.push(orthogonal);
+        }
+        
+        outputs
+            .into_iter()
+            .map(|values| {
+                let mut columns = vec![times.clone()];
+                columns.extend(codes.iter().zip(values).map(|(code, v)| Series::new(code, v)));
+                Ok(WideTable::new(DataFrame::new(columns)?, time_col)?)
+            })
+            .collect()
+    }
+    
+    /// 截面回归：估计每期的因子收益率
+    /// 
+    /// 每个时间截面上，以股票为样本把收益率对各因子值做带截距的最小二乘回归，
+    /// 回归系数即当期的因子收益率。收益率或任一因子缺失（null、NaN，或因子表没有该时间）的股票
+    /// 不参与当期回归；有效股票数不超过回归参数个数的时间，结果为 null。
+    /// 因子值与收益率按同一时间对齐，如需用 `t-1` 期因子解释 `t` 期收益，应先将因子表滞后一期。
+    /// 
+    /// # Arguments
+    /// * `returns` - 收益率宽表
+    /// * `factors` - `(因子名, 因子宽表)` 列表，每张表须包含 `returns` 的全部股票列（按时间列对齐）
+    /// 
+    /// # Returns
+    /// 按时间排序的 DataFrame，列为时间列、各因子名（因子收益率）和 `r2`（当期回归的 R²，收益率没有截面差异时为 null）
+    /// 
+    /// # Errors
+    /// 因子列表为空、因子名重复或与输出列冲突、因子表缺少股票列，
+    /// 或某期设计矩阵秩亏（因子之间完全共线，或某个因子在截面上没有差异）时返回错误
+    pub fn cross_sectional_regression(returns: &WideTable, factors: &[(&str, &WideTable)]) -> Result<DataFrame> {
+        if factors.is_empty() {
+            return Err(anyhow::anyhow!("截面回归至少需要一个因子"));
+        }
+        let time_col = returns.time_col();
+        for (k, (name, _)) in factors.iter().enumerate() {
+            if *name == time_col || *name == "r2" || factors[..k].iter().any(|(other, _)| other == name) {
+                return Err(anyhow::anyhow!("因子名 '{}' 重复或与输出列冲突", name));
+            }
+        }
+        
+        let codes = returns.stock_cols();
+        let tables: Vec<&WideTable> = factors.iter().map(|(_, table)| *table).collect();
+        let labels: Vec<String> = factors.iter().map(|(name, _)| format!("因子 '{}' 的宽表", name)).collect();
+        let (df, return_values, factor_values) = join_panels(returns, &tables, &labels, &codes)?;
+        
+        let valid = |v: Option<f64>| v.filter(|x| !x.is_nan());
+        let mut coefs: AlignedColumns = vec![vec![None; df.height()]; factors.len()];
+        let mut r2 = vec![None; df.height()];
+        let times = df.column(time_col)?;
+        for i in 0..df.height() {
+            let members: Vec<usize> = (0..codes.len())
+                .filter(|&j| valid(return_values[j][i]).is_some() && factor_values.iter().all(|f| valid(f[j][i]).is_some()))
+                .collect();
+            if members.len() <= factors.len() + 1 {
+                continue;
+            }
+            
+            let y: Vec<f64> = members.iter().map(|&j| return_values[j][i].unwrap()).collect();
+            let xs: Vec<Vec<f64>> = factor_values
+                .iter()
+                .map(|f| members.iter().map(|&j| f[j][i].unwrap()).collect())
+                .collect();
+            let fit = ols_multi(&xs, &y).ok_or_else(|| {
+                let time = times.get(i).map(|t| t.to_string()).unwrap_or_default();
+                let names: Vec<&str> = factors.iter().map(|(name, _)| *name).collect();
+                anyhow::anyhow!(
+                    "{} 的截面回归设计矩阵秩亏：因子 [{}] 之间存在完全共线，或有因子在截面上没有差异",
+                    time,
+                    names.join(", ")
+                )
+            })?;
+            r2[i] = fit.r_squared(&y);
+            for (values, b) in coefs.iter_mut().zip(&fit.coef[1..]) {
+                values[i] = Some(*b);
+            }
+        }
+        
+        let mut columns = vec![times.clone()];
+        columns.extend(factors.iter().zip(coefs).map(|((name, _), values)| Series::new(name, values)));
+        columns.push(Series::new("r2", r2));
+        Ok(DataFrame::new(columns)?)
+    }
+    
+    /// 由因子信号构建多空组合权重
+    /// 
+    /// 每期按因子值排序，做多最高的 `top_pct` 比例股票、做空最低的 `bottom_pct` 比例股票，
+    /// 每条腿股票数为 `floor(有效股票数 * 比例)`。多头权重之和为 1、空头权重之和为 -1，
+    /// 组合净权重为 0；未入选或因子缺失的股票权重为 0。
+    /// 市值加权时，腿内市值缺失或不为正的股票权重为 0；
+    /// 某期凑不齐两条腿（或某条腿没有有效市值）时该期全部权重为 0。
+    /// 
+    /// 权重宽表与收益率宽表逐元素相乘再按行求和，即得到组合收益。
+    /// 
+    /// # Arguments
+    /// * `factor_table` - 因子宽表
+    /// * `top_pct` - 多头比例，取值范围 `(0, 0.5]`
+    /// * `bottom_pct` - 空头比例，取值范围 `(0, 0.5]`
+    /// * `weighting` - 加权方式
+    /// 
+    /// # Returns
+    /// 按时间排序、股票列名与 `factor_table` 相同的权重宽表；
+    /// 市值加权时只保留两表共有的时间
+    pub fn construct_long_short_portfolio(
+        factor_table: &WideTable,
+        top_pct: f64,
+        bottom_pct: f64,
+        weighting: PortfolioWeighting,
+    ) -> Result<WideTable> {
+        for (name, pct) in [("top_pct", top_pct), ("bottom_pct", bottom_pct)] {
+            if !(pct > 0.0 && pct <= 0.5) {
+                return Err(anyhow::anyhow!("{} 必须在 (0, 0.5] 内，当前为 {}", name, pct));
+            }
+        }
+        
+        let time_col = factor_table.time_col();
+        let codes = factor_table.stock_cols();
+        let (df, factor_values, caps) = match weighting {
+            PortfolioWeighting::Equal => {
+                let df = factor_table.sorted_df()?;
+                let values = codes
+                    .iter()
+                    .map(|code| -> Result<Vec<Option<f64>>> {
+                        Ok(df.column(code)?.cast(&DataType::Float64)?.f64()?.into_iter().collect())
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (df, values, None)
+            }
+            PortfolioWeighting::ValueWeighted(market_cap) => {
+                let (joined, values, caps) = join_by_time(factor_table, market_cap, &codes)?;
+                (joined, values, Some(caps))
+            }
+        };
+        
+        let height = df.height();
+        let mut weights = vec![vec![0.0; height]; codes.len()];
+        let mut row: Vec<(f64, usize)> = Vec::with_capacity(codes.len());
+        
+        for i in 0..height {
+            row.clear();
+            row.extend(
+                factor_values
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(j, values)| values[i].filter(|f| !f.is_nan()).map(|f| (f, j))),
+            );
+            let n_long = (row.len() as f64 * top_pct).floor() as usize;
+            let n_short = (row.len() as f64 * bottom_pct).floor() as usize;
+            if n_long == 0 || n_short == 0 {
+                continue;
+            }
+            
+            row.sort_by(|a, b| a.0.total_cmp(&b.0));
+            // 腿内每只股票的原始权重（等权为 1，市值加权为市值），归一化后即为该腿的权重
+            let raw_weight = |j: usize| match &caps {
+                None => 1.0,
+                Some(caps) => caps[j][i].filter(|c| *c > 0.0).unwrap_or(0.0),
+            };
+            let long_leg = &row[row.len() - n_long..];
+            let short_leg = &row[..n_short];
+            let long_total: f64 = long_leg.iter().map(|&(_, j)| raw_weight(j)).sum();
+            let short_total: f64 = short_leg.iter().map(|&(_, j)| raw_weight(j)).sum();
+            if long_total == 0.0 || short_total == 0.0 {
+                continue;
+            }
+            
+            for &(_, j) in long_leg {
+                weights[j][i] = raw_weight(j) / long_total;
+            }
+            for &(_, j) in short_leg {
+                weights[j][i] = -raw_weight(j) / short_total;
+            }
+        }
+        
+        let mut columns = vec![df.column(time_col)?.clone()];
+        columns.extend(codes.iter().zip(weights).map(|(code, w)| Series::new(code, w)));
+        
+        Ok(WideTable::new(DataFrame::new(columns)?, time_col)?)
+    }
+    
+    /// 因子衰减分析
+    /// 
+    /// 对每只股票计算 `t` 时刻因子值与 `t` 到 `t+k` 累计收益之间的时间序列 IC（皮尔逊相关系数），
+    /// `k` 取 `1..=max_forward_periods`。累计收益由单期收益复利得到：`prod(1 + r_{t+1..=t+k}) - 1`。
+    /// 因子或累计收益缺失的时刻不参与计算，有效样本不足或没有波动时 IC 为 null。
+    /// 
+    /// # Arguments
+    /// * `factor_table` - 因子宽表
+    /// * `return_table` - 单期收益率宽表（小数形式），股票列名须与 `factor_table` 一致（按时间列对齐）
+    /// * `max_forward_periods` - 最大向前期数，至少为 1
+    /// 
+    /// # Returns
+    /// 每个 `k` 一行的 DataFrame，列为 `k` 与各股票的 IC
+    pub fn factor_decay_analysis(
+        factor_table: &WideTable,
+        return_table: &WideTable,
+        max_forward_periods: usize,
+    ) -> Result<DataFrame> {
+        if max_forward_periods == 0 {
+            return Err(anyhow::anyhow!("最大向前期数至少为 1"));
+        }
+        
+        let codes = factor_table.stock_cols();
+        let (_, factor_values, return_values) = join_by_time(factor_table, return_table, &codes)?;
+        
+        let mut columns = vec![Series::new("k", (1..=max_forward_periods as u32).collect::<Vec<_>>())];
+        for (code, (factor, returns)) in codes.iter().zip(factor_values.iter().zip(&return_values)) {
+            let height = factor.len();
+            let ics: Vec<Option<f64>> = (1..=max_forward_periods)
+                .map(|k| {
+                    let (mut x, mut y) = (Vec::new(), Vec::new());
+                    for t in 0..height.saturating_sub(k) {
+                        let f = match factor[t] {
+                            Some(f) if !f.is_nan() => f,
+                            _ => continue,
+                        };
+                        let growth = returns[t + 1..=t + k]
+                            .iter()
+                            .try_fold(1.0, |acc, r| r.filter(|r| !r.is_nan()).map(|r| acc * (1.0 + r)));
+                        if let Some(growth) = growth {
+                            x.push(f);
+                            y.push(growth - 1.0);
+                        }
+                    }
+                    stats::pearson(&x, &y)
+                })
+                .collect();
+            columns.push(Series::new(code, ics));
+        }
+        
+        Ok(DataFrame::new(columns)?)
+    }
+    
+    /// 由 IC 衰减序列估计因子半衰期
+    /// 
+    /// 假设 `|IC_k| = a * exp(-lambda * k)`（`k` 从 1 开始），对 `ln|IC_k|` 关于 `k` 做最小二乘拟合，
+    /// 半衰期为 `ln 2 / lambda`。null、NaN 与 0 值不参与拟合。
+    /// 
+    /// # Arguments
+    /// * `decay_series` - 按 `k = 1, 2, ...` 排列的 IC 序列，如 `factor_decay_analysis` 输出的某一列
+    /// 
+    /// # Returns
+    /// 以期数计的半衰期；信号不衰减时为 `f64::INFINITY`，有效点少于 2 个时为 NaN
+    pub fn half_life_from_decay(decay_series: &Series) -> f64 {
+        let values = match decay_series.cast(&DataType::Float64) {
+            Ok(values) => values,
+            Err(_) => return f64::NAN,
+        };
+        let (ks, logs): (Vec<f64>, Vec<f64>) = values
+            .f64()
+            .expect("已转换为 Float64")
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, ic)| {
+                ic.filter(|ic| ic.is_finite() && *ic != 0.0)
+                    .map(|ic| ((i + 1) as f64, ic.abs().ln()))
+            })
+            .unzip();
+        if ks.len() < 2 {
+            return f64::NAN;
+        }
+        
+        let n = ks.len() as f64;
+        let mean_k = ks.iter().sum::<f64>() / n;
+        let mean_log = logs.iter().sum::<f64>() / n;
+        let sxy: f64 = ks.iter().zip(&logs).map(|(k, l)| (k - mean_k) * (l - mean_log)).sum();
+        let sxx: f64 = ks.iter().map(|k| (k - mean_k).powi(2)).sum();
+        let slope = sxy / sxx;
+        if slope >= 0.0 {
+            return f64::INFINITY;
+        }
+        std::f64::consts::LN_2 / -slope
+    }
+    
+    /// 构造 SMB（小市值减大市值）因子收益，单变量排序
+    /// 
+    /// 每年 6 月末（收益率表中 6 月的最后一个观测）按当日市值的 30% / 70% 分位数分组，
+    /// 持有到次年 6 月末：`SMB = 市值最低 30% 组的等权收益 - 市值最高 30% 组的等权收益`。
+    /// 第一次调仓之前（含调仓当期）为 null；调仓日市值表中没有该日期、
+    /// 或某组在当期没有有效收益率时为 null。
+    /// 
+    /// 只有市值一个特征，无法做标准的 2×3 交叉分组；
+    /// 需要与学术定义一致的结果时请使用 [`FactorCalculator::construct_fama_french`]。
+    /// 
+    /// # Arguments
+    /// * `returns` - 单期收益率宽表（小数形式），时间列须为日期或时间类型
+    /// * `market_cap` - 市值宽表，包含 `returns` 的全部股票列，按时间列取调仓日的值
+    /// 
+    /// # Returns
+    /// 名为 `smb` 的 Series，与按时间排序后的 `returns` 逐行对应
+    pub fn construct_smb(returns: &WideTable, market_cap: &WideTable) -> Result<Series> {
+        june_rebalanced_factor(returns, &[market_cap], "smb", |chars, rets| {
+            let groups = breakpoint_groups(&chars[0], &[0.3, 0.7]);
+            let leg = |g: usize| weighted_mean_return(rets, |i| (groups[i] == Some(g)).then_some(1.0));
+            Some(leg(0)? - leg(2)?)
+        })
+    }
+    
+    /// 构造 HML（高账面市值比减低账面市值比）因子收益，单变量排序
+    /// 
+    /// 与 [`FactorCalculator::construct_smb`] 相同的 6 月末年度调仓：
+    /// `HML = 账面市值比最高 30% 组的等权收益 - 最低 30% 组的等权收益`。
+    /// 
+    /// # Arguments
+    /// * `returns` - 单期收益率宽表（小数形式），时间列须为日期或时间类型
+    /// * `book_to_market` - 账面市值比宽表，包含 `returns` 的全部股票列
+    /// 
+    /// # Returns
+    /// 名为 `hml` 的 Series，与按时间排序后的 `returns` 逐行对应
+    pub fn construct_hml(returns: &WideTable, book_to_market: &WideTable) -> Result<Series> {
+        june_rebalanced_factor(returns, &[book_to_market], "hml", |chars, rets| {
+            let groups = breakpoint_groups(&chars[0], &[0.3, 0.7]);
+            let leg = |g: usize| weighted_mean_return(rets, |i| (groups[i] == Some(g)).then_some(1.0));
+            Some(leg(2)? - leg(0)?)
+        })
+    }
+    
+    /// 按 Fama-French 六组合交叉分组法构造 SMB 与 HML
+    /// 
+    /// 每年 6 月末（收益率表中 6 月的最后一个观测）调仓，持有到次年 6 月末：
+    /// 按市值中位数分为小（S）、大（B）两组，按账面市值比的 30% / 70% 分位数分为
+    /// 低（L）、中（N）、高（H）三组，交叉得到 6 个组合，组合内按调仓日市值加权。
+    /// 
+    /// * `SMB = (S/L + S/N + S/H) / 3 - (B/L + B/N + B/H) / 3`
+    /// * `HML = (S/H + B/H) / 2 - (S/L + B/L) / 2`
+    /// 
+    /// 分位数取自全部有效股票（原始定义只用 NYSE 股票）。只有市值为正且账面市值比有效的股票参与分组；
+    /// 第一次调仓之前（含调仓当期）、调仓日特征缺失或任一组合当期没有有效收益率时为 null。
+    /// 
+    /// # Arguments
+    /// * `returns` - 单期收益率宽表（小数形式），时间列须为日期或时间类型
+    /// * `market_cap` - 市值宽表，包含 `returns` 的全部股票列
+    /// * `book_to_market` - 账面市值比宽表，包含 `returns` 的全部股票列
+    /// 
+    /// # Returns
+    /// 按时间排序的 DataFrame，列为时间列与 `smb, hml`
+    pub fn construct_fama_french(
+        returns: &WideTable,
+        market_cap: &WideTable,
+        book_to_market: &WideTable,
+    ) -> Result<DataFrame> {
+        let characteristics = [market_cap, book_to_market];
+        let smb = june_rebalanced_factor(returns, &characteristics, "smb", |chars, rets| {
+            let p = six_portfolio_returns(chars, rets)?;
+            Some((p[0][0] + p[0][1] + p[0][2]) / 3.0 - (p[1][0] + p[1][1] + p[1][2]) / 3.0)
+        })?;
+        let hml = june_rebalanced_factor(returns, &characteristics, "hml", |chars, rets| {
+            let p = six_portfolio_returns(chars, rets)?;
+            Some((p[0][2] + p[1][2]) / 2.0 - (p[0][0] + p[1][0]) / 2.0)
+        })?;
+        
+        Ok(DataFrame::new(vec![
+            returns.sorted_df()?.column(returns.time_col())?.clone(),
+            smb,
+            hml,
+        ])?)
+    }
+}
+
+/// 对 1 期小数收益率做滚动极值，输出只包含时间列与 `{code}_{tag}_{window}` 列
+fn rolling_return_extreme(
+    table: &WideTable,
+    window: usize,
+    tag: &str,
+    extreme: fn(Expr, RollingOptionsFixedWindow) -> Expr,
+) -> Result<WideTable> {
+    if window == 0 {
+        return Err(anyhow::anyhow!("滚动窗口必须为正数"));
+    }
+    
+    let returns = table.pct_change_fraction(1)?;
+    let mut exprs = vec![col(table.time_col())];
+    for code in table.stock_cols() {
+        let options = RollingOptionsFixedWindow {
+            window_size: window,
+            min_periods: window,
+            center: false,
+        };
+        exprs.push(extreme(col(&format!("{}_return_1", code)), options).alias(&format!("{}_{}_{}", code, tag, window)));
+    }
+    
+    Ok(WideTable::from_lazy(returns.lazy().select(exprs), table.time_col())?)
+}
+
+/// 在按时间排序的价格上做滚动统计，窗口内有效价格须满 `window` 个，
+/// 输出只包含时间列与 `{code}_{tag}_{window}` 列
+fn rolling_price_stat<F>(table: &WideTable, window: usize, tag: &str, mut stat: F) -> Result<WideTable>
+where
+    F: FnMut(&[f64]) -> Option<f64>,
+{
+    if window < 2 {
+        return Err(anyhow::anyhow!("滚动窗口至少为 2，当前为 {}", window));
+    }
+    
+    let df = table.sorted_df()?;
+    let mut columns = vec![df.column(table.time_col())?.clone()];
+    for code in table.stock_cols() {
+        let prices: Vec<Option<f64>> = df.column(&code)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+        let values = stats::rolling_window_apply(&prices, window, window, &mut stat);
+        columns.push(Series::new(&format!("{}_{}_{}", code, tag, window), values));
+    }
+    
+    Ok(WideTable::new(DataFrame::new(columns)?, table.time_col())?)
+}
+
+/// 对每只股票的有效收益率（剔除 null 与 NaN，至少一个）计算统计量，
+/// 输出每只股票一行、列为 `stock, {name}` 的 DataFrame
+fn per_stock_stat<F>(returns: &WideTable, name: &str, stat: F) -> Result<DataFrame>
+where
+    F: FnMut(&[f64]) -> Option<f64>,
+{
+    let values = per_stock_series(returns, name, stat)?;
+    Ok(DataFrame::new(vec![Series::new("stock", returns.stock_cols()), values])?)
+}
+
+/// 与 [`per_stock_stat`] 相同，但只返回名为 `name` 的 Series，按 `stock_cols()` 的顺序排列
+fn per_stock_series<F>(returns: &WideTable, name: &str, mut stat: F) -> Result<Series>
+where
+    F: FnMut(&[f64]) -> Option<f64>,
+{
+    let stock_cols = returns.stock_cols();
+    let mut values = Vec::with_capacity(stock_cols.len());
+    
+    for code in &stock_cols {
+        let series = returns.df().column(code)?.cast(&DataType::Float64)?;
+        let valid: Vec<f64> = series
+            .f64()?
+            .into_iter()
+            .flatten()
+            .filter(|r| !r.is_nan())
+            .collect();
+        values.push(if valid.is_empty() { None } else { stat(&valid) });
+    }
+    
+    Ok(Series::new(name, values))
+}
+
+/// 校验置信水平在 `(0, 1)` 内
+fn check_confidence(confidence: f64) -> Result<()> {
+    if confidence > 0.0 && confidence < 1.0 {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("置信水平必须在 (0, 1) 内，当前为 {}", confidence))
+    }
+}
+
+/// 按 `method` 计算一组收益率的 VaR（损失为正数）
+fn value_at_risk(returns: &[f64], confidence: f64, method: VarMethod) -> Option<f64> {
+    let alpha = 1.0 - confidence;
+    let quantile = match method {
+        VarMethod::Historical => {
+            let mut sorted = returns.to_vec();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            stats::quantile_sorted(&sorted, alpha)
+        }
+        VarMethod::Parametric | VarMethod::CornishFisher => {
+            let n = returns.len() as f64;
+            if returns.len() < 2 {
+                return None;
+            }
+            let mean = returns.iter().sum::<f64>() / n;
+            let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+            let z = stats::normal_ppf(alpha);
+            let z = if method == VarMethod::CornishFisher {
+                let s = stats::skew(returns).unwrap_or(0.0);
+                let k = stats::kurtosis(returns)?;
+                z + (z * z - 1.0) * s / 6.0 + (z.powi(3) - 3.0 * z) * k / 24.0
+                    - (2.0 * z.powi(3) - 5.0 * z) * s * s / 36.0
+            } else {
+                z
+            };
+            mean + z * std
+        }
+    };
+    Some(-quantile)
+}
+
+/// 对每只股票的主动收益计算统计量，`stat` 接收主动收益的均值与样本标准差
+fn active_return_stat<F>(
+    returns: &WideTable,
+    benchmark: &Series,
+    name: &str,
+    stat: F,
+) -> Result<Series>
+where
+    F: Fn(f64, f64) -> Option<f64>,
+{
+    if benchmark.len() != returns.df().height() {
+        return Err(anyhow::anyhow!(
+            "基准收益率长度 {} 与宽表行数 {} 不一致",
+            benchmark.len(),
+            returns.df().height()
+        ));
+    }
+    
+    let bench: Vec<Option<f64>> = benchmark.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+    let mut values = Vec::new();
+    for (_, series) in returns.iter_stocks() {
+        let series = series.cast(&DataType::Float64)?;
+        let active: Vec<f64> = series
+            .f64()?
+            .into_iter()
+            .zip(&bench)
+            .filter_map(|(r, b)| match (r, b) {
+                (Some(r), Some(b)) if !r.is_nan() && !b.is_nan() => Some(r - b),
+                _ => None,
+            })
+            .collect();
+        
+        if active.len() < 2 {
+            values.push(None);
+            continue;
+        }
+        
+        let n = active.len() as f64;
+        let mean = active.iter().sum::<f64>() / n;
+        let var = active.iter().map(|a| (a - mean) * (a - mean)).sum::<f64>() / (n - 1.0);
+        values.push(stat(mean, var.sqrt()));
+    }
+    
+    Ok(Series::new(name, values))
+}
+
+/// 按股票顺序排列的各列数值
+type AlignedColumns = Vec<Vec<Option<f64>>>;
+
+/// 按时间列内连接两张宽表并按时间排序，取出 `codes` 中每只股票在两张表中的数值
+/// 
+/// 返回连接后的 DataFrame（含时间列）以及左、右两表按 `codes` 顺序排列的列值
+fn join_by_time(
+    left: &WideTable,
+    right: &WideTable,
+    codes: &[String],
+) -> Result<(DataFrame, AlignedColumns, AlignedColumns)> {
+    let time_col = left.time_col();
+    let right_cols = right.stock_cols();
+    if let Some(missing) = codes.iter().find(|code| !right_cols.contains(code)) {
+        return Err(anyhow::anyhow!("宽表中缺少股票列 '{}'", missing));
+    }
+    
+    let mut left_exprs = vec![col(time_col)];
+    left_exprs.extend(codes.iter().map(|code| col(code).cast(DataType::Float64)));
+    let mut right_exprs = vec![col(right.time_col()).alias(time_col)];
+    right_exprs.extend(
+        codes
+            .iter()
+            .map(|code| col(code).cast(DataType::Float64).alias(&format!("__right_{}", code))),
+    );
+    
+    let joined = left
+        .lazy()
+        .select(left_exprs)
+        .join(
+            right.lazy().select(right_exprs),
+            [col(time_col)],
+            [col(time_col)],
+            JoinArgs::new(polars::prelude::JoinType::Inner),
+        )
+        .sort([time_col], SortMultipleOptions::default())
+        .collect()?;
+    
+    let column = |name: &str| -> Result<Vec<Option<f64>>> {
+        Ok(joined.column(name)?.f64()?.into_iter().collect())
+    };
+    let left_values = codes.iter().map(|code| column(code)).collect::<Result<Vec<_>>>()?;
+    let right_values = codes
+        .iter()
+        .map(|code| column(&format!("__right_{}", code)))
+        .collect::<Result<Vec<_>>>()?;
+    
+    Ok((joined, left_values, right_values))
+}
+
+/// 把若干张宽表按时间列左连接到 `base` 上，取出 `codes` 中每只股票在各表中的数值
+/// 
+/// 以 `base` 的时间为准（按时间排序），其他表缺少的时间记为 null。
+/// 返回连接后的 DataFrame（含时间列）、`base` 的列值，以及每张其他表按 `codes` 顺序排列的列值；
+/// 其他表缺少 `codes` 中的股票列时返回错误，错误信息用 `labels` 中对应的描述指明是哪张表；
+/// 时间列类型与 `base` 不同时返回 [`WideTableError::SchemaMismatch`]，而不是按字符串表示匹配时间
+fn join_panels(
+    base: &WideTable,
+    others: &[&WideTable],
+    labels: &[String],
+    codes: &[String],
+) -> Result<(DataFrame, AlignedColumns, Vec<AlignedColumns>)> {
+    let time_col = base.time_col();
+    let mut lazy_df = base.sorted_df()?.lazy();
+    for (k, (other, label)) in others.iter().zip(labels).enumerate() {
+        let other_cols = other.stock_cols();
+        if let Some(missing) = codes.iter().find(|code| !other_cols.contains(code)) {
+            return Err(anyhow::anyhow!("{}中缺少股票列 '{}'", label, missing));
+        }
+        let base_dtype = base.df().column(time_col)?.dtype();
+        let other_dtype = other.df().column(other.time_col())?.dtype();
+        if base_dtype != other_dtype {
+            return Err(WideTableError::SchemaMismatch(format!(
+                "{}的时间列类型为 {}，与 {} 不一致",
+                label, other_dtype, base_dtype
+            ))
+            .into());
+        }
+        let mut renamed = vec![col(other.time_col()).alias(time_col)];
+        renamed.extend(codes.iter().map(|code| col(code).alias(&format!("__panel{}_{}", k, code))));
+        lazy_df = lazy_df.join(
+            other.lazy().select(renamed),
+            [col(time_col)],
+            [col(time_col)],
+            JoinArgs::new(polars::prelude::JoinType::Left),
+        );
+    }
+    let df = lazy_df.collect()?;
+    
+    let column = |name: &str| -> Result<Vec<Option<f64>>> {
+        Ok(df.column(name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect())
+    };
+    let base_values = codes.iter().map(|code| column(code)).collect::<Result<Vec<_>>>()?;
+    let other_values = (0..others.len())
+        .map(|k| codes.iter().map(|code| column(&format!("__panel{}_{}", k, code))).collect::<Result<Vec<_>>>())
+        .collect::<Result<Vec<_>>>()?;
+    Ok((df, base_values, other_values))
+}
+
+/// 按每年 6 月末调仓的特征值逐期计算因子收益
+/// 
+/// `spread` 接收调仓日各特征表的取值（外层按 `characteristics` 顺序，内层按 `returns` 的股票列顺序）
+/// 与当期收益率，缺失值与 NaN 均为 None。特征表按时间列左连接到 `returns` 上，
+/// 调仓日为收益率表中每年 6 月的最后一个观测，从下一行开始生效；之前的行为 null。
+fn june_rebalanced_factor<F>(
+    returns: &WideTable,
+    characteristics: &[&WideTable],
+    name: &str,
+    mut spread: F,
+) -> Result<Series>
+where
+    F: FnMut(&[Vec<Option<f64>>], &[Option<f64>]) -> Option<f64>,
+{
+    let codes = returns.stock_cols();
+    let time_col = returns.time_col();
+    let labels: Vec<String> = (1..=characteristics.len()).map(|k| format!("第 {} 张特征宽表", k)).collect();
+    let (df, rets, char_values) = join_panels(returns, characteristics, &labels, &codes)?;
+    let valid = |columns: AlignedColumns| -> AlignedColumns {
+        columns
+            .into_iter()
+            .map(|c| c.into_iter().map(|v| v.filter(|x| !x.is_nan())).collect())
+            .collect()
+    };
+    let rets = valid(rets);
+    let char_values: Vec<AlignedColumns> = char_values.into_iter().map(valid).collect();
+    
+    let months: Vec<Option<i32>> = df
+        .clone()
+        .lazy()
+        .select([col(time_col).dt().month().cast(DataType::Int32)])
+        .collect()?
+        .column(time_col)?
+        .i32()?
+        .into_iter()
+        .collect();
+    
+    let mut values = Vec::with_capacity(df.height());
+    // 当前生效的调仓日特征值，第一次调仓之前为 None；调仓日特征表中没有该时间时取值全为 None
+    let mut chars: Option<Vec<Vec<Option<f64>>>> = None;
+    let mut row = vec![None; codes.len()];
+    for i in 0..df.height() {
+        match &chars {
+            Some(chars) => {
+                for (r, ret) in row.iter_mut().zip(&rets) {
+                    *r = ret[i];
+                }
+                values.push(spread(chars, &row));
+            }
+            None => values.push(None),
+        }
+        
+        let june_end = months[i] == Some(6) && months.get(i + 1).copied().flatten() != Some(6);
+        if june_end {
+            chars = Some(
+                char_values
+                    .iter()
+                    .map(|columns| columns.iter().map(|c| c[i]).collect())
+                    .collect(),
+            );
+        }
+    }
+    
+    Ok(Series::new(name, values))
+}
+
+/// 按分位数断点分组：组号为值严格大于的断点个数，缺失值为 None
+/// 
+/// 例如 `levels = [0.3, 0.7]` 时，不超过 30% 分位数为 0 组，超过 70% 分位数为 2 组
+fn breakpoint_groups(values: &[Option<f64>], levels: &[f64]) -> Vec<Option<usize>> {
+    let mut sorted: Vec<f64> = values.iter().flatten().copied().collect();
+    if sorted.is_empty() {
+        return vec![None; values.len()];
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let breakpoints: Vec<f64> = levels.iter().map(|&q| stats::quantile_sorted(&sorted, q)).collect();
+    values
+        .iter()
+        .map(|v| v.map(|x| breakpoints.iter().filter(|&&b| x > b).count()))
+        .collect()
+}
+
+/// 加权平均收益率，`weight` 返回 None 的股票与收益率缺失的股票不参与；没有有效股票时为 None
+fn weighted_mean_return(rets: &[Option<f64>], weight: impl Fn(usize) -> Option<f64>) -> Option<f64> {
+    let (mut total, mut weighted) = (0.0, 0.0);
+    for (i, r) in rets.iter().enumerate() {
+        if let (Some(r), Some(w)) = (r, weight(i)) {
+            total += w;
+            weighted += w * r;
+        }
+    }
+    (total > 0.0).then(|| weighted / total)
+}
+
+/// Fama-French 2×3 交叉分组的市值加权组合收益，`[规模][价值]`，
+/// 规模 0 / 1 为小 / 大，价值 0 / 1 / 2 为低 / 中 / 高；任一组合没有有效收益率时为 None
+fn six_portfolio_returns(chars: &[Vec<Option<f64>>], rets: &[Option<f64>]) -> Option<[[f64; 3]; 2]> {
+    let (cap, btm) = (&chars[0], &chars[1]);
+    let eligible: Vec<bool> = cap.iter().zip(btm).map(|(c, b)| c.is_some_and(|c| c > 0.0) && b.is_some()).collect();
+    let only_eligible = |values: &[Option<f64>]| -> Vec<Option<f64>> {
+        values.iter().zip(&eligible).map(|(v, &e)| v.filter(|_| e)).collect()
+    };
+    let size = breakpoint_groups(&only_eligible(cap), &[0.5]);
+    let value = breakpoint_groups(&only_eligible(btm), &[0.3, 0.7]);
+    
+    let mut portfolios = [[0.0; 3]; 2];
+    for (s, row) in portfolios.iter_mut().enumerate() {
+        for (v, ret) in row.iter_mut().enumerate() {
+            *ret = weighted_mean_return(rets, |i| cap[i].filter(|_| size[i] == Some(s) && value[i] == Some(v)))?;
+        }
+    }
+    Some(portfolios)
+}
+
+/// Wilder 平滑（RMA）：`alpha = 1 / window` 的指数平滑，有效值不足 `window` 个时为 null
+fn wilder_rma(e: Expr, window: usize) -> Expr {
+    e.ewm_mean(EWMOptions {
+        alpha: 1.0 / window as f64,
+        adjust: false,
+        bias: false,
+        min_periods: window,
+        ignore_nulls: true,
+    })
+}
+
+/// 真实波幅 `max(high - low, |high - prev_close|, |low - prev_close|)`，
+/// 基于 [`PriceSet::aligned_lazy`] 的列，首行没有前收盘价时为 null
+fn true_range_expr(code: &str) -> Expr {
+    let max2 = |a: Expr, b: Expr| when(a.clone().gt_eq(b.clone())).then(a).otherwise(b);
+    let high = col(&format!("__high_{}", code));
+    let low = col(&format!("__low_{}", code));
+    let prev_close = col(&format!("__close_{}", code)).shift(lit(1));
+    
+    when(prev_close.clone().is_null())
+        .then(lit(NULL).cast(DataType::Float64))
+        .otherwise(max2(
+            high.clone() - low.clone(),
+            max2((high - prev_close.clone()).abs(), (low - prev_close).abs()),
+        ))
+}
+
+/// 除基准列外的股票列，基准列不存在时报错
+fn non_benchmark_cols(table: &WideTable, benchmark_col: &str) -> Result<Vec<String>> {
+    let stock_cols = table.stock_cols();
+    if !stock_cols.iter().any(|code| code == benchmark_col) {
+        return Err(anyhow::anyhow!("基准列 '{}' 不存在", benchmark_col));
+    }
+    Ok(stock_cols.into_iter().filter(|code| code != benchmark_col).collect())
+}
+
+/// 回归输出列：列名片段与从拟合结果中取值的函数
+type FitOutput = (&'static str, fn(&OlsFit) -> f64);
+
+/// 对每只股票的收益率做相对基准收益率的滚动回归，按 `outputs` 输出 `{code}_{name}_{window}` 列
+fn rolling_regression_factor(
+    table: &WideTable,
+    benchmark_col: &str,
+    window: usize,
+    fit: impl Fn(&[Option<f64>], &[Option<f64>]) -> Vec<Option<OlsFit>>,
+    outputs: &[FitOutput],
+) -> Result<WideTable> {
+    if window < 3 {
+        return Err(anyhow::anyhow!("回归窗口至少为 3，当前为 {}", window));
+    }
+    
+    let codes = non_benchmark_cols(table, benchmark_col)?;
+    let ret_table = table.pct_change_fraction(1)?;
+    let returns = |code: &str| -> Result<Vec<Option<f64>>> {
+        Ok(ret_table
+            .df()
+            .column(&format!("{}_return_1", code))?
+            .f64()?
+            .into_iter()
+            .collect())
+    };
+    let bench = returns(benchmark_col)?;
+    
+    let mut columns = vec![ret_table.df().column(table.time_col())?.clone()];
+    for code in &codes {
+        let fits = fit(&bench, &returns(code)?);
+        for (name, extract) in outputs {
+            let values: Vec<Option<f64>> = fits.iter().map(|fit| fit.as_ref().map(extract)).collect();
+            columns.push(Series::new(&format!("{}_{}_{}", code, name, window), values));
+        }
+    }
+    
+    Ok(WideTable::new(DataFrame::new(columns)?, table.time_col())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    
+    fn single_stock_table(values: Vec<f64>) -> WideTable {
+        let dates: Vec<NaiveDate> = (0..values.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", values),
+        ]).unwrap();
+        WideTable::new(df, "date").unwrap()
+    }
+    
+    fn last_value(table: &WideTable, col_name: &str) -> f64 {
+        let s = table.df().column(col_name).unwrap();
+        s.f64().unwrap().get(s.len() - 1).unwrap()
+    }
+    
+    #[test]
+    fn test_bias_constant_price_is_zero() {
+        let table = single_stock_table(vec![10.0; 5]);
+        
+        let bias = FactorCalculator::calculate_bias(&table, 3).unwrap();
+        let s = bias.df().column("stock_A_bias_3").unwrap().f64().unwrap().clone();
+        assert_eq!(s.null_count(), 2);
+        assert!(s.into_iter().flatten().all(|v| v == 0.0));
+    }
+    
+    #[test]
+    fn test_momentum_accel_constant_returns() {
+        let prices: Vec<f64> = (0..12).map(|i| 100.0 * 1.02_f64.powi(i)).collect();
+        let table = single_stock_table(prices);
+        
+        let accel = FactorCalculator::calculate_momentum_accel(&table, 3).unwrap();
+        assert!(last_value(&accel, "stock_A_mom_accel_3").abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_ivol_exact_linear_is_zero() {
+        let bench: Vec<f64> = (0..10).map(|i| 100.0 + (i as f64).sin() * 5.0).collect();
+        // 股票收益率恰好是基准收益率的 2 倍，残差为 0
+        let mut stock = vec![50.0];
+        for i in 1..bench.len() {
+            let r = bench[i] / bench[i - 1] - 1.0;
+            let last = *stock.last().unwrap();
+            stock.push(last * (1.0 + 2.0 * r));
+        }
+        let dates: Vec<NaiveDate> = (0..bench.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("index", bench),
+            Series::new("stock_A", stock),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let ivol = FactorCalculator::calculate_ivol(&table, "index", 5).unwrap();
+        assert_eq!(ivol.df().get_column_names(), &["date", "stock_A_ivol_5"]);
+        assert_eq!(ivol.df().column("stock_A_ivol_5").unwrap().null_count(), 5);
+        assert!(last_value(&ivol, "stock_A_ivol_5") < 1e-10);
+    }
+    
+    #[test]
+    fn test_idiosyncratic_volatility_from_returns() {
+        let market: Vec<f64> = (0..12).map(|i| 0.01 * (i as f64).sin()).collect();
+        // stock_A 完全由市场解释；stock_B 额外叠加正负交替的特质收益
+        let exact: Vec<f64> = market.iter().map(|r| 0.002 + 1.5 * r).collect();
+        let noisy: Vec<f64> = market
+            .iter()
+            .enumerate()
+            .map(|(i, r)| 0.8 * r + if i % 2 == 0 { 0.01 } else { -0.01 })
+            .collect();
+        let dates: Vec<NaiveDate> = (0..market.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", exact),
+            Series::new("stock_B", noisy),
+        ]).unwrap();
+        let returns = WideTable::new(df, "date").unwrap();
+        let market = Series::new("market", market);
+        
+        let ivol = FactorCalculator::calculate_idiosyncratic_volatility(&returns, &market, 5).unwrap();
+        assert_eq!(ivol.df().get_column_names(), &["date", "stock_A_ivol_5", "stock_B_ivol_5"]);
+        assert_eq!(ivol.df().column("stock_A_ivol_5").unwrap().null_count(), 4);
+        assert!(last_value(&ivol, "stock_A_ivol_5") < 1e-10);
+        assert!(last_value(&ivol, "stock_B_ivol_5") > 0.005);
+        
+        let short = Series::new("market", &[0.01, 0.02]);
+        assert!(FactorCalculator::calculate_idiosyncratic_volatility(&returns, &short, 5).is_err());
+    }
+    
+    #[test]
+    fn test_downside_beta() {
+        // 基准涨跌交替：下跌日股票收益率是基准的 2 倍，上涨日只有 0.5 倍
+        let bench_returns = [-0.02, 0.01, -0.01, 0.03, -0.03, 0.02, -0.015, 0.01, -0.025, 0.015];
+        let mut bench = vec![100.0];
+        let mut stock = vec![50.0];
+        for r in bench_returns {
+            let stock_r = if r < 0.0 { 2.0 * r } else { 0.5 * r };
+            bench.push(bench.last().unwrap() * (1.0 + r));
+            stock.push(stock.last().unwrap() * (1.0 + stock_r));
+        }
+        let dates: Vec<NaiveDate> = (0..bench.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("index", bench),
+            Series::new("stock_A", stock),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+    
+        let dbeta = FactorCalculator::calculate_downside_beta(&table, "index", 6).unwrap();
+        assert_eq!(dbeta.df().get_column_names(), &["date", "stock_A_dbeta_6"]);
+        // 前 6 行窗口未满（首行收益率为 null）
+        assert_eq!(dbeta.df().column("stock_A_dbeta_6").unwrap().null_count(), 6);
+        assert!((last_value(&dbeta, "stock_A_dbeta_6") - 2.0).abs() < 1e-10);
+    
+        // 全样本 beta 混合了上涨日，明显低于下行 beta
+        let beta = FactorCalculator::calculate_rolling_beta(&table, "index", 6).unwrap();
+        assert!(last_value(&beta, "stock_A_beta_6") < 1.9);
+    
+        // 6 日窗口内只有 3 个下跌日，要求 4 个时全部为 null
+        let strict = FactorCalculator::calculate_downside_beta_with(&table, "index", 6, 4).unwrap();
+        assert_eq!(strict.df().column("stock_A_dbeta_6").unwrap().null_count(), table.df().height());
+    
+        assert!(FactorCalculator::calculate_downside_beta_with(&table, "index", 6, 7).is_err());
+    }
+    
+    #[test]
+    fn test_info_ratio() {
+        let bench: Vec<f64> = (0..10).map(|i| 100.0 + (i as f64).sin() * 5.0).collect();
+        // 每期在基准收益率上叠加正的、有波动的超额收益
+        let mut outperform = vec![50.0];
+        for i in 1..bench.len() {
+            let r = bench[i] / bench[i - 1] - 1.0;
+            let excess = 0.01 + 0.002 * (i % 2) as f64;
+            let last = *outperform.last().unwrap();
+            outperform.push(last * (1.0 + r + excess));
+        }
+        let dates: Vec<NaiveDate> = (0..bench.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("index", bench.clone()),
+            Series::new("tracker", bench),
+            Series::new("outperform", outperform),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let ir = FactorCalculator::calculate_info_ratio(&table, "index", 4).unwrap();
+        assert_eq!(ir.df().get_column_names(), &["date", "tracker_ir_4", "outperform_ir_4"]);
+        // 完全跟踪基准：跟踪误差为 0，结果为 null
+        assert_eq!(ir.df().column("tracker_ir_4").unwrap().null_count(), 10);
+        assert!(last_value(&ir, "outperform_ir_4") > 0.0);
+    }
+    
+    #[test]
+    fn test_calmar_and_ulcer_rise_then_crash() {
+        let table = single_stock_table(vec![100.0, 110.0, 120.0, 90.0]);
+        
+        // 最大回撤 120 -> 90 为 25%，3 期总收益 -10%
+        let calmar = FactorCalculator::calculate_calmar(&table, 4).unwrap();
+        let expected = (0.9_f64.powf(252.0 / 3.0) - 1.0) / 0.25;
+        assert!((last_value(&calmar, "stock_A_calmar_4") - expected).abs() < 1e-12);
+        
+        // 回撤序列 0, 0, 0, -25%：sqrt(25^2 / 4) = 12.5
+        let ulcer = FactorCalculator::calculate_ulcer(&table, 4).unwrap();
+        assert!((last_value(&ulcer, "stock_A_ulcer_4") - 12.5).abs() < 1e-9);
+        
+        // 单调上涨没有回撤，Calmar 为 null
+        let rising = single_stock_table(vec![100.0, 110.0, 120.0]);
+        let calmar = FactorCalculator::calculate_calmar(&rising, 3).unwrap();
+        assert_eq!(calmar.df().column("stock_A_calmar_3").unwrap().null_count(), 3);
+    }
+    
+    #[test]
+    fn test_long_short_returns() {
+        let dates = vec![
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+        ];
+        let factor = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates.clone()),
+            Series::new("a", &[Some(1.0), Some(1.0)]),
+            Series::new("b", &[Some(2.0), None]),
+            Series::new("c", &[Some(3.0), None]),
+            Series::new("d", &[Some(4.0), None]),
+        ]).unwrap(), "date").unwrap();
+        let fwd = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[0.01, 0.01]),
+            Series::new("b", &[0.02, 0.02]),
+            Series::new("c", &[0.03, 0.03]),
+            Series::new("d", &[0.04, 0.04]),
+        ]).unwrap(), "date").unwrap();
+        
+        let ls = FactorCalculator::long_short_returns(&factor, &fwd, 0.25).unwrap();
+        let spread = ls.column("long_short").unwrap().f64().unwrap().clone();
+        assert!((spread.get(0).unwrap() - 0.03).abs() < 1e-12);
+        // 第二期只有 1 只股票有因子值，凑不齐两条腿
+        assert_eq!(spread.get(1), None);
+    }
+    
+    #[test]
+    fn test_construct_long_short_portfolio() {
+        let dates = vec![
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+        ];
+        let factor = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates.clone()),
+            Series::new("a", &[Some(1.0), Some(1.0)]),
+            Series::new("b", &[Some(2.0), None]),
+            Series::new("c", &[Some(3.0), None]),
+            Series::new("d", &[Some(4.0), None]),
+            Series::new("e", &[Some(5.0), Some(2.0)]),
+            Series::new("f", &[Some(6.0), None]),
+        ]).unwrap(), "date").unwrap();
+        let weights_at = |table: &WideTable, i: usize| -> Vec<f64> {
+            ["a", "b", "c", "d", "e", "f"]
+                .iter()
+                .map(|code| table.df().column(code).unwrap().f64().unwrap().get(i).unwrap())
+                .collect()
+        };
+        
+        let equal = FactorCalculator::construct_long_short_portfolio(&factor, 0.4, 0.2, PortfolioWeighting::Equal).unwrap();
+        assert_eq!(weights_at(&equal, 0), vec![-1.0, 0.0, 0.0, 0.0, 0.5, 0.5]);
+        // 第二期只有 2 只股票有因子值，凑不齐两条腿
+        assert_eq!(weights_at(&equal, 1), vec![0.0; 6]);
+        
+        let cap = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[10.0, 10.0]),
+            Series::new("b", &[10.0, 10.0]),
+            Series::new("c", &[10.0, 10.0]),
+            Series::new("d", &[10.0, 10.0]),
+            Series::new("e", &[100.0, 100.0]),
+            Series::new("f", &[300.0, 300.0]),
+        ]).unwrap(), "date").unwrap();
+        let value = FactorCalculator::construct_long_short_portfolio(
+            &factor,
+            0.4,
+            0.2,
+            PortfolioWeighting::ValueWeighted(&cap),
+        ).unwrap();
+        assert_eq!(weights_at(&value, 0), vec![-1.0, 0.0, 0.0, 0.0, 0.25, 0.75]);
+        
+        assert!(FactorCalculator::construct_long_short_portfolio(&factor, 0.6, 0.2, PortfolioWeighting::Equal).is_err());
+    }
+    
+    #[test]
+    fn test_factor_decay_analysis() {
+        let returns = vec![0.01, -0.02, 0.03, 0.00, -0.01, 0.02, 0.015, -0.005];
+        // 因子恰好等于下一期收益，k = 1 时 IC 为 1
+        let mut factor: Vec<Option<f64>> = returns[1..].iter().copied().map(Some).collect();
+        factor.push(None);
+        let return_table = single_stock_table(returns);
+        let factor_df = DataFrame::new(vec![
+            return_table.df().column("date").unwrap().clone(),
+            Series::new("stock_A", factor),
+        ]).unwrap();
+        let factor_table = WideTable::new(factor_df, "date").unwrap();
+        
+        let decay = FactorCalculator::factor_decay_analysis(&factor_table, &return_table, 3).unwrap();
+        assert_eq!(decay.height(), 3);
+        let ic = decay.column("stock_A").unwrap().f64().unwrap().clone();
+        assert!((ic.get(0).unwrap() - 1.0).abs() < 1e-12);
+        assert!(ic.get(2).unwrap() < 1.0);
+    }
+    
+    #[test]
+    fn test_half_life_from_decay() {
+        let ics: Vec<f64> = (1..=6).map(|k| 0.08 * 0.5f64.powf(k as f64 / 3.0)).collect();
+        let half_life = FactorCalculator::half_life_from_decay(&Series::new("ic", ics));
+        assert!((half_life - 3.0).abs() < 1e-9);
+        
+        let flat = FactorCalculator::half_life_from_decay(&Series::new("ic", &[0.05, 0.05, 0.05]));
+        assert!(flat.is_infinite());
+        assert!(FactorCalculator::half_life_from_decay(&Series::new("ic", &[0.05])).is_nan());
+    }
+    
+    #[test]
+    fn test_trix_matches_reference() {
+        // 参考值按 `ta.trend.TRIXIndicator(close, window=4)` 的算法计算：
+        // 三次 `ewm(span=4, adjust=False, min_periods=4).mean()` 后取 `pct_change() * 100`
+        let close: Vec<f64> = (0..30)
+            .map(|i| 100.0 + 10.0 * (i as f64 / 4.0).sin() + i as f64 * 0.3)
+            .collect();
+        let table = single_stock_table(close);
+        
+        let trix = FactorCalculator::calculate_trix(&table, 4).unwrap();
+        assert_eq!(trix.df().width(), 2);
+        let s = trix.df().column("stock_A_trix_4").unwrap().f64().unwrap().clone();
+        assert_eq!(s.null_count(), 10);
+        assert!((s.get(10).unwrap() - 0.2563794965156084).abs() < 1e-9);
+        assert!((s.get(29).unwrap() - 1.9944078460161028).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_abnormal_volume_spike() {
+        let dates: Vec<NaiveDate> = (0..6)
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[100i64, 100, 100, 100, 100, 1000]),
+        ]).unwrap();
+        let volume = WideTable::new(df, "date").unwrap();
+        
+        let avol = FactorCalculator::calculate_abnormal_volume(&volume, 5).unwrap();
+        let s = avol.df().column("stock_A_avol_5").unwrap().f64().unwrap().clone();
+        // 成交量不变的窗口标准差为 0，结果为 null
+        assert_eq!(s.get(4), None);
+        assert!(s.get(5).unwrap() > 1.5);
+    }
+    
+    #[test]
+    fn test_vov_regime_change() {
+        // 前半段每期涨跌 ±1%，后半段 ±5%
+        let mut prices = vec![100.0];
+        for i in 0..30 {
+            let step = if i < 15 { 0.01 } else { 0.05 };
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let last = *prices.last().unwrap();
+            prices.push(last * (1.0 + sign * step));
+        }
+        let table = single_stock_table(prices);
+        
+        let vov = FactorCalculator::calculate_vov(&table, 4, 3).unwrap();
+        let s = vov.df().column("stock_A_vov_4_3").unwrap().f64().unwrap().clone();
+        
+        // 收益率从第 1 行开始，波动率从第 4 行开始，vov 从第 6 行开始
+        assert_eq!(s.null_count(), 6);
+        assert!(s.get(6).unwrap() < 1e-3);
+        // 波动率切换期间 vov 明显升高
+        let peak = s.into_iter().flatten().fold(0.0, f64::max);
+        assert!(peak > 0.01);
+    }
+    
+    #[test]
+    fn test_aroon_single_peak() {
+        let prices = vec![1.0, 2.0, 5.0, 3.0, 2.0, 1.0, 1.0];
+        let set = PriceSet::new(
+            single_stock_table(prices.clone()),
+            single_stock_table(prices.clone()),
+            single_stock_table(prices),
+        ).unwrap();
+        
+        let aroon = FactorCalculator::calculate_aroon(&set, 3).unwrap();
+        let values = |name: &str| -> Vec<Option<f64>> {
+            aroon.df().column(name).unwrap().f64().unwrap().into_iter()
+                .map(|v| v.map(|v| (v * 100.0).round() / 100.0))
+                .collect()
+        };
+        assert_eq!(
+            values("stock_A_aroon_up_3"),
+            vec![None, None, Some(100.0), Some(66.67), Some(33.33), Some(33.33), Some(33.33)]
+        );
+        // 最后一个窗口 [2, 1, 1] 中最低价出现两次，取最近一次
+        assert_eq!(
+            values("stock_A_aroon_down_3"),
+            vec![None, None, Some(33.33), Some(33.33), Some(100.0), Some(100.0), Some(100.0)]
+        );
+    }
+    
+    #[test]
+    fn test_drawdown_duration_peak_then_decline() {
+        let prices = vec![1.0, 2.0, 3.0, 10.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0];
+        let table = single_stock_table(prices);
+        
+        let duration = FactorCalculator::calculate_drawdown_duration(&table, 6).unwrap();
+        assert_eq!(duration.df().get_column_names(), &["date", "stock_A_dd_dur_6"]);
+        let values: Vec<Option<f64>> = duration.df().column("stock_A_dd_dur_6").unwrap().f64().unwrap().into_iter().collect();
+        // 峰值之后持续下跌，期数逐日增加；峰值移出 6 日窗口后，窗口最高点始终是窗口首行
+        assert_eq!(
+            values,
+            vec![None, None, None, None, None, Some(2.0), Some(3.0), Some(4.0), Some(5.0), Some(5.0), Some(5.0)]
+        );
+        
+        let at_high = FactorCalculator::calculate_drawdown_duration(&single_stock_table(vec![1.0, 2.0, 3.0]), 2).unwrap();
+        assert_eq!(last_value(&at_high, "stock_A_dd_dur_2"), 0.0);
+        
+        assert!(FactorCalculator::calculate_drawdown_duration(&table, 0).is_err());
+    }
+    
+    #[test]
+    fn test_price_to_high() {
+        let dates: Vec<NaiveDate> = (0..6)
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            // 最后一个窗口 [10, 8, 6] 的最高价在开头
+            Series::new("start", &[Some(1.0), Some(2.0), Some(5.0), Some(10.0), Some(8.0), Some(6.0)]),
+            // 最后一个窗口 [8, 10, 6] 的最高价在中间
+            Series::new("middle", &[Some(1.0), Some(2.0), Some(5.0), Some(8.0), Some(10.0), Some(6.0)]),
+            // 最后一个窗口 [6, 8, 10] 的最高价在末尾（新高）；窗口内的 null 被跳过
+            Series::new("end", &[Some(1.0), Some(2.0), None, Some(6.0), Some(8.0), Some(10.0)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let pth = FactorCalculator::calculate_price_to_high(&table, 3).unwrap();
+        assert_eq!(
+            pth.df().get_column_names(),
+            &["date", "start_pth_3", "middle_pth_3", "end_pth_3"]
+        );
+        assert!((last_value(&pth, "start_pth_3") - 0.6).abs() < 1e-12);
+        assert!((last_value(&pth, "middle_pth_3") - 0.6).abs() < 1e-12);
+        assert_eq!(last_value(&pth, "end_pth_3"), 1.0);
+        
+        let end: Vec<Option<f64>> = pth.df().column("end_pth_3").unwrap().f64().unwrap().into_iter().collect();
+        // 第 3 行价格缺失为 null；第 4 行的窗口 [2, null, 6] 只在有效价格中取最高价
+        assert_eq!(end, vec![None, None, None, Some(1.0), Some(1.0), Some(1.0)]);
+        let start: Vec<Option<f64>> = pth.df().column("start_pth_3").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(start[..3], [None, None, Some(1.0)]);
+        
+        assert!(FactorCalculator::calculate_price_to_high(&table, 0).is_err());
+    }
+    
+    #[test]
+    fn test_consistency_steady_vs_spike() {
+        let steady = single_stock_table((0..7).map(|i| 100.0 * 1.01f64.powi(i)).collect());
+        let result = FactorCalculator::calculate_consistency(&steady, 6, 2).unwrap();
+        assert_eq!(result.df().get_column_names(), &["date", "stock_A_consist_6_2"]);
+        assert_eq!(result.df().column("stock_A_consist_6_2").unwrap().null_count(), 6);
+        assert_eq!(last_value(&result, "stock_A_consist_6_2"), 1.0);
+        
+        // 整体上涨，但只有最后一个子区间靠一次跳涨为正
+        let spike = single_stock_table(vec![100.0, 99.0, 98.0, 97.0, 96.0, 95.0, 130.0]);
+        let result = FactorCalculator::calculate_consistency(&spike, 6, 2).unwrap();
+        assert!((last_value(&result, "stock_A_consist_6_2") - 1.0 / 3.0).abs() < 1e-12);
+        
+        assert!(FactorCalculator::calculate_consistency(&steady, 6, 4).is_err());
+        assert!(FactorCalculator::calculate_consistency(&steady, 6, 0).is_err());
+    }
+    
+    #[test]
+    fn test_momentum_reversal() {
+        let table = single_stock_table(vec![100.0, 110.0, 120.0, 90.0]);
+        let result = FactorCalculator::calculate_momentum_reversal(&table, 2, 1).unwrap();
+        assert_eq!(result.df().get_column_names(), &["date", "stock_A_mom_rev_2_1"]);
+        
+        let values: Vec<Option<f64>> = result.df().column("stock_A_mom_rev_2_1").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values[..3], [None; 3]);
+        // 3 期涨跌 -10%，最近 1 期 -25%：剔除后为 +15%
+        assert!((values[3].unwrap() - 0.15).abs() < 1e-12);
+        
+        assert!(FactorCalculator::calculate_momentum_reversal(&table, 0, 1).is_err());
+    }
+    
+    #[test]
+    fn test_momentum_rank() {
+        let dates: Vec<NaiveDate> = (1..=4).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[100.0, 110.0, 121.0, 133.1]),
+            Series::new("stock_B", &[100.0, 90.0, 81.0, 72.9]),
+            Series::new("stock_C", &[None, Some(100.0), Some(100.0), Some(110.0)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let result = FactorCalculator::calculate_momentum_rank(&table, 2).unwrap();
+        assert_eq!(
+            result.df().get_column_names(),
+            &["date", "stock_A_momrank_2", "stock_B_momrank_2", "stock_C_momrank_2"]
+        );
+        let column = |name: &str| -> Vec<Option<f64>> {
+            result.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        // 前 2 行窗口未满，不排名
+        assert_eq!(column("stock_A_momrank_2"), vec![None, None, Some(1.0), Some(1.0)]);
+        assert_eq!(column("stock_B_momrank_2"), vec![None, None, Some(0.5), Some(1.0 / 3.0)]);
+        // 第 3 行 2 期前的价格缺失，部分窗口不参与排名
+        assert_eq!(column("stock_C_momrank_2"), vec![None, None, None, Some(2.0 / 3.0)]);
+        
+        assert!(FactorCalculator::calculate_momentum_rank(&table, 0).is_err());
+    }
+    
+    #[test]
+    fn test_neutralize_against_size() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let sizes = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let noise = [0.3, -0.1, 0.4, -0.5, 0.2, -0.3];
+        let panel = |values: &dyn Fn(usize) -> [Option<f64>; 2]| -> WideTable {
+            let mut columns = vec![Series::new("date", dates.clone())];
+            columns.extend((0..6).map(|j| Series::new(&format!("s{}", j), values(j))));
+            WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap()
+        };
+        // 第 2 期 s5 的因子缺失；第 2 期规模在截面上相同，回归奇异
+        let factor = panel(&|j| [Some(2.0 * sizes[j] + noise[j]), (j != 5).then(|| 2.0 * sizes[j] + noise[j])]);
+        let size = panel(&|j| [Some(sizes[j]), Some(1.0)]);
+        
+        let neutral = FactorCalculator::neutralize(&factor, &[&size]).unwrap();
+        assert_eq!(neutral.stock_cols(), factor.stock_cols());
+        let residuals: Vec<f64> = (0..6)
+            .map(|j| neutral.df().column(&format!("s{}", j)).unwrap().f64().unwrap().get(0).unwrap())
+            .collect();
+        assert!(stats::pearson(&residuals, &sizes).unwrap().abs() < 1e-9);
+        assert!(residuals.iter().sum::<f64>().abs() < 1e-9);
+        
+        // 第 2 期退化为去均值，缺失的 s5 保持 null
+        let second: Vec<Option<f64>> = (0..6)
+            .map(|j| neutral.df().column(&format!("s{}", j)).unwrap().f64().unwrap().get(1))
+            .collect();
+        assert_eq!(second[5], None);
+        assert!(second.iter().flatten().sum::<f64>().abs() < 1e-9);
+        
+        let partial = single_stock_table(vec![1.0, 2.0]);
+        assert!(FactorCalculator::neutralize(&factor, &[&partial]).is_err());
+    }
+    
+    #[test]
+    fn test_cross_sectional_regression() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let size = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let value = [0.5, -1.0, 2.0, 0.0, 1.5, -0.5];
+        let panel = |values: &dyn Fn(usize) -> [Option<f64>; 2]| -> WideTable {
+            let mut columns = vec![Series::new("date", dates.clone())];
+            columns.extend((0..6).map(|j| Series::new(&format!("s{}", j), values(j))));
+            WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap()
+        };
+        // 第 1 期收益率由两个因子精确生成；第 2 期只有 3 只股票有效，样本不足
+        let returns = panel(&|j| [Some(0.01 + 0.02 * size[j] - 0.03 * value[j]), (j < 3).then_some(0.01)]);
+        let size_table = panel(&|j| [Some(size[j]), Some(size[j])]);
+        let value_table = panel(&|j| [Some(value[j]), Some(value[j])]);
+        
+        let result = FactorCalculator::cross_sectional_regression(
+            &returns,
+            &[("size", &size_table), ("value", &value_table)],
+        ).unwrap();
+        assert_eq!(result.get_column_names(), &["date", "size", "value", "r2"]);
+        let get = |name: &str, i: usize| result.column(name).unwrap().f64().unwrap().get(i);
+        assert!((get("size", 0).unwrap() - 0.02).abs() < 1e-9);
+        assert!((get("value", 0).unwrap() + 0.03).abs() < 1e-9);
+        assert!((get("r2", 0).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!((get("size", 1), get("r2", 1)), (None, None));
+        
+        // 完全共线的因子
+        let doubled = panel(&|j| [Some(2.0 * size[j]), Some(2.0 * size[j])]);
+        let err = FactorCalculator::cross_sectional_regression(&returns, &[("size", &size_table), ("size2", &doubled)])
+            .unwrap_err();
+        assert!(err.to_string().contains("秩亏"));
+        assert!(FactorCalculator::cross_sectional_regression(&returns, &[("r2", &size_table)]).is_err());
+        assert!(FactorCalculator::cross_sectional_regression(&returns, &[]).is_err());
+    }
+    
+    #[test]
+    fn test_orthogonalize() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let panel = |values: &dyn Fn(usize) -> [Option<f64>; 2]| -> WideTable {
+            let mut columns = vec![Series::new("date", dates.clone())];
+            columns.extend((0..8).map(|j| Series::new(&format!("s{}", j), values(j))));
+            WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap()
+        };
+        let x = |j: usize| j as f64;
+        let momentum = panel(&|j| [Some(x(j)), Some(x(j).sin())]);
+        let reversal = panel(&|j| [Some(0.5 * x(j) + (x(j) * 1.3).cos()), Some(x(j) * x(j))]);
+        // 第 2 期波动率全部缺失
+        let volatility = panel(&|j| [Some(x(j).sqrt() - 0.2 * x(j)), None]);
+        
+        let outputs = FactorCalculator::orthogonalize(&[&momentum, &reversal, &volatility]).unwrap();
+        assert_eq!(outputs.len(), 3);
+        assert!(outputs[0].df().equals_missing(momentum.df()));
+        
+        let cross_section = |table: &WideTable, i: usize| -> Vec<Option<f64>> {
+            (0..8).map(|j| table.df().column(&format!("s{}", j)).unwrap().f64().unwrap().get(i)).collect()
+        };
+        for i in 0..2 {
+            for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+                let (u, v) = (cross_section(&outputs[a], i), cross_section(&outputs[b], i));
+                if v.iter().all(Option::is_none) {
+                    continue;
+                }
+                let (u, v): (Vec<f64>, Vec<f64>) = u.into_iter().zip(v).map(|(p, q)| (p.unwrap(), q.unwrap())).unzip();
+                assert!(stats::pearson(&u, &v).unwrap().abs() < 1e-9);
+            }
+        }
+        assert!(cross_section(&outputs[2], 1).iter().all(Option::is_none));
+        
+        assert!(FactorCalculator::orthogonalize(&[]).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_information_ratio_and_tracking_error() {
+        let dates: Vec<NaiveDate> = (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let returns = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates),
+            // 超额收益 0.01, 0.03, -0.01, 0.01（第 3 行基准缺失被剔除）
+            Series::new("a", &[0.02, 0.04, 0.5, -0.02, 0.02]),
+            // 与基准完全相同：跟踪误差为 0
+            Series::new("b", &[0.01, 0.01, 0.3, -0.01, 0.01]),
+        ]).unwrap(), "date").unwrap();
+        let benchmark = Series::new("bench", &[Some(0.01), Some(0.01), None, Some(-0.01), Some(0.01)]);
+        
+        let ir = FactorCalculator::calculate_information_ratio(&returns, &benchmark, 4.0).unwrap();
+        let te = FactorCalculator::calculate_tracking_error(&returns, &benchmark, 4.0).unwrap();
+        assert_eq!((ir.name(), ir.len()), ("information_ratio", 2));
+        // std = sqrt(0.0008 / 3)，IR = 0.01 / std * 2 = sqrt(1.5)
+        let std = (0.0008f64 / 3.0).sqrt();
+        assert!((ir.f64().unwrap().get(0).unwrap() - 1.5f64.sqrt()).abs() < 1e-9);
+        assert!((te.f64().unwrap().get(0).unwrap() - 2.0 * std).abs() < 1e-12);
+        assert_eq!(ir.f64().unwrap().get(1), None);
+        assert!(te.f64().unwrap().get(1).unwrap().abs() < 1e-12);
+        
+        // 基准与宽表的行不对应
+        let short = Series::new("bench", &[0.01, 0.01, 0.01]);
+        assert!(FactorCalculator::calculate_information_ratio(&returns, &short, 4.0).is_err());
+        assert!(FactorCalculator::calculate_tracking_error(&returns, &short, 4.0).is_err());
+    }
+    
+    #[test]
+    fn test_rank_turnover() {
+        let dates: Vec<NaiveDate> = (1..=4).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[1.0, 2.0, 3.0, 3.0]),
+            Series::new("b", &[2.0, 3.0, 1.0, 1.0]),
+            Series::new("c", &[Some(3.0), Some(4.0), Some(2.0), None]),
+        ]).unwrap();
+        let factor = WideTable::new(df, "date").unwrap();
+        
+        let turnover = FactorCalculator::rank_turnover(&factor).unwrap();
+        assert_eq!(turnover.get_column_names(), &["date", "turnover"]);
+        let values: Vec<Option<f64>> = turnover.column("turnover").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values[..2], [None, Some(0.0)]);
+        // 第 3 期名次由 (1, 2, 3) 变为 (3, 1, 2)，百分位变化之和为 4/3
+        assert!((values[2].unwrap() - 4.0 / 9.0).abs() < 1e-12);
+        // 第 4 期只有 a、b 两只股票：百分位 (1, 1/3) -> (1, 1/2)，c 不参与
+        assert!((values[3].unwrap() - (0.5 - 1.0 / 3.0) / 2.0).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_return_entropy() {
+        // 前 4 期收益率恒为 +1%，后 4 期收益率在 [0%, 3%] 上均匀分布
+        let mut prices = vec![100.0];
+        for r in [0.01, 0.01, 0.01, 0.01, 0.0, 0.01, 0.02, 0.03] {
+            let last = *prices.last().unwrap();
+            prices.push(last * (1.0 + r));
+        }
+        let table = single_stock_table(prices);
+        let result = FactorCalculator::calculate_return_entropy(&table, 4, 4).unwrap();
+        assert_eq!(result.df().get_column_names(), &["date", "stock_A_entropy_4_4"]);
+        
+        let values: Vec<Option<f64>> = result.df().column("stock_A_entropy_4_4").unwrap().f64().unwrap().into_iter().collect();
+        // 前 4 行窗口未满；恒定收益率的窗口没有波动，熵视为未定义
+        assert_eq!(values[..5], [None; 5]);
+        // 均匀分布：每个区间一个收益率，熵为 ln(4)
+        assert!((values[8].unwrap() - 4f64.ln()).abs() < 1e-12);
+        // 三个 +1% 与一个 0%：集中在一个区间，熵更低
+        let concentrated = values[5].unwrap();
+        assert!(concentrated > 0.0 && concentrated < values[8].unwrap());
+        
+        // 收益率恒为 10%，只有浮点误差，同样视为没有波动
+        let table = single_stock_table(vec![100.0, 110.0, 121.0, 133.1, 146.41, 161.051]);
+        let result = FactorCalculator::calculate_return_entropy(&table, 4, 4).unwrap();
+        assert_eq!(result.df().column("stock_A_entropy_4_4").unwrap().null_count(), 6);
+        
+        assert!(FactorCalculator::calculate_return_entropy(&table, 4, 1).is_err());
+    }
+    
+    #[test]
+    fn test_seasonality_monday_effect() {
+        // 2024-01-01 为周一：每个周一收益 +1%，其余日期为 0
+        let mut prices = vec![100.0];
+        for i in 1..22 {
+            let last = prices[i - 1];
+            prices.push(if i % 7 == 0 { last * 1.01 } else { last });
+        }
+        let table = single_stock_table(prices);
+        
+        let result = FactorCalculator::calculate_seasonality(&table, SeasonGroup::Weekday).unwrap();
+        assert_eq!(result.df().get_column_names(), &["date", "stock_A_seas_wd"]);
+        let values: Vec<Option<f64>> = result.df().column("stock_A_seas_wd").unwrap().f64().unwrap().into_iter().collect();
+        // 01-01 没有收益率，01-08 之前没有周一的有效收益率
+        assert_eq!(values[0], None);
+        assert_eq!(values[7], None);
+        assert!((values[14].unwrap() - 0.01).abs() < 1e-12);
+        assert!((values[21].unwrap() - 0.01).abs() < 1e-12);
+        // 周二：01-02 无历史，之后均值为 0
+        assert_eq!(values[1], None);
+        assert_eq!(values[8], Some(0.0));
+        
+        // 同月份：01-22 之前 20 个有效收益率中有 2 个周一
+        let monthly = FactorCalculator::calculate_seasonality(&table, SeasonGroup::Month).unwrap();
+        assert!((last_value(&monthly, "stock_A_seas_m") - 0.02 / 20.0).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_fama_french_construction() {
+        // 月频数据 2023-01 至 2024-12，2023 年 6 月末第一次调仓
+        let dates: Vec<NaiveDate> = (0..24)
+            .map(|m| NaiveDate::from_ymd_opt(2023 + m / 12, (m % 12 + 1) as u32, 1).unwrap())
+            .collect();
+        let caps = [1.0, 2.0, 3.0, 10.0, 20.0, 30.0];
+        let btms = [0.1, 0.5, 0.9, 0.1, 0.5, 0.9];
+        // 小市值每期 2%、大市值 1%，高账面市值比再加 0.5%、低账面市值比减 0.5%
+        let rets: Vec<f64> = (0..6)
+            .map(|i| if i < 3 { 0.02 } else { 0.01 } + [-0.005, 0.0, 0.005][i % 3])
+            .collect();
+        let panel = |values: &[f64]| -> WideTable {
+            let mut columns = vec![Series::new("date", dates.clone())];
+            columns.extend(values.iter().enumerate().map(|(i, v)| Series::new(&format!("s{}", i), vec![*v; 24])));
+            WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap()
+        };
+        let (returns, market_cap, book_to_market) = (panel(&rets), panel(&caps), panel(&btms));
+        
+        let ff = FactorCalculator::construct_fama_french(&returns, &market_cap, &book_to_market).unwrap();
+        assert_eq!(ff.get_column_names(), &["date", "smb", "hml"]);
+        for name in ["smb", "hml"] {
+            let values = ff.column(name).unwrap().f64().unwrap();
+            // 2023 年 1 月至 6 月（含调仓当期）为 null
+            assert_eq!(values.null_count(), 6);
+            assert!(values.into_iter().flatten().all(|v| (v - 0.01).abs() < 1e-12));
+        }
+        
+        // 单变量排序：市值最低 30% 为 s0、s1，最高 30% 为 s4、s5
+        let smb = FactorCalculator::construct_smb(&returns, &market_cap).unwrap();
+        assert_eq!(smb.name(), "smb");
+        assert!((smb.f64().unwrap().get(23).unwrap() - 0.005).abs() < 1e-12);
+        // 账面市值比最高 30% 为 s2、s5，最低 30% 为 s0、s3
+        let hml = FactorCalculator::construct_hml(&returns, &book_to_market).unwrap();
+        assert!((hml.f64().unwrap().get(23).unwrap() - 0.01).abs() < 1e-12);
+        assert_eq!(hml.f64().unwrap().get(5), None);
+        assert!(hml.f64().unwrap().get(6).is_some());
+        
+        // 特征表按时间列连接：行顺序不同、时间更多都不影响结果
+        let extra = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut columns = vec![Series::new("date", [&[extra], &dates[..]].concat())];
+        columns.extend(caps.iter().enumerate().map(|(i, v)| Series::new(&format!("s{}", i), vec![*v; 25])));
+        let shuffled = DataFrame::new(columns).unwrap().reverse();
+        let shuffled = WideTable::new(shuffled, "date").unwrap();
+        let aligned = FactorCalculator::construct_smb(&returns, &shuffled).unwrap();
+        assert!(aligned.equals_missing(&smb));
+        
+        // 时间列类型不同（Date 与 Datetime）时不按字符串匹配，直接报错
+        let as_datetime = market_cap
+            .df()
+            .clone()
+            .lazy()
+            .with_column(col("date").cast(DataType::Datetime(TimeUnit::Milliseconds, None)))
+            .collect()
+            .unwrap();
+        let as_datetime = WideTable::new(as_datetime, "date").unwrap();
+        let Err(e) = FactorCalculator::construct_smb(&returns, &as_datetime) else { panic!("时间列类型不同时应报错") };
+        assert!(matches!(e.downcast_ref::<WideTableError>(), Some(WideTableError::SchemaMismatch(_))));
+    }
+    
+    #[test]
+    fn test_risk_adj_momentum_matches_two_step() {
+        let prices = vec![100.0, 102.0, 101.0, 104.0, 103.0, 107.0, 106.0, 110.0];
+        let table = single_stock_table(prices);
+        
+        let ramom = FactorCalculator::calculate_risk_adj_momentum(&table, 3, 4).unwrap();
+        assert_eq!(ramom.df().get_column_names(), &["date", "stock_A_ramom_3_4"]);
+        let values: Vec<Option<f64>> = ramom.df().column("stock_A_ramom_3_4").unwrap().f64().unwrap().into_iter().collect();
+        // 波动率需要 4 个收益率，前 4 行为 null
+        assert_eq!(values[..4], [None; 4]);
+        
+        let momentum = table.momentum(3).unwrap();
+        let volatility = table.volatility(4).unwrap();
+        let column = |t: &WideTable, name: &str| -> Vec<Option<f64>> {
+            t.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        let (mom, vol) = (column(&momentum, "stock_A_momentum_3"), column(&volatility, "stock_A_volatility_4"));
+        for i in 4..values.len() {
+            let manual = mom[i].unwrap() / vol[i].unwrap();
+            assert!((values[i].unwrap() - manual).abs() < 1e-9);
+        }
+        
+        // 价格不变时波动率为 0，结果为 null
+        let flat = FactorCalculator::calculate_risk_adj_momentum(&single_stock_table(vec![10.0; 6]), 2, 3).unwrap();
+        assert_eq!(flat.df().column("stock_A_ramom_2_3").unwrap().null_count(), 6);
+    }
+    
+    #[test]
+    fn test_leadlag_exact_lagged_copy() {
+        let bench_returns: Vec<f64> = (0..12).map(|i| 0.01 * ((i * 7 % 5) as f64 - 2.0)).collect();
+        // 股票收益率恰好是 2 期前的基准收益率
+        let mut bench = vec![100.0];
+        let mut stock = vec![50.0];
+        for (t, r) in bench_returns.iter().enumerate() {
+            let stock_r = if t >= 2 { bench_returns[t - 2] } else { 0.003 };
+            bench.push(bench.last().unwrap() * (1.0 + r));
+            stock.push(stock.last().unwrap() * (1.0 + stock_r));
+        }
+        let dates: Vec<NaiveDate> = (0..bench.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("index", bench),
+            Series::new("stock_A", stock),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let lagged = FactorCalculator::calculate_leadlag(&table, "index", 5, 2).unwrap();
+        assert_eq!(lagged.df().get_column_names(), &["date", "stock_A_leadlag_5_2"]);
+        assert!((last_value(&lagged, "stock_A_leadlag_5_2") - 1.0).abs() < 1e-9);
+        
+        // 不滞后时相关性明显低于 1
+        let same_day = FactorCalculator::calculate_leadlag(&table, "index", 5, 0).unwrap();
+        assert!(last_value(&same_day, "stock_A_leadlag_5_0") < 0.9);
+        
+        // 交换角色：基准滞后于股票 2 期，用负的 lag 识别股票领先
+        let reversed = WideTable::new(
+            table.df().select(["date", "stock_A", "index"]).unwrap().rename("stock_A", "bench").unwrap().clone(),
+            "date",
+        ).unwrap();
+        let leading = FactorCalculator::calculate_leadlag(&reversed, "bench", 5, -2).unwrap();
+        let values = leading.df().column("index_leadlag_5_-2").unwrap().f64().unwrap();
+        assert!((values.get(8).unwrap() - 1.0).abs() < 1e-9);
+        // 最后 2 行需要之后的基准收益率，为 null
+        assert_eq!(values.get(12), None);
+    }
+    
+    #[test]
+    fn test_cumulative_returns() {
+        let prices = single_stock_table(vec![100.0, 110.0, 99.0]);
+        let returns = prices.pct_change(1).unwrap().df().select(["date", "stock_A_pct_change_1"]).unwrap();
+        let returns = WideTable::new(returns, "date").unwrap();
+        
+        let cumret = FactorCalculator::calculate_cumulative_returns(&returns, 1.0, ReturnScale::Percent).unwrap();
+        assert_eq!(cumret.df().get_column_names(), &["date", "stock_A_pct_change_1_cumret"]);
+        let s = cumret.df().column("stock_A_pct_change_1_cumret").unwrap().f64().unwrap().clone();
+        // 首期收益为 null，净值保持初始值
+        assert_eq!(s.get(0), Some(1.0));
+        assert!((s.get(2).unwrap() - 0.99).abs() < 1e-12);
+        
+        // 小数收益率得到相同的净值
+        let fraction = prices.pct_change_fraction(1).unwrap().df().select(["date", "stock_A_return_1"]).unwrap();
+        let fraction = WideTable::new(fraction, "date").unwrap();
+        let cumret = FactorCalculator::calculate_cumulative_returns(&fraction, 1.0, ReturnScale::Fraction).unwrap();
+        let s = cumret.df().column("stock_A_return_1_cumret").unwrap().f64().unwrap().clone();
+        assert!((s.get(2).unwrap() - 0.99).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_cmo_all_gains() {
+        let table = single_stock_table(vec![10.0, 11.0, 12.5, 13.0, 15.0]);
+        
+        let cmo = FactorCalculator::calculate_cmo(&table, 3).unwrap();
+        let s = cmo.df().column("stock_A_cmo_3").unwrap().f64().unwrap().clone();
+        assert_eq!(s.null_count(), 3);
+        assert_eq!(s.get(3), Some(100.0));
+        assert_eq!(s.get(4), Some(100.0));
+        
+        let flat = FactorCalculator::calculate_cmo(&single_stock_table(vec![10.0; 5]), 3).unwrap();
+        assert_eq!(flat.df().column("stock_A_cmo_3").unwrap().null_count(), 5);
+    }
+    
+    #[test]
+    fn test_keltner_matches_reference() {
+        // 参考值由独立的 Python 实现算出：中轨同 pandas `ewm(span, adjust=False)`，ATR 同 pandas-ta 的 RMA 约定
+        let close: Vec<f64> = (0..40)
+            .map(|i| 100.0 + 8.0 * (i as f64 / 4.0).sin() + 0.3 * i as f64)
+            .collect();
+        let high: Vec<f64> = close.iter().enumerate().map(|(i, c)| c + 1.0 + (i % 3) as f64 * 0.5).collect();
+        let low: Vec<f64> = close.iter().enumerate().map(|(i, c)| c - 1.0 - (i % 4) as f64 * 0.3).collect();
+        let set = PriceSet::new(
+            single_stock_table(high),
+            single_stock_table(low),
+            single_stock_table(close),
+        ).unwrap();
+        
+        let atr = FactorCalculator::calculate_atr(&set, 10).unwrap();
+        assert!((last_value(&atr, "stock_A_atr_10") - 3.2135715415220254).abs() < 1e-9);
+        
+        let keltner = FactorCalculator::calculate_keltner(&set, 10, 2.0).unwrap();
+        let s = keltner.df().column("stock_A_keltner_pos_10").unwrap().f64().unwrap().clone();
+        assert_eq!(s.null_count(), 10);
+        assert!((s.get(10).unwrap() - 0.5388893369387722).abs() < 1e-9);
+        assert!((s.get(39).unwrap() - 0.23827199607861638).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_annualized_return() {
+        let dates: Vec<NaiveDate> = (1..=3).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[Some(0.1), Some(f64::NAN), Some(-0.05)]),
+            Series::new("stock_B", &[Some(0.2), Some(-1.0), Some(0.1)]),
+            Series::new("stock_C", &[Some(0.5), Some(-1.5), None]),
+            Series::new("stock_D", &[None::<f64>, None, None]),
+        ]).unwrap();
+        let returns = WideTable::new(df, "date").unwrap();
+        
+        let result = FactorCalculator::annualized_return(&returns, 12.0).unwrap();
+        assert_eq!(result.get_column_names(), &["stock", "annualized_return"]);
+        let values: Vec<Option<f64>> = result.column("annualized_return").unwrap().f64().unwrap().into_iter().collect();
+        // 2 个有效月收益率，净值 1.1 * 0.95 = 1.045，年化为 1.045^6 - 1
+        assert!((values[0].unwrap() - (1.045f64.powi(6) - 1.0)).abs() < 1e-12);
+        // 本金亏光与净值为负时没有几何年化
+        assert_eq!(values[1..], [None, None, None]);
+        
+        assert!(FactorCalculator::annualized_return(&returns, 0.0).is_err());
+    }
+    
+    #[test]
+    fn test_var_and_cvar() {
+        // -0.05, -0.04, ..., 0.04，共 10 个收益率
+        let returns = single_stock_table((0..10).map(|i| (i as f64 - 5.0) / 100.0).collect());
+        let get = |series: Series, name: &str| {
+            assert_eq!(series.name(), name);
+            series.f64().unwrap().get(0).unwrap()
+        };
+        
+        // 0.1 分位数：位置 0.9，在 -0.05 与 -0.04 之间插值
+        let var = FactorCalculator::calculate_var(&returns, 0.9, VarMethod::Historical).unwrap();
+        assert!((get(var, "var") - 0.041).abs() < 1e-12);
+        let cvar = FactorCalculator::calculate_cvar(&returns, 0.9, VarMethod::Historical).unwrap();
+        assert!((get(cvar, "cvar") - 0.05).abs() < 1e-12);
+        
+        // 均值为 -0.005，参数法 VaR = -(mean + z * std)，z 为 0.05 分位数
+        let std = ((0..10).map(|i| ((i as f64 - 4.5) / 100.0).powi(2)).sum::<f64>() / 9.0).sqrt();
+        let var = FactorCalculator::calculate_var(&returns, 0.95, VarMethod::Parametric).unwrap();
+        assert!((get(var, "var") - (0.005 + 1.6448536269514726 * std)).abs() < 1e-9);
+        
+        // 对称分布的偏度为 0，Cornish-Fisher 只做峰度修正
+        let var = FactorCalculator::calculate_var(&returns, 0.95, VarMethod::CornishFisher).unwrap();
+        assert!(get(var, "var") > 0.0);
+        
+        // 左偏且厚尾的样本：偏度 -1.5909，超额峰度 1.1561，修正后的 z 为 -2.0262
+        let skewed = [0.01, 0.02, 0.01, 0.015, -0.08, 0.012, 0.018, -0.05];
+        let left = single_stock_table(skewed.to_vec());
+        let parametric = get(FactorCalculator::calculate_var(&left, 0.95, VarMethod::Parametric).unwrap(), "var");
+        let cornish_fisher = get(FactorCalculator::calculate_var(&left, 0.95, VarMethod::CornishFisher).unwrap(), "var");
+        assert!((parametric - 0.06760744422043649).abs() < 1e-9);
+        assert!((cornish_fisher - 0.0819776193856354).abs() < 1e-9);
+        // 左尾更厚，修正后的 VaR 高于正态假设
+        assert!(cornish_fisher > parametric);
+        
+        // 镜像得到右偏样本，修正方向相反
+        let right = single_stock_table(skewed.iter().map(|r| -r).collect());
+        let parametric = get(FactorCalculator::calculate_var(&right, 0.95, VarMethod::Parametric).unwrap(), "var");
+        let cornish_fisher = get(FactorCalculator::calculate_var(&right, 0.95, VarMethod::CornishFisher).unwrap(), "var");
+        assert!((cornish_fisher - 0.03664638463689376).abs() < 1e-9);
+        assert!(cornish_fisher < parametric);
+        
+        assert!(FactorCalculator::calculate_var(&returns, 1.0, VarMethod::Historical).is_err());
+    }
+    
+    #[test]
+    fn test_amihud() {
+        let returns = single_stock_table(vec![0.01, -0.02, 0.03, 0.0]);
+        let volume = single_stock_table(vec![100.0, 200.0, 0.0, 50.0]);
+        
+        let result = FactorCalculator::calculate_amihud(&returns, &volume, 2, false).unwrap();
+        assert_eq!(result.df().get_column_names(), &["date", "stock_A_amihud_2"]);
+        let values: Vec<Option<f64>> = result.df().column("stock_A_amihud_2").unwrap().f64().unwrap().into_iter().collect();
+        // 第 3 期成交量为 0，包含它的窗口为 null
+        assert_eq!(values[0], None);
+        assert!((values[1].unwrap() - (0.0001 + 0.0001) / 2.0).abs() < 1e-15);
+        assert_eq!(values[2..], [None, None]);
+        
+        let logged = FactorCalculator::calculate_amihud(&returns, &volume, 2, true).unwrap();
+        let expected = (1.0 + values[1].unwrap()).ln();
+        let logged: Vec<Option<f64>> = logged.df().column("stock_A_amihud_2").unwrap().f64().unwrap().into_iter().collect();
+        assert!((logged[1].unwrap() - expected).abs() < 1e-15);
+        
+        let shorter = single_stock_table(vec![100.0, 200.0, 50.0]);
+        assert!(FactorCalculator::calculate_amihud(&returns, &shorter, 2, false).is_err());
+    }
+    
+    #[test]
+    fn test_turnover_with_share_change() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let volume = WideTable::new(DataFrame::new(vec![
+            Series::new("date", [1, 2, 3, 5, 6, 7].into_iter().map(date).collect::<Vec<_>>()),
+            Series::new("stock_A", &[100i64, 100, 100, 100, 100, 100]),
+        ]).unwrap(), "date").unwrap();
+        // 股本在成交量表之外的 1 月 4 日由 1000 变为 500
+        let shares = WideTable::new(DataFrame::new(vec![
+            Series::new("date", &[date(1), date(4)]),
+            Series::new("stock_A", &[1000.0, 500.0]),
+        ]).unwrap(), "date").unwrap();
+        
+        let turn = FactorCalculator::calculate_turnover(&volume, &shares, 2).unwrap();
+        let s: Vec<Option<f64>> = turn.df().column("stock_A_turn_2").unwrap().f64().unwrap().into_iter()
+            .map(|v| v.map(|v| (v * 1e9).round() / 1e9))
+            .collect();
+        assert_eq!(s, vec![None, Some(0.1), Some(0.1), Some(0.15), Some(0.2), Some(0.2)]);
+    }
+    
+    #[test]
+    fn test_max_and_min_return() {
+        // 第 3 期单日上涨 20%，其余各期 ±1%
+        let table = single_stock_table(vec![100.0, 101.0, 121.2, 119.988, 121.18788]);
+        
+        let max_ret = FactorCalculator::calculate_max_return(&table, 3).unwrap();
+        let s = max_ret.df().column("stock_A_maxret_3").unwrap().f64().unwrap().clone();
+        assert_eq!(s.null_count(), 3);
+        assert!((s.get(3).unwrap() - 0.2).abs() < 1e-12);
+        assert!((s.get(4).unwrap() - 0.2).abs() < 1e-12);
+        
+        let min_ret = FactorCalculator::calculate_min_return(&table, 3).unwrap();
+        assert!((last_value(&min_ret, "stock_A_minret_3") + 0.01).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_adx_matches_reference() {
+        // 参考值由 pandas-ta 约定（RMA = alpha 为 1/window 的指数平滑）的独立实现算出
+        let n = 50;
+        let close: Vec<f64> = (0..n)
+            .map(|i| 100.0 + 8.0 * (i as f64 / 4.0).sin() + 0.3 * i as f64)
+            .collect();
+        let high: Vec<f64> = close.iter().enumerate().map(|(i, c)| c + 1.0 + (i % 3) as f64 * 0.5).collect();
+        let low: Vec<f64> = close.iter().enumerate().map(|(i, c)| c - 1.0 - (i % 4) as f64 * 0.3).collect();
+        let set = PriceSet::new(
+            single_stock_table(high),
+            single_stock_table(low),
+            single_stock_table(close),
+        ).unwrap();
+        
+        let adx = FactorCalculator::calculate_adx(&set, 14).unwrap();
+        assert_eq!(
+            adx.df().get_column_names(),
+            &["date", "stock_A_plus_di_14", "stock_A_minus_di_14", "stock_A_adx_14"]
+        );
+        assert!((last_value(&adx, "stock_A_plus_di_14") - 26.92370890364225).abs() < 1e-9);
+        assert!((last_value(&adx, "stock_A_minus_di_14") - 15.763939020616446).abs() < 1e-9);
+        assert!((last_value(&adx, "stock_A_adx_14") - 26.778713490003952).abs() < 1e-9);
+        assert_eq!(adx.df().column("stock_A_adx_14").unwrap().null_count(), 27);
     }
 }
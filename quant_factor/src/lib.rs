@@ -1,5 +1,15 @@
+pub mod calendar;
+pub mod error;
 pub mod factor;
+pub mod pipeline;
+pub mod price_set;
+mod regression;
+mod stats;
 pub mod wide_table;
 
+pub use calendar::*;
+pub use error::*;
 pub use factor::*;
+pub use pipeline::*;
+pub use price_set::*;
 pub use wide_table::*;
@@ -0,0 +1,291 @@
+use crate::wide_table::WideTable;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+/// 因子计算步骤
+pub type FactorStep = Box<dyn Fn(&WideTable) -> Result<WideTable>>;
+
+/// 流水线中的一个已注册步骤
+struct PipelineStep {
+    name: String,
+    f: FactorStep,
+    /// 依赖的上游步骤名，为 None 时以源表为输入
+    depends_on: Option<String>,
+}
+
+/// 因子计算流水线
+/// 
+/// 按注册顺序执行各步骤，默认每个步骤都以源表为输入，
+/// 通过 [`FactorPipeline::depends_on`] 可以让步骤改为以上游步骤的输出为输入。
+/// 步骤只能拿到输入表的不可变引用，互不影响。
+/// 步骤返回 `anyhow::Result`，`WideTable` 方法返回的 [`WideTableError`](crate::WideTableError)
+/// 可用 `Ok(...?)` 转换。
+/// 
+/// ```ignore
+/// let mut pipeline = FactorPipeline::new();
+/// pipeline
+///     .add_step("returns", Box::new(|t| Ok(t.pct_change_fraction(1)?)))
+///     .add_step("momentum", Box::new(|t| Ok(t.momentum(20)?)))
+///     .add_step("bias", Box::new(|t| FactorCalculator::calculate_bias(t, 5)))
+///     .depends_on("momentum");
+/// let results = pipeline.run(&table)?;
+/// ```
+#[derive(Default)]
+pub struct FactorPipeline {
+    steps: Vec<PipelineStep>,
+    /// 在注册任何步骤之前调用 `depends_on` 时记录的依赖名，`run` 时报错
+    orphan_dependency: Option<String>,
+}
+
+impl FactorPipeline {
+    /// 创建空流水线
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 注册一个步骤
+    /// 
+    /// # Arguments
+    /// * `name` - 步骤名，也是 `run` 结果中的键
+    /// * `f` - 步骤的计算函数
+    pub fn add_step(&mut self, name: &str, f: FactorStep) -> &mut Self {
+        self.steps.push(PipelineStep {
+            name: name.to_string(),
+            f,
+            depends_on: None,
+        });
+        self
+    }
+    
+    /// 注册一个以上一步骤输出为输入的步骤，用于串联多个阶段
+    /// 
+    /// 等价于 `add_step(name, stage)` 后再 `depends_on(上一步骤名)`；
+    /// 流水线为空时以源表为输入。配合 [`stages`] 中的内置阶段和 [`FactorPipeline::run_final`] 使用。
+    /// 阶段是闭包，无法从中得到名字，步骤名按注册位置自动生成为 `stage_{序号}`（从 0 开始），
+    /// 即 `run` 结果中的键；需要自定义步骤名时使用 [`FactorPipeline::add_step`]。
+    /// 
+    /// # Arguments
+    /// * `stage` - 步骤的计算函数
+    pub fn add(&mut self, stage: FactorStep) -> &mut Self {
+        let previous = self.steps.last().map(|step| step.name.clone());
+        let name = format!("stage_{}", self.steps.len());
+        self.add_step(&name, stage);
+        match previous {
+            Some(previous) => self.depends_on(&previous),
+            None => self,
+        }
+    }
+    
+    /// 指定最近注册的步骤以 `step_name` 步骤的输出为输入
+    /// 
+    /// 依赖的步骤必须先于当前步骤注册，否则 `run` 时报错。
+    /// 尚未注册任何步骤时调用不会 panic，而是记录下来，由 `run` 返回错误。
+    pub fn depends_on(&mut self, step_name: &str) -> &mut Self {
+        match self.steps.last_mut() {
+            Some(step) => step.depends_on = Some(step_name.to_string()),
+            None => self.orphan_dependency = Some(step_name.to_string()),
+        }
+        self
+    }
+    
+    /// 按注册顺序执行所有步骤
+    /// 
+    /// # Arguments
+    /// * `source` - 源宽表
+    /// 
+    /// # Returns
+    /// 以步骤名为键的结果表
+    /// 
+    /// # Errors
+    /// 步骤名重复、依赖的步骤不存在、在注册步骤之前调用了 `depends_on`，
+    /// 或任一步骤失败时返回错误；步骤失败时保留原始错误，可用 `downcast_ref` 取出
+    pub fn run(&self, source: &WideTable) -> Result<HashMap<String, WideTable>> {
+        if let Some(dep) = &self.orphan_dependency {
+            return Err(anyhow::anyhow!("depends_on(\"{}\") 必须在 add_step 之后调用", dep));
+        }
+        
+        let mut results: HashMap<String, WideTable> = HashMap::new();
+        
+        for step in &self.steps {
+            if results.contains_key(&step.name) {
+                return Err(anyhow::anyhow!("步骤名 '{}' 重复", step.name));
+            }
+            
+            let input = match &step.depends_on {
+                Some(dep) => results.get(dep).ok_or_else(|| {
+                    anyhow::anyhow!("步骤 '{}' 依赖的步骤 '{}' 不存在或尚未执行", step.name, dep)
+                })?,
+                None => source,
+            };
+            
+            let output = (step.f)(input).with_context(|| format!("步骤 '{}' 执行失败", step.name))?;
+            results.insert(step.name.clone(), output);
+        }
+        
+        Ok(results)
+    }
+    
+    /// 执行所有步骤，只返回最后注册的步骤的输出
+    /// 
+    /// # Errors
+    /// 流水线为空或任一步骤失败时返回错误
+    pub fn run_final(&self, source: &WideTable) -> Result<WideTable> {
+        let last = self
+            .steps
+            .last()
+            .ok_or_else(|| anyhow::anyhow!("流水线中没有任何步骤"))?;
+        let mut results = self.run(source)?;
+        Ok(results.remove(&last.name).expect("run 的结果包含所有步骤"))
+    }
+}
+
+/// 内置的流水线阶段
+/// 
+/// ```ignore
+/// let mut pipeline = FactorPipeline::new();
+/// pipeline
+///     .add(stages::momentum(20))
+///     .add(stages::winsorize(0.05, 0.95))
+///     .add(stages::zscore());
+/// let factor = pipeline.run_final(&table)?;
+/// ```
+pub mod stages {
+    use super::FactorStep;
+    use crate::wide_table::{WideTable, WinsorMethod};
+    use polars::prelude::*;
+    
+    /// 动量阶段：[`WideTable::momentum`]，只保留时间列与 `{code}_momentum_{periods}` 列，
+    /// 使后续的截面阶段不会混入价格与收益率列
+    pub fn momentum(periods: i32) -> FactorStep {
+        Box::new(move |t| {
+            let suffix = format!("_momentum_{}", periods);
+            let mut exprs = vec![col(t.time_col())];
+            exprs.extend(t.stock_cols().iter().map(|code| col(&format!("{}{}", code, suffix))));
+            Ok(WideTable::from_lazy(t.momentum(periods)?.lazy().select(exprs), t.time_col())?)
+        })
+    }
+    
+    /// 截面标准化阶段：[`WideTable::zscore_cross_section`]
+    pub fn zscore() -> FactorStep {
+        Box::new(|t| Ok(t.zscore_cross_section()?))
+    }
+    
+    /// 截面缩尾阶段：[`WideTable::winsorize_cross_section`]
+    pub fn winsorize(lower: f64, upper: f64) -> FactorStep {
+        Box::new(move |t| Ok(t.winsorize_cross_section(lower, upper)?))
+    }
+    
+    /// 按指定方法去极值的阶段：[`WideTable::winsorize`]
+    pub fn winsorize_with(method: WinsorMethod) -> FactorStep {
+        Box::new(move |t| Ok(t.winsorize(method)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use polars::prelude::*;
+    
+    #[test]
+    fn test_pipeline_depends_on() {
+        let dates: Vec<NaiveDate> = (0..4)
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", vec![10.0, 11.0, 12.0, 13.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let mut pipeline = FactorPipeline::new();
+        pipeline
+            .add_step("returns", Box::new(|t| Ok(t.pct_change_fraction(1)?)))
+            .add_step("sma", Box::new(|t| Ok(t.sma(2)?)))
+            .add_step("returns_sma", Box::new(|t| Ok(t.sma(2)?)))
+            .depends_on("returns");
+        let results = pipeline.run(&table).unwrap();
+        
+        assert!(results["sma"].df().column("stock_A_sma_2").is_ok());
+        assert!(results["returns_sma"].df().column("stock_A_return_1_sma_2").is_ok());
+        
+        pipeline.add_step("bad", Box::new(|t| Ok(t.sma(2)?))).depends_on("missing");
+        assert!(pipeline.run(&table).is_err());
+        
+        // 没有步骤时调用 depends_on 不会 panic，而是在 run 时报错
+        let mut empty = FactorPipeline::new();
+        empty.depends_on("returns");
+        assert!(empty.run(&table).is_err());
+    }
+    
+    #[test]
+    fn test_pipeline_step_error_keeps_source() {
+        let dates: Vec<NaiveDate> = (0..3)
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", vec![10.0, 11.0, 12.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let mut pipeline = FactorPipeline::new();
+        pipeline.add_step("bad_sma", Box::new(|t| Ok(t.sma(0)?)));
+        let Err(err) = pipeline.run(&table) else {
+            panic!("sma(0) 应当失败");
+        };
+        
+        assert!(err.to_string().contains("bad_sma"));
+        assert!(matches!(
+            err.downcast_ref::<crate::WideTableError>(),
+            Some(crate::WideTableError::InvalidArgument(_))
+        ));
+    }
+    
+    #[test]
+    fn test_pipeline_stages_match_manual() {
+        let dates: Vec<NaiveDate> = (0..5)
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", vec![10.0, 11.0, 12.0, 13.0, 14.0]),
+            Series::new("b", vec![20.0, 19.0, 21.0, 20.0, 22.0]),
+            Series::new("c", vec![5.0, 5.5, 6.5, 6.0, 9.0]),
+            Series::new("d", vec![8.0, 7.0, 6.0, 5.0, 4.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let mut pipeline = FactorPipeline::new();
+        pipeline
+            .add(stages::momentum(2))
+            .add(stages::winsorize(0.1, 0.9))
+            .add(stages::zscore());
+        let factor = pipeline.run_final(&table).unwrap();
+        assert!(pipeline.run(&table).unwrap()["stage_2"].df().equals_missing(factor.df()));
+        
+        let momentum = table.momentum(2).unwrap();
+        let momentum = WideTable::new(
+            momentum.df().select(["date", "a_momentum_2", "b_momentum_2", "c_momentum_2", "d_momentum_2"]).unwrap(),
+            "date",
+        ).unwrap();
+        let manual = momentum
+            .winsorize_cross_section(0.1, 0.9)
+            .unwrap()
+            .zscore_cross_section()
+            .unwrap();
+        assert!(factor.df().equals_missing(manual.df()));
+        
+        // 每行 z-score 的均值为 0
+        let last_row: f64 = ["a", "b", "c", "d"]
+            .iter()
+            .map(|code| {
+                let s = factor.df().column(&format!("{}_momentum_2", code)).unwrap();
+                s.f64().unwrap().get(s.len() - 1).unwrap()
+            })
+            .sum();
+        assert!(last_row.abs() < 1e-9);
+        
+        assert!(FactorPipeline::new().run_final(&table).is_err());
+    }
+}
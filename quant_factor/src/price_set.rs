@@ -0,0 +1,90 @@
+use crate::wide_table::WideTable;
+use polars::prelude::*;
+use anyhow::Result;
+
+/// 同一组股票的 high / low / close 宽表集合
+/// 
+/// 三张表的时间列名和股票列必须一致，行可以乱序，计算时按时间对齐
+#[derive(Clone)]
+pub struct PriceSet {
+    high: WideTable,
+    low: WideTable,
+    close: WideTable,
+}
+
+impl PriceSet {
+    /// 创建价格集合
+    /// 
+    /// # Arguments
+    /// * `high` - 最高价宽表
+    /// * `low` - 最低价宽表
+    /// * `close` - 收盘价宽表
+    pub fn new(high: WideTable, low: WideTable, close: WideTable) -> Result<Self> {
+        let time_col = close.time_col();
+        let codes = close.stock_cols();
+        
+        for (name, table) in [("high", &high), ("low", &low)] {
+            if table.time_col() != time_col {
+                return Err(anyhow::anyhow!(
+                    "{} 表的时间列 '{}' 与 close 表的 '{}' 不一致",
+                    name,
+                    table.time_col(),
+                    time_col
+                ));
+            }
+            if table.stock_cols() != codes {
+                return Err(anyhow::anyhow!("{} 表的股票列与 close 表不一致", name));
+            }
+        }
+        
+        Ok(Self { high, low, close })
+    }
+    
+    /// 获取最高价宽表
+    pub fn high(&self) -> &WideTable {
+        &self.high
+    }
+    
+    /// 获取最低价宽表
+    pub fn low(&self) -> &WideTable {
+        &self.low
+    }
+    
+    /// 获取收盘价宽表
+    pub fn close(&self) -> &WideTable {
+        &self.close
+    }
+    
+    /// 按时间对齐三张表，返回按时间排序的 LazyFrame
+    /// 
+    /// 列为时间列以及每只股票的 `__high_{code}`、`__low_{code}`、`__close_{code}`
+    pub(crate) fn aligned_lazy(&self) -> LazyFrame {
+        let time_col = self.close.time_col();
+        let codes = self.close.stock_cols();
+        
+        let prefixed = |table: &WideTable, prefix: &str| {
+            let mut exprs = vec![col(time_col)];
+            exprs.extend(
+                codes
+                    .iter()
+                    .map(|code| col(code).cast(DataType::Float64).alias(&format!("__{}_{}", prefix, code))),
+            );
+            table.df().clone().lazy().select(exprs)
+        };
+        
+        prefixed(&self.close, "close")
+            .join(
+                prefixed(&self.high, "high"),
+                [col(time_col)],
+                [col(time_col)],
+                JoinArgs::new(polars::prelude::JoinType::Inner),
+            )
+            .join(
+                prefixed(&self.low, "low"),
+                [col(time_col)],
+                [col(time_col)],
+                JoinArgs::new(polars::prelude::JoinType::Inner),
+            )
+            .sort([time_col], SortMultipleOptions::default())
+    }
+}
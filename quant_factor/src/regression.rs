@@ -0,0 +1,170 @@
+//! 因子计算共用的回归工具
+
+/// 一元线性回归 `y = alpha + beta * x` 的拟合结果
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OlsFit {
+    pub alpha: f64,
+    pub beta: f64,
+    /// 残差标准差，自由度为 `n - 2`
+    pub resid_std: f64,
+}
+
+/// 一元最小二乘回归
+/// 
+/// 样本少于 3 个或 `x` 没有波动时返回 None
+pub(crate) fn ols(x: &[f64], y: &[f64]) -> Option<OlsFit> {
+    let n = x.len();
+    if n < 3 || n != y.len() {
+        return None;
+    }
+    
+    let mean_x = x.iter().sum::<f64>() / n as f64;
+    let mean_y = y.iter().sum::<f64>() / n as f64;
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    for (xi, yi) in x.iter().zip(y) {
+        sxx += (xi - mean_x) * (xi - mean_x);
+        sxy += (xi - mean_x) * (yi - mean_y);
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+    
+    let beta = sxy / sxx;
+    let alpha = mean_y - beta * mean_x;
+    let ssr: f64 = x
+        .iter()
+        .zip(y)
+        .map(|(xi, yi)| {
+            let resid = yi - alpha - beta * xi;
+            resid * resid
+        })
+        .sum();
+    
+    Some(OlsFit {
+        alpha,
+        beta,
+        resid_std: (ssr / (n - 2) as f64).sqrt(),
+    })
+}
+
+/// 滚动一元回归
+/// 
+/// 第 `i` 个结果使用 `[i + 1 - window, i]` 区间内的样本，
+/// 区间内任一 `x` 或 `y` 缺失（null 或 NaN）时结果为 None
+pub(crate) fn rolling_ols(x: &[Option<f64>], y: &[Option<f64>], window: usize) -> Vec<Option<OlsFit>> {
+    rolling_ols_where(x, y, window, 0, |_| true)
+}
+
+/// 条件滚动一元回归
+/// 
+/// 与 [`rolling_ols`] 相同的窗口与缺失值规则，但窗口内只有 `keep(x)` 为真的样本参与回归，
+/// 参与回归的样本少于 `min_obs` 时结果为 None
+pub(crate) fn rolling_ols_where(
+    x: &[Option<f64>],
+    y: &[Option<f64>],
+    window: usize,
+    min_obs: usize,
+    keep: impl Fn(f64) -> bool,
+) -> Vec<Option<OlsFit>> {
+    let mut xs = Vec::with_capacity(window);
+    let mut ys = Vec::with_capacity(window);
+    
+    (0..x.len().min(y.len()))
+        .map(|i| {
+            if window == 0 || i + 1 < window {
+                return None;
+            }
+            xs.clear();
+            ys.clear();
+            for j in i + 1 - window..=i {
+                match (x[j], y[j]) {
+                    (Some(xj), Some(yj)) if !xj.is_nan() && !yj.is_nan() => {
+                        if keep(xj) {
+                            xs.push(xj);
+                            ys.push(yj);
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            if xs.len() < min_obs {
+                return None;
+            }
+            ols(&xs, &ys)
+        })
+        .collect()
+}
+
+/// 带截距的多元最小二乘回归的拟合结果
+#[derive(Debug, Clone)]
+pub(crate) struct MultiOlsFit {
+    /// 回归系数 `[b0, b1, ..., bk]`，`b0` 为截距
+    pub coef: Vec<f64>,
+    /// 各样本的残差
+    pub residuals: Vec<f64>,
+}
+
+impl MultiOlsFit {
+    /// 拟合优度 R²；`y` 没有波动时为 None
+    pub fn r_squared(&self, y: &[f64]) -> Option<f64> {
+        let mean = y.iter().sum::<f64>() / y.len() as f64;
+        let sst: f64 = y.iter().map(|v| (v - mean) * (v - mean)).sum();
+        let ssr: f64 = self.residuals.iter().map(|r| r * r).sum();
+        (sst > 0.0).then(|| 1.0 - ssr / sst)
+    }
+}
+
+/// 带截距的多元最小二乘回归
+/// 
+/// `xs[j]` 为第 `j` 个解释变量的 `n` 个样本，回归 `y = b0 + b1 * xs[0] + ... + bk * xs[k - 1]`，
+/// 用正规方程配合列主元高斯消元求解。样本数不超过参数个数或设计矩阵（近似）奇异时返回 None
+pub(crate) fn ols_multi(xs: &[Vec<f64>], y: &[f64]) -> Option<MultiOlsFit> {
+    let n = y.len();
+    let k = xs.len() + 1;
+    if n <= k || xs.iter().any(|x| x.len() != n) {
+        return None;
+    }
+    
+    // 第 i 个样本的设计向量 [1, x_i1, ..., x_ik]
+    let row = |i: usize| std::iter::once(1.0).chain(xs.iter().map(move |x| x[i]));
+    let mut a = vec![vec![0.0; k + 1]; k];
+    for (i, yi) in y.iter().enumerate() {
+        let r: Vec<f64> = row(i).collect();
+        for (a_row, rp) in a.iter_mut().zip(&r) {
+            for (v, rq) in a_row.iter_mut().zip(&r) {
+                *v += rp * rq;
+            }
+            a_row[k] += rp * yi;
+        }
+    }
+    
+    let scale = (0..k).map(|p| a[p][p].abs()).fold(0.0, f64::max);
+    for col in 0..k {
+        let pivot = (col..k).max_by(|&p, &q| a[p][col].abs().total_cmp(&a[q][col].abs()))?;
+        if a[pivot][col].abs() <= 1e-10 * scale {
+            return None;
+        }
+        a.swap(col, pivot);
+        let pivot_row = a[col].clone();
+        for (p, a_row) in a.iter_mut().enumerate() {
+            if p != col {
+                let factor = a_row[col] / pivot_row[col];
+                for (v, pv) in a_row.iter_mut().zip(&pivot_row).skip(col) {
+                    *v -= factor * pv;
+                }
+            }
+        }
+    }
+    let coef: Vec<f64> = a.iter().enumerate().map(|(p, a_row)| a_row[k] / a_row[p]).collect();
+    let residuals = (0..n)
+        .map(|i| y[i] - row(i).zip(&coef).map(|(x, b)| x * b).sum::<f64>())
+        .collect();
+    
+    Some(MultiOlsFit { coef, residuals })
+}
+
+/// 带截距的多元最小二乘回归的残差，规则同 [`ols_multi`]
+pub(crate) fn ols_residuals(xs: &[Vec<f64>], y: &[f64]) -> Option<Vec<f64>> {
+    ols_multi(xs, y).map(|fit| fit.residuals)
+}
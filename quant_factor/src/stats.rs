@@ -0,0 +1,266 @@
+//! 因子计算共用的统计工具
+
+use std::collections::VecDeque;
+
+/// 标准差不超过该值时视为 0，避免浮点误差放大出极端比率
+pub(crate) const ZERO_TOLERANCE: f64 = 1e-12;
+
+/// 偏差修正的样本偏度（调整后的 Fisher-Pearson 系数，与 pandas 一致）
+/// 
+/// 样本少于 3 个或没有波动（标准差不超过 [`ZERO_TOLERANCE`]）时返回 None
+pub(crate) fn skew(values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    if values.len() < 3 {
+        return None;
+    }
+    let (m2, m3, _) = central_moments(values);
+    if m2.sqrt() <= ZERO_TOLERANCE {
+        return None;
+    }
+    Some((n * (n - 1.0)).sqrt() / (n - 2.0) * m3 / m2.powf(1.5))
+}
+
+/// 偏差修正的样本超额峰度（与 pandas 一致，正态分布约为 0）
+/// 
+/// 样本少于 4 个或没有波动（标准差不超过 [`ZERO_TOLERANCE`]）时返回 None
+pub(crate) fn kurtosis(values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    if values.len() < 4 {
+        return None;
+    }
+    let (m2, _, m4) = central_moments(values);
+    if m2.sqrt() <= ZERO_TOLERANCE {
+        return None;
+    }
+    Some((n - 1.0) / ((n - 2.0) * (n - 3.0)) * ((n + 1.0) * m4 / (m2 * m2) - 3.0 * (n - 1.0)))
+}
+
+/// 相对窗口内历史最高点的回撤序列（小数，<= 0）
+/// 
+/// 第 `i` 个值为 `prices[i] / max(prices[..=i]) - 1`
+pub(crate) fn drawdowns(prices: &[f64]) -> Vec<f64> {
+    let mut peak = f64::NEG_INFINITY;
+    prices
+        .iter()
+        .map(|&p| {
+            peak = peak.max(p);
+            p / peak - 1.0
+        })
+        .collect()
+}
+
+/// 皮尔逊相关系数
+/// 
+/// 样本少于 2 个或任一序列没有波动时返回 None
+pub(crate) fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_x = x[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = y[..n].iter().sum::<f64>() / n as f64;
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (a, b) in x[..n].iter().zip(&y[..n]) {
+        let (dx, dy) = (a - mean_x, b - mean_y);
+        sxy += dx * dy;
+        sxx += dx * dx;
+        syy += dy * dy;
+    }
+    if sxx == 0.0 || syy == 0.0 {
+        return None;
+    }
+    Some(sxy / (sxx * syy).sqrt())
+}
+
+/// 样本协方差（自由度 `n - 1`）
+/// 
+/// 样本少于 2 个时返回 None
+pub(crate) fn covariance(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_x = x[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = y[..n].iter().sum::<f64>() / n as f64;
+    let sxy: f64 = x[..n].iter().zip(&y[..n]).map(|(a, b)| (a - mean_x) * (b - mean_y)).sum();
+    Some(sxy / (n - 1) as f64)
+}
+
+/// 滚动窗口内距最大值出现位置的期数（0 表示当前行即为最大值）
+/// 
+/// 使用单调队列，时间复杂度 O(n)。相同最大值取最近一次出现的位置。
+/// 窗口未满 `window` 行或窗口内有 null / NaN 时为 None。
+pub(crate) fn rolling_periods_since_max(values: &[Option<f64>], window: usize) -> Vec<Option<usize>> {
+    // 队列中保存下标，对应的值严格递减，队首为窗口最大值
+    let mut deque: VecDeque<usize> = VecDeque::with_capacity(window);
+    let mut last_invalid: Option<usize> = None;
+    let mut result = Vec::with_capacity(values.len());
+    
+    for (i, v) in values.iter().enumerate() {
+        match v {
+            Some(x) if !x.is_nan() => {
+                while deque.back().is_some_and(|&j| values[j].unwrap() <= *x) {
+                    deque.pop_back();
+                }
+                deque.push_back(i);
+            }
+            _ => last_invalid = Some(i),
+        }
+        while deque.front().is_some_and(|&j| j + window <= i) {
+            deque.pop_front();
+        }
+        
+        let full = i + 1 >= window && last_invalid.is_none_or(|j| j + window <= i);
+        result.push(if full { deque.front().map(|&j| i - j) } else { None });
+    }
+    result
+}
+
+/// 标准正态分布的分位数函数（Acklam 近似，相对误差约 1e-9）
+/// 
+/// `p` 须在 `(0, 1)` 内，否则返回 NaN
+pub(crate) fn normal_ppf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+    
+    if !(p > 0.0 && p < 1.0) {
+        return f64::NAN;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// 有偏的二、三、四阶中心矩
+fn central_moments(values: &[f64]) -> (f64, f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+    for v in values {
+        let d = v - mean;
+        m2 += d * d;
+        m3 += d * d * d;
+        m4 += d * d * d * d;
+    }
+    (m2 / n, m3 / n, m4 / n)
+}
+
+/// 已排序数据的分位数（线性插值，与 numpy 默认一致）
+/// 
+/// `sorted` 须非空且升序，`q` 须在 `[0, 1]` 内
+pub(crate) fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+/// 等宽直方图的香农熵（自然对数）
+/// 
+/// 在 `[min, max]` 上划分 `bins` 个等宽区间，最大值落入最后一个区间。
+/// 数据为空或没有波动（`max - min` 不超过 [`ZERO_TOLERANCE`]）时返回 None，
+/// 避免浮点误差把几乎相同的值分散到不同区间
+pub(crate) fn histogram_entropy(values: &[f64], bins: usize) -> Option<f64> {
+    let min = values.iter().copied().reduce(f64::min)?;
+    let max = values.iter().copied().reduce(f64::max)?;
+    if max - min <= ZERO_TOLERANCE {
+        return None;
+    }
+    
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0usize; bins];
+    for &v in values {
+        let k = (((v - min) / width) as usize).min(bins - 1);
+        counts[k] += 1;
+    }
+    
+    let n = values.len() as f64;
+    Some(
+        counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / n;
+                -p * p.ln()
+            })
+            .sum(),
+    )
+}
+
+/// 一元局部水平模型（随机游走加观测噪声）的卡尔曼滤波
+/// 
+/// 状态方程 `x_t = x_{t-1} + w_t, w_t ~ N(0, q)`，观测方程 `y_t = x_t + v_t, v_t ~ N(0, r)`。
+/// 以第一个有效观测为初始状态、`r` 为初始方差；null 与 NaN 的观测只做预测不做更新。
+/// 第一个有效观测之前为 None
+pub(crate) fn local_level_filter(values: &[Option<f64>], q: f64, r: f64) -> Vec<Option<f64>> {
+    let mut state: Option<(f64, f64)> = None;
+    values
+        .iter()
+        .map(|obs| {
+            let obs = obs.filter(|y| !y.is_nan());
+            state = match (state, obs) {
+                (None, Some(y)) => Some((y, r)),
+                (Some((x, p)), Some(y)) => {
+                    let p = p + q;
+                    let gain = p / (p + r);
+                    Some((x + gain * (y - x), (1.0 - gain) * p))
+                }
+                (Some((x, p)), None) => Some((x, p + q)),
+                (None, None) => None,
+            };
+            state.map(|(x, _)| x)
+        })
+        .collect()
+}
+
+/// 滚动窗口统计
+/// 
+/// 第 `i` 个结果使用 `[i + 1 - window, i]` 区间（开头不足 `window` 行时取已有的行），
+/// 剔除 null 与 NaN 后有效值少于 `min_valid` 个时为 None
+pub(crate) fn rolling_window_apply<F>(
+    values: &[Option<f64>],
+    window: usize,
+    min_valid: usize,
+    mut f: F,
+) -> Vec<Option<f64>>
+where
+    F: FnMut(&[f64]) -> Option<f64>,
+{
+    let mut buf = Vec::with_capacity(window);
+    (0..values.len())
+        .map(|i| {
+            let start = (i + 1).saturating_sub(window);
+            buf.clear();
+            buf.extend(values[start..=i].iter().flatten().filter(|v| !v.is_nan()));
+            if buf.len() < min_valid.max(1) {
+                None
+            } else {
+                f(&buf)
+            }
+        })
+        .collect()
+}
@@ -1,21 +1,282 @@
+use crate::stats;
 use polars::prelude::*;
 use chrono::NaiveDate;
-use anyhow::Result;
+use crate::calendar::TradingCalendar;
+use crate::error::WideTableError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+type Result<T, E = WideTableError> = std::result::Result<T, E>;
+
+/// 动量因子的加权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MomentumWeighting {
+    /// 等权：窗口内收益率直接求和（默认）
+    #[default]
+    Equal,
+    /// 线性衰减：最近一期权重为 periods，最早一期权重为 1
+    LinearDecay,
+}
+
+/// 收益率的单位
+/// 
+/// 混用不同单位的因子会让下游的 IC 与回测结果出错，需要时应显式指定并在计算链路上保持一致。
+/// 
+/// 默认值为 [`ReturnScale::Fraction`] 而不是与 [`WideTable::pct_change`] 一致的百分比：
+/// 除 `pct_change` 为兼容旧代码保留百分比外，动量、波动率、未来收益率以及
+/// [`FactorCalculator`](crate::FactorCalculator) 的各项统计都以小数收益率为输入或输出，
+/// 默认取小数可以让 `*_with(ReturnScale::default())` 与对应的无后缀方法结果相同。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReturnScale {
+    /// 百分比，1.0 表示 1%（与 [`WideTable::pct_change`] 一致）
+    Percent,
+    /// 小数，0.01 表示 1%（默认，与 [`WideTable::momentum`]、[`WideTable::volatility`] 一致）
+    #[default]
+    Fraction,
+    /// 基点，100.0 表示 1%
+    BasisPoints,
+}
+
+impl ReturnScale {
+    /// 相对小数收益率的缩放系数
+    pub fn factor(self) -> f64 {
+        match self {
+            ReturnScale::Percent => 100.0,
+            ReturnScale::Fraction => 1.0,
+            ReturnScale::BasisPoints => 10_000.0,
+        }
+    }
+    
+    /// 对应的收益率列名片段：`pct_change`、`return` 或 `bps_change`
+    fn tag(self) -> &'static str {
+        match self {
+            ReturnScale::Percent => "pct_change",
+            ReturnScale::Fraction => "return",
+            ReturnScale::BasisPoints => "bps_change",
+        }
+    }
+    
+    /// 动量、波动率等列名中的单位片段：小数为空，百分比为 `_pct`，基点为 `_bps`
+    fn unit_suffix(self) -> &'static str {
+        match self {
+            ReturnScale::Percent => "_pct",
+            ReturnScale::Fraction => "",
+            ReturnScale::BasisPoints => "_bps",
+        }
+    }
+}
+
+/// 宽表横向合并方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JoinType {
+    /// 仅保留两表共有的时间
+    #[default]
+    Inner,
+    /// 保留左表的全部时间
+    Left,
+    /// 保留两表的全部时间
+    Outer,
+}
+
+impl From<JoinType> for polars::prelude::JoinType {
+    fn from(how: JoinType) -> Self {
+        match how {
+            JoinType::Inner => polars::prelude::JoinType::Inner,
+            JoinType::Left => polars::prelude::JoinType::Left,
+            JoinType::Outer => polars::prelude::JoinType::Outer,
+        }
+    }
+}
+
+/// 截面排名时相同值的处理方式，与 polars 的 rank 选项一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RankMethod {
+    /// 相同值取名次的平均值
+    #[default]
+    Average,
+    /// 相同值取最小名次
+    Min,
+    /// 相同值取最大名次
+    Max,
+    /// 相同值取最小名次，且名次连续不跳号
+    Dense,
+    /// 相同值按列顺序依次排名
+    Ordinal,
+}
+
+/// 行业/分组中性化时，没有分组映射的股票的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UngroupedPolicy {
+    /// 原样保留（默认）
+    #[default]
+    PassThrough,
+    /// 报错
+    Error,
+    /// 从结果中删除该股票列
+    Drop,
+    /// 归入名为 `"UNKNOWN"` 的分组，与其他未映射的股票一起中性化
+    Unknown,
+}
+
+/// 截面有效股票数少于分组数时的处理方式，用于 [`WideTable::cross_qcut_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SparseGroupPolicy {
+    /// 报错（默认）
+    #[default]
+    Error,
+    /// 该截面只分 `n` 组（`n` 为有效股票数），每只股票单独一组
+    Collapse,
+    /// 仍按 `n_groups` 计算组号，组号不连续（如 2 只股票分 5 组得到 1 和 3），
+    /// 即 [`WideTable::quantile_buckets`] 的行为
+    Spread,
+}
+
+/// 截面分组时相同值的先后顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    /// 按列顺序稳定排序
+    ColumnOrder,
+    /// 按列名排序，与列顺序无关
+    ColumnName,
+}
+
+/// 沿时间方向填充缺失值的方式，用于 [`WideTable::fillna`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillStrategy {
+    /// 用之前最近的有效值填充；`limit` 为连续填充的最大行数，None 表示不限
+    ForwardFill { limit: Option<usize> },
+    /// 用之后最近的有效值填充
+    BackwardFill,
+    /// 填充 0
+    Zero,
+    /// 填充该股票全部有效值的均值
+    Mean,
+    /// 按行序在前后两个有效值之间线性插值，开头与结尾的缺失值保持不变
+    LinearInterpolate,
+}
+
+/// 截面去极值（缩尾）的方法，用于 [`WideTable::winsorize`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WinsorMethod {
+    /// 中位数 ± n 倍 MAD（绝对偏差的中位数，不乘 1.4826 的一致性系数）
+    Mad(f64),
+    /// 均值 ± n 倍样本标准差
+    Sigma(f64),
+    /// 下、上分位数（线性插值，与 numpy 默认一致），须满足 `0 <= lower <= upper <= 1`
+    Quantile(f64, f64),
+}
+
+/// CSV 解析选项，用于 [`WideTable::from_csv_with`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// 分隔符，默认为 `,`
+    pub delimiter: u8,
+    /// 首行是否为表头，默认为 true；没有表头时列名为 `column_1`、`column_2`……
+    pub has_header: bool,
+    /// 时间列的数据类型，默认为 None（由 polars 推断）
+    pub time_dtype: Option<DataType>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_header: true,
+            time_dtype: None,
+        }
+    }
+}
+
+/// [`WideTable::align_with_summary`] 对齐时被丢弃的行数与列数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AlignSummary {
+    /// `self` 中被丢弃的行数（时间不在交集中）
+    pub dropped_rows_left: usize,
+    /// `other` 中被丢弃的行数
+    pub dropped_rows_right: usize,
+    /// `self` 中被丢弃的股票列数
+    pub dropped_cols_left: usize,
+    /// `other` 中被丢弃的股票列数
+    pub dropped_cols_right: usize,
+}
+
+/// 宽表的元数据，以 JSON 形式与 Parquet 数据文件一同保存
+/// 
+/// Parquet 文件本身不记录哪一列是时间列，配合 [`WideTable::load_with_meta`] 使用，
+/// 读取时无需调用方记住时间列名
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WideTableMeta {
+    /// 时间列名
+    pub time_col: String,
+    /// 数据说明（如字段含义、来源）
+    pub description: Option<String>,
+}
+
+impl WideTableMeta {
+    /// 以 JSON 格式写入文件
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+    
+    /// 从 JSON 文件读取
+    /// 
+    /// # Errors
+    /// 文件无法打开时返回 [`WideTableError::Io`]，内容不是合法的元数据 JSON 时返回 [`WideTableError::Json`]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+}
 
 /// 宽表数据结构
-/// - index: 时间（DateTime）
+/// - index: 时间（Date / Datetime），或没有日期的数据中的整数序号（如 bar 编号）
 /// - columns: 股票代码
 /// - values: 价格数据（如 close）
+/// 
+/// 支持 serde 序列化，JSON 格式为 `{ "data": <DataFrame>, "time_col": "..." }`（设置了说明时还有 `description`），
+/// 反序列化时会重新校验时间列是否存在
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawWideTable")]
 pub struct WideTable {
     /// DataFrame，行索引为时间，列为股票代码
+    #[serde(rename = "data")]
     df: DataFrame,
     /// 时间列名
     time_col: String,
+    /// 数据说明，来自 [`WideTableMeta`]；由计算派生出的新宽表不继承
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+/// 反序列化的中间结构，经 [`WideTable::new`] 校验后转换为 WideTable
+#[derive(Deserialize)]
+struct RawWideTable {
+    data: DataFrame,
+    time_col: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+impl TryFrom<RawWideTable> for WideTable {
+    type Error = WideTableError;
+    
+    fn try_from(raw: RawWideTable) -> Result<Self> {
+        let mut table = Self::new(raw.data, raw.time_col)?;
+        table.description = raw.description;
+        Ok(table)
+    }
 }
 
 impl WideTable {
     /// 创建新的宽表
     /// 
+    /// 时间列只需可排序：除日期与时间类型外，也可以是整数序号（如日内 bar 编号），
+    /// 排序、shift 与滚动窗口都按行序计算，结果与日期索引一致。
+    /// 依赖日历的方法（如 [`WideTable::reindex_to_calendar`]、[`WideTable::pct_change_from_base`]）
+    /// 要求时间列为 `Date`，对整数序号返回错误。
+    /// 
     /// # Arguments
     /// * `df` - DataFrame，必须包含时间列和多个股票列
     /// * `time_col` - 时间列的名称
@@ -24,10 +285,10 @@ impl WideTable {
         
         // 验证时间列存在
         if !df.column(&time_col).is_ok() {
-            return Err(anyhow::anyhow!("时间列 '{}' 不存在", time_col));
+            return Err(WideTableError::TimeColumnMissing(time_col));
         }
         
-        Ok(Self { df, time_col })
+        Ok(Self { df, time_col, description: None })
     }
     
     /// 从 CSV 文件加载宽表数据
@@ -36,154 +297,4560 @@ impl WideTable {
     /// * `path` - CSV 文件路径
     /// * `time_col` - 时间列名称
     pub fn from_csv(path: impl AsRef<std::path::Path>, time_col: impl Into<String>) -> Result<Self> {
+        Self::from_csv_with(path, time_col, CsvOptions::default())
+    }
+    
+    /// 按指定的解析选项从 CSV 文件加载宽表数据
+    /// 
+    /// # Arguments
+    /// * `path` - CSV 文件路径
+    /// * `time_col` - 时间列名称
+    /// * `options` - 分隔符、表头与时间列类型等解析选项
+    pub fn from_csv_with(
+        path: impl AsRef<std::path::Path>,
+        time_col: impl Into<String>,
+        options: CsvOptions,
+    ) -> Result<Self> {
         let time_col = time_col.into();
-        let df = LazyFrame::scan_csv(path, ScanArgsCSV::default())
-            .collect()?;
+        let df = csv_reader(path, &time_col, &options)?.collect()?;
+        
+        Self::new(df, time_col)
+    }
+    
+    /// 使用 polars 流式引擎从大型 CSV 文件中只加载部分股票与部分时间
+    /// 
+    /// 只读取时间列与 `stocks` 中的列，并在扫描时应用 `filter`（如限定时间范围），
+    /// 投影与过滤都下推到流式扫描中，按批次解析后丢弃不需要的数据。
+    /// 峰值匿名内存只与筛选后的结果大小有关，而不是整个文件，这是与 [`WideTable::from_csv`] 的区别；
+    /// 需要整个文件时直接使用 `from_csv` 即可，流式读取并不能降低完整结果本身的内存占用。
+    /// 对比数据可用 `examples/bench_csv_streaming.rs` 复现。
+    /// 
+    /// 读取数据前先根据 schema 校验列名，列不存在时不会扫描整个文件。
+    /// 
+    /// # Arguments
+    /// * `path` - CSV 文件路径
+    /// * `time_col` - 时间列名称
+    /// * `stocks` - 要加载的股票列，不能为空
+    /// * `filter` - 扫描时应用的行过滤条件，None 表示保留全部行
+    /// 
+    /// # Errors
+    /// 时间列不存在时返回 [`WideTableError::TimeColumnMissing`]；
+    /// `stocks` 为空或包含不存在的列时返回 [`WideTableError::InvalidArgument`]
+    pub fn from_csv_streaming(
+        path: impl AsRef<std::path::Path>,
+        time_col: impl Into<String>,
+        stocks: &[&str],
+        filter: Option<Expr>,
+    ) -> Result<Self> {
+        let time_col = time_col.into();
+        if stocks.is_empty() {
+            return Err(WideTableError::InvalidArgument(
+                "至少指定一只股票；需要加载整个文件时使用 from_csv".to_string(),
+            ));
+        }
+        
+        let lazy_df = csv_reader(path, &time_col, &CsvOptions::default())?;
+        let schema = lazy_df.schema()?;
+        if schema.get(&time_col).is_none() {
+            return Err(WideTableError::TimeColumnMissing(time_col));
+        }
+        if let Some(missing) = stocks.iter().find(|&&name| schema.get(name).is_none()) {
+            return Err(WideTableError::InvalidArgument(format!("CSV 中缺少股票列 '{}'", missing)));
+        }
+        
+        let mut columns = vec![col(&time_col)];
+        columns.extend(stocks.iter().map(|&name| col(name)));
+        let mut lazy_df = lazy_df.select(columns);
+        if let Some(filter) = filter {
+            lazy_df = lazy_df.filter(filter);
+        }
+        let df = lazy_df.with_streaming(true).collect()?;
         
         Self::new(df, time_col)
     }
     
+    /// 在 tokio 运行时中异步加载 CSV 文件
+    /// 
+    /// CSV 解析是阻塞操作，这里通过 `tokio::task::spawn_blocking` 放到阻塞线程池执行，
+    /// 不会占用异步执行器的工作线程。需要启用 `tokio` feature。
+    /// 
+    /// # Arguments
+    /// * `path` - CSV 文件路径
+    /// * `time_col` - 时间列名称
+    #[cfg(feature = "tokio")]
+    pub async fn from_csv_async(
+        path: impl AsRef<std::path::Path>,
+        time_col: impl Into<String>,
+    ) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let time_col = time_col.into();
+        tokio::task::spawn_blocking(move || Self::from_csv(path, time_col)).await?
+    }
+    
+    /// 从 Parquet 文件加载宽表数据
+    /// 
+    /// # Arguments
+    /// * `path` - Parquet 文件路径
+    /// * `time_col` - 时间列名称
+    pub fn from_parquet(path: impl AsRef<std::path::Path>, time_col: impl Into<String>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::new(ParquetReader::new(file).finish()?, time_col)
+    }
+    
+    /// 读取 Parquet 数据文件与 JSON 元数据文件，重建宽表
+    /// 
+    /// 时间列名取自元数据，与 [`WideTable::to_parquet`] 和 [`WideTable::save_meta`] 配合使用
+    /// 
+    /// # Arguments
+    /// * `data_path` - Parquet 文件路径
+    /// * `meta_path` - 元数据 JSON 文件路径
+    /// 
+    /// # Returns
+    /// 时间列与 [`WideTable::description`] 均取自元数据的宽表
+    /// 
+    /// # Errors
+    /// 元数据文件无法读取时返回 [`WideTableError::Io`]，内容不是合法的元数据 JSON 时返回 [`WideTableError::Json`]；
+    /// 元数据中的时间列不在数据中时返回 [`WideTableError::TimeColumnMissing`]
+    pub fn load_with_meta(data_path: impl AsRef<std::path::Path>, meta_path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let meta = WideTableMeta::load(meta_path)?;
+        let mut table = Self::from_parquet(data_path, meta.time_col)?;
+        table.description = meta.description;
+        Ok(table)
+    }
+    
+    /// 从 LazyFrame 创建宽表
+    /// 
+    /// 会立即执行 `lf` 的查询计划，结果必须包含 `time_col` 列
+    /// 
+    /// # Arguments
+    /// * `lf` - LazyFrame
+    /// * `time_col` - 时间列名称
+    pub fn from_lazy(lf: LazyFrame, time_col: impl Into<String>) -> Result<Self> {
+        Self::new(lf.collect()?, time_col)
+    }
+    
+    /// 获取底层数据的 LazyFrame，用于拼接自定义的 polars 表达式
+    /// 
+    /// 这是库未覆盖场景的兜底接口。自定义表达式需要保持时间列约定：
+    /// 不要删除或重命名时间列，其余列都会被当作股票列参与后续计算。
+    /// 处理完后用 [`WideTable::from_lazy`] 包装回宽表。
+    /// 
+    /// ```ignore
+    /// let lf = table.momentum(20)?.lazy().with_columns([
+    ///     (col("000001_momentum_20") - col("000002_momentum_20")).alias("spread"),
+    /// ]);
+    /// let table = WideTable::from_lazy(lf, "date")?;
+    /// ```
+    pub fn lazy(&self) -> LazyFrame {
+        self.df.clone().lazy()
+    }
+    
+    /// 转换为 LazyFrame 与时间列名，不复制数据
+    /// 
+    /// 与 [`WideTable::lazy`] 相同，但会消耗宽表本身；继续拼接表达式后可用
+    /// [`WideTable::from_lazy`] 包装回宽表。
+    pub fn into_lazy(self) -> (LazyFrame, String) {
+        (self.df.lazy(), self.time_col)
+    }
+    
     /// 获取 DataFrame
     pub fn df(&self) -> &DataFrame {
         &self.df
     }
     
+    /// 前 `n` 行组成的新宽表，行顺序与原表一致，便于交互式查看
+    /// 
+    /// `n` 超过行数时返回全部行
+    pub fn head(&self, n: usize) -> WideTable {
+        WideTable {
+            df: self.df.head(Some(n)),
+            time_col: self.time_col.clone(),
+            description: None,
+        }
+    }
+    
+    /// 后 `n` 行组成的新宽表，与 [`WideTable::head`] 对称
+    pub fn tail(&self, n: usize) -> WideTable {
+        WideTable {
+            df: self.df.tail(Some(n)),
+            time_col: self.time_col.clone(),
+            description: None,
+        }
+    }
+    
     /// 获取时间列名
     pub fn time_col(&self) -> &str {
         &self.time_col
     }
     
-    /// 计算百分比变化（收益率）
+    /// 重命名时间列
+    /// 
+    /// 合并不同来源的数据（如 `date` 与 `trade_date`）前，先把时间列名统一。
+    /// 新名称与当前名称相同时不做任何修改。
     /// 
     /// # Arguments
-    /// * `periods` - 计算周期，例如 1 表示计算 1 期的收益率
+    /// * `new_name` - 新的时间列名
     /// 
-    /// # Returns
-    /// 返回新的 WideTable，包含收益率数据
-    pub fn pct_change(&self, periods: i32) -> Result<WideTable> {
-        let mut df = self.df.clone();
-        
-        // 按时间列排序
-        df = df.sort([&self.time_col], SortOptions::default())?;
+    /// # Errors
+    /// 新名称与某个股票列重名时返回 [`WideTableError::DuplicateColumn`]
+    pub fn rename_time_column(&mut self, new_name: &str) -> Result<()> {
+        if new_name == self.time_col {
+            return Ok(());
+        }
+        if self.df.column(new_name).is_ok() {
+            return Err(WideTableError::DuplicateColumn(new_name.to_string()));
+        }
         
-        // 获取所有列名（排除时间列）
-        let stock_cols: Vec<String> = df
+        self.df.rename(&self.time_col, new_name)?;
+        self.time_col = new_name.to_string();
+        Ok(())
+    }
+    
+    /// 按时间列排序后的 DataFrame
+    pub(crate) fn sorted_df(&self) -> Result<DataFrame> {
+        Ok(self.df.sort([&self.time_col], SortOptions::default())?)
+    }
+    
+    /// 获取所有股票列名（排除时间列）
+    pub(crate) fn stock_cols(&self) -> Vec<String> {
+        self.df
             .get_column_names()
             .iter()
             .filter(|&col| col != &self.time_col)
             .map(|s| s.to_string())
+            .collect()
+    }
+    
+    /// 检查所有股票列都是数值类型
+    /// 
+    /// CSV 中混入非数字内容时整列会被读成字符串，直接参与算术运算只会得到难以定位的 polars 错误
+    /// 
+    /// # Errors
+    /// 存在非数值列时返回 [`WideTableError::SchemaMismatch`]，列出每个非数值列及其类型
+    fn ensure_numeric_stock_cols(&self) -> Result<()> {
+        let problems: Vec<String> = self
+            .df
+            .get_columns()
+            .iter()
+            .filter(|series| series.name() != self.time_col && !series.dtype().is_numeric())
+            .map(|series| format!("股票列 '{}' 类型为 {}，需要数值类型", series.name(), series.dtype()))
             .collect();
         
-        // 对每个股票列计算 pct_change
-        let mut lazy_df = df.lazy();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(WideTableError::SchemaMismatch(problems.join("; ")))
+        }
+    }
+    
+    /// 按字典序排列股票列，时间列放在第一列
+    /// 
+    /// 不同代码路径或合并得到的宽表列顺序可能不同，排序后便于比较与复现
+    pub fn sort_columns(&self) -> WideTable {
+        let mut names = self.stock_cols();
+        names.sort();
+        names.insert(0, self.time_col.clone());
         
-        for col_name in &stock_cols {
-            let pct_col = format!("{}_pct_change_{}", col_name, periods);
-            // 使用 shift 和除法来计算百分比变化: (current - previous) / previous * 100
-            lazy_df = lazy_df.with_columns([
-                ((col(col_name) - col(col_name).shift(lit(periods))) 
-                    / col(col_name).shift(lit(periods)) 
-                    * lit(100.0))
-                    .alias(&pct_col)
-            ]);
+        WideTable {
+            df: self.df.select(&names).expect("列名均来自宽表本身"),
+            time_col: self.time_col.clone(),
+            description: None,
+        }
+    }
+    
+    /// 按指定顺序排列股票列，时间列放在第一列
+    /// 
+    /// 未在 `order` 中出现的股票列按原顺序排在最后
+    /// 
+    /// # Arguments
+    /// * `order` - 股票列名顺序
+    /// 
+    /// # Errors
+    /// `order` 中有不存在的股票列或重复的列名时返回错误
+    pub fn reorder_columns(&self, order: &[&str]) -> Result<WideTable> {
+        let stock_cols = self.stock_cols();
+        let mut seen = HashSet::new();
+        for name in order {
+            if !stock_cols.iter().any(|c| c == name) {
+                return Err(WideTableError::ColumnNotFound(name.to_string()));
+            }
+            if !seen.insert(*name) {
+                return Err(WideTableError::DuplicateColumn(name.to_string()));
+            }
         }
         
-        let new_df = lazy_df.collect()?;
+        let mut names = vec![self.time_col.as_str()];
+        names.extend(order);
+        names.extend(stock_cols.iter().map(|c| c.as_str()).filter(|c| !seen.contains(c)));
         
         Ok(WideTable {
-            df: new_df,
+            df: self.df.select(names)?,
             time_col: self.time_col.clone(),
+            description: None,
         })
     }
     
-    /// 计算动量因子
+    /// 获取所有列名及其数据类型（包含时间列）
+    /// 
+    /// 只读取 schema，不做任何计算
+    pub fn dtypes(&self) -> Vec<(String, DataType)> {
+        self.df
+            .get_columns()
+            .iter()
+            .map(|s| (s.name().to_string(), s.dtype().clone()))
+            .collect()
+    }
+    
+    /// 依次遍历每个股票列，产出 `(列名, Series)`，不包含时间列
     /// 
-    /// 动量因子通常定义为过去 N 期的累计收益率
+    /// Series 是克隆出来的，但 polars 的 Series 内部按引用计数共享数据，克隆不会复制列中的值，
+    /// 也不会克隆整个 DataFrame。行顺序与 [`WideTable::df`] 相同（不会按时间重新排序）。
+    pub fn iter_stocks(&self) -> impl Iterator<Item = (String, Series)> + '_ {
+        self.df
+            .get_columns()
+            .iter()
+            .filter(|s| s.name() != self.time_col)
+            .map(|s| (s.name().to_string(), s.clone()))
+    }
+    
+    /// 计算每个股票列的描述性统计
     /// 
-    /// # Arguments
-    /// * `periods` - 动量周期，例如 20 表示过去 20 期的动量
+    /// 时间列不参与统计。NaN 与 null 都视为缺失值。
     /// 
     /// # Returns
-    /// 返回包含动量因子的新 WideTable
-    pub fn momentum(&self, periods: i32) -> Result<WideTable> {
-        // 先计算收益率
-        let ret_table = self.pct_change(1)?;
+    /// 每个股票列一行的 DataFrame，列为
+    /// `stock, count, mean, std, min, 25%, 50%, 75%, max, null_count`，
+    /// 其中 `count` 为非缺失值个数，`null_count` 为缺失值个数
+    pub fn describe(&self) -> Result<DataFrame> {
+        let stock_cols = self.stock_cols();
+        let n = stock_cols.len();
         
-        let mut df = ret_table.df().clone();
+        let mut count = Vec::with_capacity(n);
+        let mut mean = Vec::with_capacity(n);
+        let mut std = Vec::with_capacity(n);
+        let mut min = Vec::with_capacity(n);
+        let mut q25 = Vec::with_capacity(n);
+        let mut q50 = Vec::with_capacity(n);
+        let mut q75 = Vec::with_capacity(n);
+        let mut max = Vec::with_capacity(n);
+        let mut null_count = Vec::with_capacity(n);
         
-        // 按时间列排序
-        df = df.sort([&self.time_col], SortOptions::default())?;
+        for col_name in &stock_cols {
+            let values = self.df.column(col_name)?.cast(&DataType::Float64)?;
+            // NaN 统一转成 null
+            let ca: Float64Chunked = values
+                .f64()?
+                .into_iter()
+                .map(|v| v.filter(|x| !x.is_nan()))
+                .collect();
+            
+            count.push((ca.len() - ca.null_count()) as u32);
+            null_count.push(ca.null_count() as u32);
+            mean.push(ca.mean());
+            std.push(ca.std(1));
+            min.push(ca.min());
+            q25.push(ca.quantile(0.25, QuantileInterpolOptions::Linear)?);
+            q50.push(ca.median());
+            q75.push(ca.quantile(0.75, QuantileInterpolOptions::Linear)?);
+            max.push(ca.max());
+        }
         
-        // 获取所有收益率列名
-        let ret_cols: Vec<String> = df
-            .get_column_names()
+        let df = DataFrame::new(vec![
+            Series::new("stock", stock_cols),
+            Series::new("count", count),
+            Series::new("mean", mean),
+            Series::new("std", std),
+            Series::new("min", min),
+            Series::new("25%", q25),
+            Series::new("50%", q50),
+            Series::new("75%", q75),
+            Series::new("max", max),
+            Series::new("null_count", null_count),
+        ])?;
+        
+        Ok(df)
+    }
+    
+    /// 计算每个时间截面上所有股票的统计量
+    /// 
+    /// 用于观察因子截面分布随时间的变化（例如每天有多少只股票动量为正）。
+    /// NaN 与 null 都视为缺失值，不参与统计。
+    /// 
+    /// # Returns
+    /// 宽格式的 DataFrame：每个时间一行（行顺序与原表一致），列为
+    /// `{time_col}, mean, std, min, max, count, positive_count, median`。
+    /// `std` 为样本标准差，有效值少于 2 个时为 null；没有有效值时 `mean`、`min`、`max`、`median` 为 null。
+    /// `count` 为有效值个数，`positive_count` 为大于 0 的值的个数
+    pub fn cross_section_stats(&self) -> Result<DataFrame> {
+        let inputs: Vec<Vec<Option<f64>>> = self
+            .stock_cols()
             .iter()
-            .filter(|&col| col != &self.time_col && col.contains("_pct_change_1"))
-            .map(|s| s.to_string())
-            .collect();
+            .map(|name| -> Result<Vec<Option<f64>>> {
+                Ok(self.df.column(name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect())
+            })
+            .collect::<Result<_>>()?;
         
-        // 对每个收益率列计算滚动求和（动量）
-        let mut lazy_df = df.lazy();
+        let height = self.df.height();
+        let mut mean = Vec::with_capacity(height);
+        let mut std = Vec::with_capacity(height);
+        let mut min = Vec::with_capacity(height);
+        let mut max = Vec::with_capacity(height);
+        let mut median = Vec::with_capacity(height);
+        let mut count = Vec::with_capacity(height);
+        let mut positive_count = Vec::with_capacity(height);
         
-        for ret_col in &ret_cols {
-            // 提取原始列名
-            let base_col = ret_col.replace("_pct_change_1", "");
-            let momentum_col = format!("{}_momentum_{}", base_col, periods);
+        let mut valid = Vec::with_capacity(inputs.len());
+        for i in 0..height {
+            valid.clear();
+            valid.extend(inputs.iter().filter_map(|values| values[i]).filter(|x| !x.is_nan()));
+            valid.sort_by(|a, b| a.total_cmp(b));
             
-            // 计算过去 periods 期的累计收益率
-            // 使用 rolling_sum 计算滚动窗口内的累计收益率
-            lazy_df = lazy_df.with_columns([
-                col(ret_col)
-                    .rolling_sum(RollingOptionsFixedWindow {
-                        window_size: periods as usize,
-                        min_periods: 1,
-                        center: false,
-                    })
-                    .alias(&momentum_col)
-            ]);
+            let n = valid.len();
+            let row_mean = (n > 0).then(|| valid.iter().sum::<f64>() / n as f64);
+            mean.push(row_mean);
+            std.push(row_mean.filter(|_| n > 1).map(|m| {
+                (valid.iter().map(|x| (x - m) * (x - m)).sum::<f64>() / (n - 1) as f64).sqrt()
+            }));
+            min.push(valid.iter().copied().reduce(f64::min));
+            max.push(valid.iter().copied().reduce(f64::max));
+            median.push((n > 0).then(|| stats::quantile_sorted(&valid, 0.5)));
+            count.push(n as u32);
+            positive_count.push(valid.iter().filter(|&&x| x > 0.0).count() as u32);
         }
         
-        let new_df = lazy_df.collect()?;
+        let df = DataFrame::new(vec![
+            self.df.column(&self.time_col)?.clone(),
+            Series::new("mean", mean),
+            Series::new("std", std),
+            Series::new("min", min),
+            Series::new("max", max),
+            Series::new("count", count),
+            Series::new("positive_count", positive_count),
+            Series::new("median", median),
+        ])?;
         
-        Ok(WideTable {
-            df: new_df,
-            time_col: self.time_col.clone(),
-        })
+        Ok(df)
     }
     
-    /// 保存到 CSV 文件
-    pub fn to_csv(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
-        let mut file = std::fs::File::create(path)?;
-        CsvWriter::new(&mut file)
-            .include_header(true)
-            .finish(&mut self.df.clone())?;
-        Ok(())
+    /// 计算每个时间截面上所有股票的均值、标准差、中位数与有效值个数
+    /// 
+    /// 这是 [`WideTable::cross_section_stats`] 的列子集视图，自身不做任何计算：
+    /// 两者结果一致，只是这里只保留中性化、标准化等截面处理需要的列，便于直接使用。
+    /// 
+    /// # Returns
+    /// 每个时间一行的 DataFrame，列为 `{time_col}, mean, std, median, count`
+    pub fn cross_sectional_stats(&self) -> Result<DataFrame> {
+        Ok(self.cross_section_stats()?.select([self.time_col.as_str(), "mean", "std", "median", "count"])?)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
     
-    #[test]
-    fn test_wide_table_creation() {
-        // 创建测试数据
-        let dates = vec![
-            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
-            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
-            NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
-        ];
+    /// 以另一张宽表为权重，计算每个时间截面上所有股票的加权平均
+    /// 
+    /// 权重通常为市值，`self` 为收益率时即得到市值加权的指数收益。
+    /// 按时间列把 `weights` 左连接到 `self` 上；只有两张表都有的股票参与加权，
+    /// 值或权重缺失（null、NaN，或权重表没有该时间）以及权重不为正的股票当期跳过。
+    /// 
+    /// # Arguments
+    /// * `weights` - 权重宽表（如市值），按时间列与股票列名对齐
+    /// 
+    /// # Returns
+    /// 名为 `value_weighted_mean` 的 Series，与按时间排序后的 `self` 逐行对应；
+    /// 没有任何有效股票的时间为 null
+    /// 
+    /// # Errors
+    /// 两张表没有共同的股票列时返回 [`WideTableError::InvalidArgument`]
+    pub fn value_weighted_mean(&self, weights: &WideTable) -> Result<Series> {
+        let weight_cols = weights.stock_cols();
+        let common: Vec<String> = self.stock_cols().into_iter().filter(|c| weight_cols.contains(c)).collect();
+        if common.is_empty() {
+            return Err(WideTableError::InvalidArgument("值宽表与权重宽表没有共同的股票列".to_string()));
+        }
         
-        let stock_a = vec![100.0, 102.0, 104.0];
-        let stock_b = vec![50.0, 51.0, 49.0];
+        let mut weight_exprs = vec![col(&weights.time_col).alias(&self.time_col)];
+        weight_exprs.extend(common.iter().map(|c| col(c).alias(&format!("__weight_{}", c))));
+        let df = self
+            .sorted_df()?
+            .lazy()
+            .join(
+                weights.lazy().select(weight_exprs),
+                [col(&self.time_col)],
+                [col(&self.time_col)],
+                JoinArgs::new(polars::prelude::JoinType::Left),
+            )
+            .collect()?;
         
-        let df = DataFrame::new(vec![
-            Series::new("date", dates),
-            Series::new("stock_A", stock_a),
-            Series::new("stock_B", stock_b),
-        ]).unwrap();
+        let column = |name: &str| -> Result<Vec<Option<f64>>> {
+            Ok(df.column(name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect())
+        };
+        let values = common.iter().map(|c| column(c)).collect::<Result<Vec<_>>>()?;
+        let weight_values = common
+            .iter()
+            .map(|c| column(&format!("__weight_{}", c)))
+            .collect::<Result<Vec<_>>>()?;
         
-        let table = WideTable::new(df, "date").unwrap();
-        assert_eq!(table.time_col(), "date");
+        let means: Vec<Option<f64>> = (0..df.height())
+            .map(|i| {
+                let (mut total, mut weighted) = (0.0, 0.0);
+                for (v, w) in values.iter().zip(&weight_values) {
+                    if let (Some(v), Some(w)) = (v[i], w[i]) {
+                        if !v.is_nan() && w > 0.0 {
+                            total += w;
+                            weighted += w * v;
+                        }
+                    }
+                }
+                (total > 0.0).then(|| weighted / total)
+            })
+            .collect();
+        Ok(Series::new("value_weighted_mean", means))
+    }
+    
+    /// 计算百分比变化（收益率）
+    /// 
+    /// 结果以百分比为单位（乘以 100），列名为 `{code}_pct_change_{periods}`。
+    /// 需要参与复利或累加运算时请使用 [`WideTable::pct_change_fraction`]，其他单位见 [`WideTable::pct_change_with`]。
+    /// 
+    /// # Arguments
+    /// * `periods` - 计算周期，例如 1 表示计算 1 期的收益率，必须为正数
+    /// 
+    /// # Returns
+    /// 返回新的 WideTable，包含收益率数据
+    /// 
+    /// # Errors
+    /// `periods <= 0` 时返回错误：负周期会计算未来收益率，造成前视偏差。
+    /// 需要未来收益率时请显式使用 [`WideTable::forward_returns`]。
+    /// 股票列不是数值类型（如 CSV 中被读成字符串）时返回 [`WideTableError::SchemaMismatch`]，
+    /// 基于收益率的 [`WideTable::momentum`] 等方法同样适用。
+    pub fn pct_change(&self, periods: i32) -> Result<WideTable> {
+        self.pct_change_with(periods, ReturnScale::Percent)
+    }
+    
+    /// 计算小数形式的收益率
+    /// 
+    /// 与 [`WideTable::pct_change`] 相同，但不乘以 100（0.01 表示 1%），
+    /// 列名为 `{code}_return_{periods}`。
+    /// 
+    /// # Arguments
+    /// * `periods` - 计算周期，必须为正数
+    pub fn pct_change_fraction(&self, periods: i32) -> Result<WideTable> {
+        self.pct_change_with(periods, ReturnScale::Fraction)
+    }
+    
+    /// 按指定单位计算收益率
+    /// 
+    /// 列名随单位变化，避免不同单位的结果混在一起：百分比为 `{code}_pct_change_{periods}`，
+    /// 小数为 `{code}_return_{periods}`，基点为 `{code}_bps_change_{periods}`。
+    /// 
+    /// # Arguments
+    /// * `periods` - 计算周期，必须为正数
+    /// * `scale` - 收益率单位
+    pub fn pct_change_with(&self, periods: i32, scale: ReturnScale) -> Result<WideTable> {
+        self.change_with_scale(periods, scale.factor(), scale.tag())
+    }
+    
+    /// 计算相对基准日的累计收益率
+    /// 
+    /// 每只股票的价格除以其在 `base_date` 的价格再减 1（小数），不同价位的股票可放在同一起点比较。
+    /// 结果在 `base_date` 当天为 0.0，列名不变。基准日价格缺失或为 0 的股票整列为 null。
+    /// 
+    /// # Arguments
+    /// * `base_date` - 基准日，必须存在于时间列中
+    /// 
+    /// # Errors
+    /// 时间列不是 `Date` 类型时返回 [`WideTableError::SchemaMismatch`]；
+    /// `base_date` 不在时间列中时返回 [`WideTableError::InvalidArgument`]
+    pub fn pct_change_from_base(&self, base_date: NaiveDate) -> Result<WideTable> {
+        let df = self.sorted_df()?;
+        let time = df.column(&self.time_col)?;
+        let dates = time.date().map_err(|_| WideTableError::SchemaMismatch(format!(
+            "时间列 '{}' 的类型为 {}，需要 Date",
+            self.time_col,
+            time.dtype()
+        )))?;
+        let base_idx = dates
+            .as_date_iter()
+            .position(|d| d == Some(base_date))
+            .ok_or_else(|| WideTableError::InvalidArgument(format!("基准日 {} 不在时间列中", base_date)))?;
+        
+        let mut exprs = Vec::new();
+        for col_name in self.stock_cols() {
+            let base = df.column(&col_name)?.cast(&DataType::Float64)?.f64()?.get(base_idx);
+            let expr = match base {
+                Some(base) if base != 0.0 => col(&col_name).cast(DataType::Float64) / lit(base) - lit(1.0),
+                _ => lit(NULL).cast(DataType::Float64),
+            };
+            exprs.push(expr.alias(&col_name));
+        }
+        
+        let new_df = df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算价格变化率（ROC）
+    /// 
+    /// `(P_t - P_{t-periods}) / P_{t-periods} * 100`，与 [`WideTable::pct_change`] 相同（百分比），
+    /// 只是列名为 `{code}_roc_{periods}`，便于与加速度因子配套使用。
+    /// 
+    /// # Arguments
+    /// * `periods` - 计算周期，必须为正数
+    pub fn roc(&self, periods: i32) -> Result<WideTable> {
+        self.change_with_scale(periods, 100.0, "roc")
+    }
+    
+    /// 计算价格加速度（动量的动量）
+    /// 
+    /// 相邻两个不重叠区间的 ROC 之差：`roc_t - roc_{t-periods}`，即价格的二阶差分。
+    /// 前 `2 * periods` 行为 null。
+    /// 
+    /// # Arguments
+    /// * `periods` - ROC 周期，必须为正数
+    /// 
+    /// # Returns
+    /// 返回同时包含 `{code}_roc_{periods}` 与 `{code}_accel_{periods}` 列的新 WideTable
+    pub fn acceleration(&self, periods: i32) -> Result<WideTable> {
+        let roc_table = self.roc(periods)?;
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                let roc = col(&format!("{}_roc_{}", col_name, periods));
+                (roc.clone() - roc.shift(lit(periods))).alias(&format!("{}_accel_{}", col_name, periods))
+            })
+            .collect();
+        
+        let new_df = roc_table.df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 一次计算多个周期的百分比收益率
+    /// 
+    /// 只排序一次，并在同一个 `with_columns` 中计算所有周期，
+    /// 比多次调用 [`WideTable::pct_change`] 更快。
+    /// 
+    /// # Arguments
+    /// * `periods` - 周期列表，每个都必须为正数且不能重复
+    /// 
+    /// # Returns
+    /// 返回包含原始价格列以及每个周期 `{code}_pct_change_{p}` 列的新 WideTable
+    pub fn pct_change_multi(&self, periods: &[i32]) -> Result<WideTable> {
+        if periods.is_empty() {
+            return Err(WideTableError::InvalidArgument("周期列表不能为空".to_string()));
+        }
+        let mut seen = HashSet::new();
+        for &p in periods {
+            if p <= 0 {
+                return Err(WideTableError::InvalidArgument(format!("收益率周期必须为正数，当前为 {}", p)));
+            }
+            if !seen.insert(p) {
+                return Err(WideTableError::InvalidArgument(format!("周期 {} 重复", p)));
+            }
+        }
+        
+        let stock_cols = self.stock_cols();
+        let exprs: Vec<Expr> = periods
+            .iter()
+            .flat_map(|&p| stock_cols.iter().map(move |c| change_expr(c, p, 100.0, "pct_change")))
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 按给定缩放系数计算收益率，输出 `{code}_{tag}_{periods}` 列
+    fn change_with_scale(&self, periods: i32, scale: f64, tag: &str) -> Result<WideTable> {
+        if periods <= 0 {
+            return Err(WideTableError::InvalidArgument(format!(
+                "收益率周期必须为正数，当前为 {}；计算未来收益率请使用 forward_returns",
+                periods
+            )));
+        }
+        
+        self.ensure_numeric_stock_cols()?;
+        
+        let mut df = self.df.clone();
+        
+        // 按时间列排序
+        df = df.sort([&self.time_col], SortOptions::default())?;
+        
+        // 获取所有列名（排除时间列）
+        let stock_cols: Vec<String> = df
+            .get_column_names()
+            .iter()
+            .filter(|&col| col != &self.time_col)
+            .map(|s| s.to_string())
+            .collect();
+        
+        // 所有股票列的表达式放进同一个 with_columns，由 polars 并行求值；
+        // 逐列链式调用 with_columns 会产生与列数等深的查询计划，列数上千时优化开销很大
+        let exprs: Vec<Expr> = stock_cols
+            .iter()
+            .map(|col_name| change_expr(col_name, periods, scale, tag))
+            .collect();
+        let new_df = df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算未来收益率（前向收益率）
+    /// 
+    /// 用于因子检验时的标签，`t` 行的值为 `t` 到 `t + periods` 的小数收益率（0.01 表示 1%），
+    /// 与 [`WideTable::momentum`]、[`WideTable::volatility`] 的单位一致；需要其他单位时使用
+    /// [`WideTable::forward_returns_with`]。结果包含未来信息，不能作为因子输入。
+    /// 
+    /// # Arguments
+    /// * `periods` - 前向周期，必须为正数
+    /// 
+    /// # Returns
+    /// 返回新的 WideTable，包含 `{code}_fwd_return_{periods}` 列
+    pub fn forward_returns(&self, periods: i32) -> Result<WideTable> {
+        self.forward_returns_with(periods, ReturnScale::Fraction)
+    }
+    
+    /// 按指定单位计算未来收益率
+    /// 
+    /// 与 [`WideTable::forward_returns`] 相同，结果再乘以 `scale` 的缩放系数。
+    /// 列名随单位变化：小数为 `{code}_fwd_return_{periods}`，百分比为 `{code}_fwd_return_pct_{periods}`，
+    /// 基点为 `{code}_fwd_return_bps_{periods}`。
+    /// 
+    /// # Arguments
+    /// * `periods` - 前向周期，必须为正数
+    /// * `scale` - 收益率单位
+    pub fn forward_returns_with(&self, periods: i32, scale: ReturnScale) -> Result<WideTable> {
+        if periods <= 0 {
+            return Err(WideTableError::InvalidArgument(format!("forward_returns 周期必须为正数，当前为 {}", periods)));
+        }
+        
+        self.ensure_numeric_stock_cols()?;
+        
+        // 负的 shift 取未来价格，与 change_with_scale 一样合并到同一个 with_columns
+        let tag = format!("fwd_return{}", scale.unit_suffix());
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| change_expr(col_name, -periods, scale.factor(), &tag))
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算动量因子
+    /// 
+    /// 动量因子通常定义为过去 N 期的累计收益率，
+    /// 基于小数收益率（[`WideTable::pct_change_fraction`]）累加，0.05 表示 5%
+    /// 
+    /// # Arguments
+    /// * `periods` - 动量周期，例如 20 表示过去 20 期的动量
+    /// 
+    /// # Returns
+    /// 返回包含动量因子的新 WideTable
+    pub fn momentum(&self, periods: i32) -> Result<WideTable> {
+        self.momentum_weighted(periods, MomentumWeighting::Equal)
+    }
+    
+    /// 按指定加权方式计算动量因子
+    /// 
+    /// 权重先归一化到和为 1，再乘以 `periods`，使结果与等权累计收益率同量纲：
+    /// 窗口内收益率全部相同时，两种加权的结果一致。
+    /// 线性衰减需要完整窗口，前 `periods` 行为 null。
+    /// 
+    /// # Arguments
+    /// * `periods` - 动量周期
+    /// * `weighting` - 加权方式
+    /// 
+    /// # Returns
+    /// 等权输出 `{code}_momentum_{periods}` 列，线性衰减输出 `{code}_momentum_decay_{periods}` 列
+    pub fn momentum_weighted(&self, periods: i32, weighting: MomentumWeighting) -> Result<WideTable> {
+        self.momentum_with(periods, weighting, ReturnScale::Fraction)
+    }
+    
+    /// 按指定加权方式与收益率单位计算动量因子
+    /// 
+    /// 与 [`WideTable::momentum_weighted`] 相同（其结果为小数），动量再乘以 `scale` 的缩放系数。
+    /// 列名随单位变化，避免不同单位的结果混在一起：小数为 `{code}_momentum_{periods}`，
+    /// 百分比为 `{code}_momentum_pct_{periods}`，基点为 `{code}_momentum_bps_{periods}`
+    /// （线性衰减为 `{code}_momentum_decay_{periods}` 等，规则相同）。
+    /// 
+    /// # Arguments
+    /// * `periods` - 动量周期
+    /// * `weighting` - 加权方式
+    /// * `scale` - 结果的收益率单位
+    pub fn momentum_with(&self, periods: i32, weighting: MomentumWeighting, scale: ReturnScale) -> Result<WideTable> {
+        if periods <= 0 {
+            return Err(WideTableError::InvalidArgument(format!("动量周期必须为正数，当前为 {}", periods)));
+        }
+        
+        // 先计算小数收益率
+        let ret_table = self.pct_change_fraction(1)?;
+        
+        let mut df = ret_table.df().clone();
+        
+        // 按时间列排序
+        df = df.sort([&self.time_col], SortOptions::default())?;
+        
+        // 获取所有收益率列名
+        let ret_cols: Vec<String> = df
+            .get_column_names()
+            .iter()
+            .filter(|&col| col != &self.time_col && col.ends_with("_return_1"))
+            .map(|s| s.to_string())
+            .collect();
+        
+        // 对每个收益率列计算滚动求和（动量），与 change_with_scale 一样合并到同一个 with_columns
+        let mut exprs = Vec::with_capacity(ret_cols.len());
+        
+        for ret_col in &ret_cols {
+            // 提取原始列名
+            let base_col = ret_col.strip_suffix("_return_1").unwrap_or(ret_col);
+            
+            let (expr, name) = match weighting {
+                MomentumWeighting::Equal => {
+                    // 计算过去 periods 期的累计收益率
+                    // 使用 rolling_sum 计算滚动窗口内的累计收益率
+                    let sum = col(ret_col).rolling_sum(RollingOptionsFixedWindow {
+                        window_size: periods as usize,
+                        min_periods: 1,
+                        center: false,
+                    });
+                    (sum, format!("{}_momentum{}_{}", base_col, scale.unit_suffix(), periods))
+                }
+                MomentumWeighting::LinearDecay => {
+                    // polars 的带权 rolling 不支持 null，这里用 shift 展开加权求和
+                    // 滞后 lag 期的收益率权重为 (periods - lag)，归一化后乘以 periods
+                    let norm = (periods * (periods + 1)) as f64 / 2.0;
+                    let mut weighted = lit(0.0);
+                    for lag in 0..periods {
+                        let weight = (periods - lag) as f64 * periods as f64 / norm;
+                        weighted = weighted + col(ret_col).shift(lit(lag)) * lit(weight);
+                    }
+                    (weighted, format!("{}_momentum_decay{}_{}", base_col, scale.unit_suffix(), periods))
+                }
+            };
+            
+            exprs.push((expr * lit(scale.factor())).alias(&name));
+        }
+        
+        let new_df = df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算成交量加权的动量因子
+    /// 
+    /// 窗口内每期的小数收益率以当期成交量（或成交额）为权重：`periods * Σ(r·v) / Σv`，
+    /// 与 [`WideTable::momentum_weighted`] 一样归一化后乘以窗口长度，成交量全部相同时等于等权动量。
+    /// 先用 [`WideTable::align`] 把两张表对齐到共有的时间，结果只包含共有的时间。
+    /// 窗口内收益率或成交量有缺失（含第一行收益率）时为 null，窗口内成交量之和为 0 时为 null。
+    /// 
+    /// # Arguments
+    /// * `volume` - 成交量宽表，须包含 `self` 的全部股票列
+    /// * `window` - 动量窗口长度，必须为正数
+    /// 
+    /// # Returns
+    /// 按时间排序、在对齐后的价格列之外增加 `{code}_momentum_vw_{window}` 列的新 WideTable
+    /// 
+    /// # Errors
+    /// `window` 为 0、成交量表缺少股票列或两表没有共同的时间时返回 [`WideTableError::InvalidArgument`]
+    pub fn volume_weighted_momentum(&self, volume: &WideTable, window: usize) -> Result<WideTable> {
+        self.volume_weighted_momentum_with(volume, window, ReturnScale::Fraction)
+    }
+    
+    /// 按指定收益率单位计算成交量加权的动量因子
+    /// 
+    /// 与 [`WideTable::volume_weighted_momentum`] 相同（其结果为小数），动量再乘以 `scale` 的缩放系数。
+    /// 列名随单位变化：小数为 `{code}_momentum_vw_{window}`，百分比为 `{code}_momentum_vw_pct_{window}`，
+    /// 基点为 `{code}_momentum_vw_bps_{window}`。
+    /// 
+    /// # Arguments
+    /// * `volume` - 成交量宽表，须包含 `self` 的全部股票列
+    /// * `window` - 动量窗口长度，必须为正数
+    /// * `scale` - 结果的收益率单位
+    pub fn volume_weighted_momentum_with(&self, volume: &WideTable, window: usize, scale: ReturnScale) -> Result<WideTable> {
+        if window == 0 {
+            return Err(WideTableError::InvalidArgument("动量窗口必须为正数".to_string()));
+        }
+        let volume_cols = volume.stock_cols();
+        if let Some(missing) = self.stock_cols().into_iter().find(|c| !volume_cols.contains(c)) {
+            return Err(WideTableError::InvalidArgument(format!("成交量宽表中缺少股票列 '{}'", missing)));
+        }
+        
+        let (prices, volume) = self.align(volume)?;
+        let stock_cols = prices.stock_cols();
+        let renamed: Vec<Series> = stock_cols
+            .iter()
+            .map(|c| -> Result<Series> {
+                Ok(volume.df.column(c)?.cast(&DataType::Float64)?.with_name(&format!("__volume_{}", c)))
+            })
+            .collect::<Result<_>>()?;
+        let df = prices.df.hstack(&renamed)?;
+        
+        let options = || RollingOptionsFixedWindow {
+            window_size: window,
+            min_periods: window,
+            center: false,
+        };
+        let exprs: Vec<Expr> = stock_cols
+            .iter()
+            .map(|c| {
+                let price = col(c).cast(DataType::Float64);
+                let ret = price.clone() / price.shift(lit(1)) - lit(1.0);
+                let vol = col(&format!("__volume_{}", c));
+                let weighted = (ret * vol.clone()).rolling_sum(options());
+                let total = vol.rolling_sum(options());
+                when(total.clone().gt(lit(0.0)))
+                    .then(weighted / total * lit(window as f64 * scale.factor()))
+                    .otherwise(lit(NULL).cast(DataType::Float64))
+                    .alias(&format!("{}_momentum_vw{}_{}", c, scale.unit_suffix(), window))
+            })
+            .collect();
+        let drops: Vec<String> = stock_cols.iter().map(|c| format!("__volume_{}", c)).collect();
+        let new_df = df.lazy().with_columns(exprs).drop(drops).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算跳过最近若干期的动量因子（如 12-1 动量）
+    /// 
+    /// 学术上的动量通常剔除最近一个月以规避短期反转：`t` 行的值为
+    /// `P_{t-skip} / P_{t-lookback} - 1`，即 `t - lookback` 到 `t - skip` 的小数复利收益率。
+    /// 月频数据上 `momentum_skip(12, 1)` 即经典的 12-1 动量。前 `lookback` 行为 null。
+    /// 
+    /// 以两段收益率之差表示的版本见 [`FactorCalculator::calculate_momentum_reversal`](crate::FactorCalculator::calculate_momentum_reversal)，单位同为小数。
+    /// 
+    /// # Arguments
+    /// * `lookback` - 回看期数，必须为正数
+    /// * `skip` - 跳过的最近期数，必须小于 `lookback`
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_momentum_{lookback}_{skip}` 列的新 WideTable
+    pub fn momentum_skip(&self, lookback: usize, skip: usize) -> Result<WideTable> {
+        if skip >= lookback {
+            return Err(WideTableError::InvalidArgument(format!(
+                "跳过期数 {} 必须小于回看期数 {}",
+                skip,
+                lookback
+            )));
+        }
+        
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                let price = col(col_name).cast(DataType::Float64);
+                (price.clone().shift(lit(skip as i64)) / price.shift(lit(lookback as i64)) - lit(1.0))
+                    .alias(&format!("{}_momentum_{}_{}", col_name, lookback, skip))
+            })
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算半衰期加权动量
+    /// 
+    /// 对小数收益率做指数加权平均，越近的收益率权重越高。半衰期与衰减系数的换算为
+    /// `alpha = 1 - 0.5^(1 / half_life)`，即滞后 `k` 期的收益率权重正比于 `0.5^(k / half_life)`，
+    /// 每经过 `half_life` 期权重减半。权重按已有样本归一化到和为 1（`adjust = true`），
+    /// 因此结果是"每期"的加权平均收益率：收益率恒为 `r` 时结果也是 `r`，与窗口长度无关。
+    /// 缺失的收益率直接跳过，从第一个有效收益率开始有值。
+    /// 
+    /// # Arguments
+    /// * `half_life` - 半衰期（期数），必须为有限正数
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_ewm_momentum` 列的新 WideTable
+    pub fn ewm_momentum(&self, half_life: f64) -> Result<WideTable> {
+        self.ewm_momentum_with(half_life, ReturnScale::Fraction)
+    }
+    
+    /// 按指定收益率单位计算半衰期加权动量
+    /// 
+    /// 与 [`WideTable::ewm_momentum`] 相同（其结果为小数），动量再乘以 `scale` 的缩放系数。
+    /// 列名随单位变化：小数为 `{code}_ewm_momentum`，百分比为 `{code}_ewm_momentum_pct`，
+    /// 基点为 `{code}_ewm_momentum_bps`。
+    /// 
+    /// # Arguments
+    /// * `half_life` - 半衰期（期数），必须为有限正数
+    /// * `scale` - 结果的收益率单位
+    pub fn ewm_momentum_with(&self, half_life: f64, scale: ReturnScale) -> Result<WideTable> {
+        if !(half_life.is_finite() && half_life > 0.0) {
+            return Err(WideTableError::InvalidArgument(format!("半衰期必须为有限正数，当前为 {}", half_life)));
+        }
+        
+        let alpha = 1.0 - 0.5f64.powf(1.0 / half_life);
+        let ret_table = self.pct_change_fraction(1)?;
+        let df = ret_table.df().sort([&self.time_col], SortOptions::default())?;
+        
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                (col(&format!("{}_return_1", col_name)).ewm_mean(EWMOptions {
+                    alpha,
+                    adjust: true,
+                    bias: false,
+                    min_periods: 1,
+                    ignore_nulls: true,
+                }) * lit(scale.factor()))
+                .alias(&format!("{}_ewm_momentum{}", col_name, scale.unit_suffix()))
+            })
+            .collect();
+        
+        let new_df = df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算指数移动平均（EMA）
+    /// 
+    /// `alpha = 2 / (span + 1)`，按递推式 `ema_t = alpha * x_t + (1 - alpha) * ema_{t-1}` 计算
+    /// （与 pandas 的 `ewm(span, adjust=False)` 一致），有效值不足 `span` 个时为 null
+    /// 
+    /// # Arguments
+    /// * `span` - 平滑跨度，必须为正数
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_ema_{span}` 列的新 WideTable
+    pub fn ema(&self, span: usize) -> Result<WideTable> {
+        if span == 0 {
+            return Err(WideTableError::InvalidArgument("EMA 跨度必须为正数".to_string()));
+        }
+        
+        let df = self.sorted_df()?;
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                ema_expr(col(col_name).cast(DataType::Float64), span).alias(&format!("{}_ema_{}", col_name, span))
+            })
+            .collect();
+        
+        let new_df = df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算简单移动平均（SMA）
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，窗口未满时为 null
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_sma_{window}` 列的新 WideTable
+    pub fn sma(&self, window: usize) -> Result<WideTable> {
+        if window == 0 {
+            return Err(WideTableError::InvalidArgument("SMA 窗口必须为正数".to_string()));
+        }
+        
+        // 与 change_with_scale 一样合并到同一个 with_columns，逐列链式调用在列数上千时会栈溢出
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                col(col_name)
+                    .rolling_mean(RollingOptionsFixedWindow {
+                        window_size: window,
+                        min_periods: window,
+                        center: false,
+                    })
+                    .alias(&format!("{}_sma_{}", col_name, window))
+            })
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算价格的滚动最高值
+    /// 
+    /// 用于通道突破类策略：价格接近滚动最高值即为突破信号。
+    /// 与 [`WideTable::sma`] 一样要求窗口内有 `window` 个有效值（`min_periods = window`），否则为 null。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，必须为正数
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_rollmax_{window}` 列的新 WideTable
+    pub fn rolling_max(&self, window: usize) -> Result<WideTable> {
+        self.rolling_extreme(window, "rollmax", Expr::rolling_max)
+    }
+    
+    /// 计算价格的滚动最低值，与 [`WideTable::rolling_max`] 对称
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_rollmin_{window}` 列的新 WideTable
+    pub fn rolling_min(&self, window: usize) -> Result<WideTable> {
+        self.rolling_extreme(window, "rollmin", Expr::rolling_min)
+    }
+    
+    /// 对每个股票列做滚动极值，输出 `{code}_{tag}_{window}` 列
+    fn rolling_extreme(
+        &self,
+        window: usize,
+        tag: &str,
+        extreme: fn(Expr, RollingOptionsFixedWindow) -> Expr,
+    ) -> Result<WideTable> {
+        if window == 0 {
+            return Err(WideTableError::InvalidArgument("滚动窗口必须为正数".to_string()));
+        }
+        
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                let options = RollingOptionsFixedWindow {
+                    window_size: window,
+                    min_periods: window,
+                    center: false,
+                };
+                extreme(col(col_name).cast(DataType::Float64), options)
+                    .alias(&format!("{}_{}_{}", col_name, tag, window))
+            })
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算回撤序列
+    /// 
+    /// 每只股票相对截至当期的历史最高价（`cum_max`）的回撤 `(price - running_max) / running_max`，
+    /// 取值不大于 0（小数）。价格缺失的行为 null，且不影响之后的历史最高价。
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_drawdown` 列的新 WideTable
+    pub fn drawdown_series(&self) -> Result<WideTable> {
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                let price = col(col_name).cast(DataType::Float64);
+                let running_max = price.clone().cum_max(false);
+                ((price - running_max.clone()) / running_max).alias(&format!("{}_drawdown", col_name))
+            })
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算滚动波动率
+    /// 
+    /// 波动率为过去 `window` 期小数收益率的样本标准差，窗口未满时为 null
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 2
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_volatility_{window}` 列的新 WideTable
+    pub fn volatility(&self, window: usize) -> Result<WideTable> {
+        self.volatility_with(window, ReturnScale::Fraction)
+    }
+    
+    /// 按指定收益率单位计算历史波动率
+    /// 
+    /// 与 [`WideTable::volatility`] 相同（其结果为小数），波动率再乘以 `scale` 的缩放系数。
+    /// 列名随单位变化：小数为 `{code}_volatility_{window}`，百分比为 `{code}_volatility_pct_{window}`，
+    /// 基点为 `{code}_volatility_bps_{window}`。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 2
+    /// * `scale` - 结果的收益率单位
+    pub fn volatility_with(&self, window: usize, scale: ReturnScale) -> Result<WideTable> {
+        if window < 2 {
+            return Err(WideTableError::InvalidArgument(format!("波动率窗口至少为 2，当前为 {}", window)));
+        }
+        
+        let ret_table = self.pct_change_fraction(1)?;
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                let ret_col = format!("{}_return_1", col_name);
+                let vol_col = format!("{}_volatility{}_{}", col_name, scale.unit_suffix(), window);
+                (col(&ret_col).rolling_std(RollingOptionsFixedWindow {
+                    window_size: window,
+                    min_periods: window,
+                    center: false,
+                }) * lit(scale.factor()))
+                .alias(&vol_col)
+            })
+            .collect();
+        
+        let new_df = ret_table.df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算指数加权（EWMA）波动率
+    /// 
+    /// 基于小数收益率递推 `sigma_t^2 = alpha * r_t^2 + (1 - alpha) * sigma_{t-1}^2`，
+    /// 以第一个有效收益率的平方为初值，结果取平方根。缺失的收益率会被跳过。
+    /// `alpha = 0.06`（即 λ = 0.94）对应 RiskMetrics 日频模型。
+    /// 
+    /// # Arguments
+    /// * `alpha` - 衰减系数，取值范围 `(0, 1]`
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_ewm_vol` 列的新 WideTable
+    pub fn ewm_volatility(&self, alpha: f64) -> Result<WideTable> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(WideTableError::InvalidArgument(format!("alpha 必须在 (0, 1] 内，当前为 {}", alpha)));
+        }
+        
+        let ret_table = self.pct_change_fraction(1)?;
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                col(&format!("{}_return_1", col_name))
+                    .pow(2)
+                    .ewm_mean(EWMOptions {
+                        alpha,
+                        adjust: false,
+                        bias: false,
+                        min_periods: 1,
+                        ignore_nulls: true,
+                    })
+                    .sqrt()
+                    .alias(&format!("{}_ewm_vol", col_name))
+            })
+            .collect();
+        
+        let new_df = ret_table.df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 用卡尔曼滤波提取价格趋势
+    /// 
+    /// 对每只股票的价格做一元局部水平模型的滤波（价格 = 随机游走的趋势 + 观测噪声），
+    /// 结果为每期的滤波状态估计，只使用当期及之前的数据。`process_noise` 相对
+    /// `observation_noise` 越小，趋势越平滑、对价格变化的反应越慢。
+    /// 缺失价格只做预测（沿用上一期估计），第一个有效价格之前为 null。
+    /// 
+    /// # Arguments
+    /// * `process_noise` - 状态方程的噪声方差，不能为负数
+    /// * `observation_noise` - 观测方程的噪声方差，必须为正数
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_kalman_trend` 列的新 WideTable
+    pub fn kalman_trend(&self, process_noise: f64, observation_noise: f64) -> Result<WideTable> {
+        if !(process_noise >= 0.0 && observation_noise > 0.0) {
+            return Err(WideTableError::InvalidArgument(format!(
+                "噪声方差须满足 process_noise >= 0 且 observation_noise > 0，当前为 {} 与 {}",
+                process_noise,
+                observation_noise
+            )));
+        }
+        
+        let mut df = self.sorted_df()?;
+        for name in self.stock_cols() {
+            let prices: Vec<Option<f64>> = df.column(&name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let trend = stats::local_level_filter(&prices, process_noise, observation_noise);
+            df.with_column(Series::new(&format!("{}_kalman_trend", name), trend))?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算收益率的滚动偏度
+    /// 
+    /// 基于小数收益率，使用偏差修正的样本偏度（与 pandas 一致）。
+    /// 窗口内有效收益率少于 3 个或收益率恒定时为 null。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 3
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_return_1` 与 `{code}_skew_{window}` 列的新 WideTable
+    pub fn rolling_skew(&self, window: usize) -> Result<WideTable> {
+        self.rolling_skew_with(window, ReturnScale::Fraction)
+    }
+    
+    /// 按指定收益率单位计算滚动偏度
+    /// 
+    /// 偏度对收益率的缩放不变，`{code}_skew_{window}` 与 [`WideTable::rolling_skew`] 相同；
+    /// `scale` 决定结果中保留的单期收益率列的单位与列名（规则同 [`WideTable::pct_change_with`]）。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 3
+    /// * `scale` - 保留的收益率列的单位
+    pub fn rolling_skew_with(&self, window: usize, scale: ReturnScale) -> Result<WideTable> {
+        self.rolling_return_stat(window, 3, "skew", stats::skew, scale)
+    }
+    
+    /// 计算收益率的滚动峰度
+    /// 
+    /// 基于小数收益率，使用偏差修正的样本超额峰度（与 pandas 一致）。
+    /// 窗口内有效收益率少于 4 个或收益率恒定时为 null。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 4
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_return_1` 与 `{code}_kurt_{window}` 列的新 WideTable
+    pub fn rolling_kurtosis(&self, window: usize) -> Result<WideTable> {
+        self.rolling_kurtosis_with(window, ReturnScale::Fraction)
+    }
+    
+    /// 按指定收益率单位计算滚动峰度
+    /// 
+    /// 峰度对收益率的缩放不变，`{code}_kurt_{window}` 与 [`WideTable::rolling_kurtosis`] 相同；
+    /// `scale` 决定结果中保留的单期收益率列的单位与列名（规则同 [`WideTable::pct_change_with`]）。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 4
+    /// * `scale` - 保留的收益率列的单位
+    pub fn rolling_kurtosis_with(&self, window: usize, scale: ReturnScale) -> Result<WideTable> {
+        self.rolling_return_stat(window, 4, "kurt", stats::kurtosis, scale)
+    }
+    
+    /// 在小数收益率上做滚动统计，输出 `scale` 单位的单期收益率列与 `{code}_{tag}_{window}` 列
+    fn rolling_return_stat(
+        &self,
+        window: usize,
+        min_valid: usize,
+        tag: &str,
+        stat: fn(&[f64]) -> Option<f64>,
+        scale: ReturnScale,
+    ) -> Result<WideTable> {
+        if window < min_valid {
+            return Err(WideTableError::InvalidArgument(format!("{} 窗口至少为 {}，当前为 {}", tag, min_valid, window)));
+        }
+        
+        let stock_cols = self.stock_cols();
+        // 统计量始终在小数收益率上计算，避免缩放后 ZERO_TOLERANCE 的判断随单位变化
+        let fraction = self.pct_change_fraction(1)?.df;
+        let mut df = match scale {
+            ReturnScale::Fraction => fraction.clone(),
+            _ => self.pct_change_with(1, scale)?.df,
+        };
+        
+        for col_name in &stock_cols {
+            let returns: Vec<Option<f64>> = fraction
+                .column(&format!("{}_return_1", col_name))?
+                .f64()?
+                .into_iter()
+                .collect();
+            let values = stats::rolling_window_apply(&returns, window, min_valid, stat);
+            df.with_column(Series::new(&format!("{}_{}_{}", col_name, tag, window), values))?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 对每个股票列做滚动窗口的自定义计算
+    /// 
+    /// 窗口未满时为 null；窗口内的 null 会被剔除，只把非 null 值传给 `f`，
+    /// 若窗口内全部为 null 则结果为 null。
+    /// 
+    /// 注意：`f` 在 Rust 侧逐窗口调用，无法利用 polars 的向量化和查询优化，
+    /// 复杂度为 O(行数 × window)，大表上明显慢于原生表达式，适合快速验证新因子。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度
+    /// * `f` - 作用于窗口内数值的函数
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_rolling_apply_{window}` 列的新 WideTable
+    pub fn rolling_apply<F>(&self, window: usize, f: F) -> Result<WideTable>
+    where
+        F: Fn(&[f64]) -> f64,
+    {
+        if window == 0 {
+            return Err(WideTableError::InvalidArgument("滚动窗口必须为正数".to_string()));
+        }
+        
+        let mut df = self.df.clone();
+        
+        // 按时间列排序
+        df = df.sort([&self.time_col], SortOptions::default())?;
+        
+        let mut buf = Vec::with_capacity(window);
+        for col_name in &self.stock_cols() {
+            let values: Vec<Option<f64>> = df
+                .column(col_name)?
+                .cast(&DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .collect();
+            
+            let result: Float64Chunked = (0..values.len())
+                .map(|i| {
+                    if i + 1 < window {
+                        return None;
+                    }
+                    buf.clear();
+                    buf.extend(values[i + 1 - window..=i].iter().flatten());
+                    if buf.is_empty() {
+                        None
+                    } else {
+                        Some(f(&buf))
+                    }
+                })
+                .collect();
+            
+            let out_col = format!("{}_rolling_apply_{}", col_name, window);
+            df.with_column(result.with_name(&out_col).into_series())?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算时间序列滚动分位排名
+    /// 
+    /// 当前值在包含当期的最近 `window` 个观测中的百分位排名：
+    /// `(小于当前值的个数 + 0.5 * 与当前值相等的其他值个数) / (window - 1)`，
+    /// 取值在 `[0, 1]` 内，窗口最小值为 0、最大值为 1。
+    /// 与截面排名 [`WideTable::rank_cross_section`] 不同，衡量的是相对自身历史的位置。
+    /// 窗口未满或窗口内有缺失值（null 或 NaN）时为 null。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，至少为 2
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_ts_rank_{window}` 列的新 WideTable
+    pub fn rolling_rank(&self, window: usize) -> Result<WideTable> {
+        if window < 2 {
+            return Err(WideTableError::InvalidArgument(format!("滚动窗口至少为 2，当前为 {}", window)));
+        }
+        
+        let mut df = self.sorted_df()?;
+        for col_name in &self.stock_cols() {
+            let values: Vec<Option<f64>> = df
+                .column(col_name)?
+                .cast(&DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|v| v.filter(|x| !x.is_nan()))
+                .collect();
+            
+            let result: Float64Chunked = (0..values.len())
+                .map(|i| {
+                    if i + 1 < window {
+                        return None;
+                    }
+                    let current = values[i]?;
+                    let (mut below, mut ties) = (0.0, 0.0);
+                    for v in &values[i + 1 - window..i] {
+                        let v = (*v)?;
+                        if v < current {
+                            below += 1.0;
+                        } else if v == current {
+                            ties += 1.0;
+                        }
+                    }
+                    Some((below + 0.5 * ties) / (window - 1) as f64)
+                })
+                .collect();
+            
+            let out_col = format!("{}_ts_rank_{}", col_name, window);
+            df.with_column(result.with_name(&out_col).into_series())?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算每个股票列的滚动分位数
+    /// 
+    /// 分位数按线性插值计算（与 numpy 默认一致），`q = 0.5` 即滚动中位数。
+    /// 与 [`WideTable::sma`] 一样要求窗口内有 `window` 个有效值（null 与 NaN 不计），否则为 null。
+    /// 
+    /// # Arguments
+    /// * `window` - 滚动窗口长度，必须为正数
+    /// * `q` - 分位数，取值范围 `[0, 1]`
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_roll_q{q}_{window}` 列的新 WideTable，例如 `stock_A_roll_q0.5_20`
+    pub fn rolling_quantile(&self, window: usize, q: f64) -> Result<WideTable> {
+        if window == 0 {
+            return Err(WideTableError::InvalidArgument("滚动窗口必须为正数".to_string()));
+        }
+        if !(0.0..=1.0).contains(&q) {
+            return Err(WideTableError::InvalidArgument(format!("分位数必须在 [0, 1] 内，当前为 {}", q)));
+        }
+        
+        let mut df = self.sorted_df()?;
+        for col_name in &self.stock_cols() {
+            let values: Vec<Option<f64>> = df.column(col_name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let result = stats::rolling_window_apply(&values, window, window, |w| {
+                let mut sorted = w.to_vec();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                Some(stats::quantile_sorted(&sorted, q))
+            });
+            df.with_column(Series::new(&format!("{}_roll_q{}_{}", col_name, q, window), result))?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算每个股票列与外部序列（如指数收益率）的滚动皮尔逊相关系数
+    /// 
+    /// `other` 按排序后的行与宽表逐行对应。窗口内须有 `window` 对有效值（非 null、非 NaN），
+    /// 否则为 null；任一序列在窗口内没有波动时同样为 null。
+    /// 
+    /// # Arguments
+    /// * `other` - 外部序列，长度须与宽表行数一致
+    /// * `window` - 滚动窗口长度，至少为 2
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_corr_{window}` 列的新 WideTable
+    pub fn rolling_correlation_to_series(&self, other: &Series, window: usize) -> Result<WideTable> {
+        if window < 2 {
+            return Err(WideTableError::InvalidArgument(format!("滚动窗口至少为 2，当前为 {}", window)));
+        }
+        if other.len() != self.df.height() {
+            return Err(WideTableError::InvalidArgument(format!(
+                "外部序列长度 {} 与宽表行数 {} 不一致",
+                other.len(),
+                self.df.height()
+            )));
+        }
+        
+        let mut df = self.sorted_df()?;
+        let target: Vec<Option<f64>> = other.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+        let valid = |v: Option<f64>| v.filter(|v| !v.is_nan());
+        
+        let (mut xs, mut ys) = (Vec::with_capacity(window), Vec::with_capacity(window));
+        for col_name in &self.stock_cols() {
+            let values: Vec<Option<f64>> = df.column(col_name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let result: Float64Chunked = (0..values.len())
+                .map(|i| {
+                    if i + 1 < window {
+                        return None;
+                    }
+                    xs.clear();
+                    ys.clear();
+                    for j in i + 1 - window..=i {
+                        if let (Some(x), Some(y)) = (valid(values[j]), valid(target[j])) {
+                            xs.push(x);
+                            ys.push(y);
+                        }
+                    }
+                    if xs.len() < window {
+                        return None;
+                    }
+                    stats::pearson(&xs, &ys)
+                })
+                .collect();
+            
+            let out_col = format!("{}_corr_{}", col_name, window);
+            df.with_column(result.with_name(&out_col).into_series())?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 计算两张宽表之间每一对股票列的滚动协方差
+    /// 
+    /// 与 [`WideTable::rolling_cov_table`] 相同，结果按 `(self 的列名, other 的列名)` 索引，
+    /// 每个 Series 与 [`WideTable::rolling_cov_table`] 结果中的行逐行对应（两表共有的时间，按时间排序）
+    pub fn rolling_cov(&self, other: &WideTable, window: usize) -> Result<HashMap<(String, String), Series>> {
+        let table = self.rolling_cov_table(other, window)?;
+        let mut pairs = HashMap::new();
+        for left in self.stock_cols() {
+            for right in other.stock_cols() {
+                let name = format!("{}:{}", left, right);
+                pairs.insert((left.clone(), right), table.df.column(&name)?.clone());
+            }
+        }
+        Ok(pairs)
+    }
+    
+    /// 计算两张宽表之间每一对股票列的滚动协方差，结果展平为宽表
+    /// 
+    /// 作为动态风险模型（如滚动 Ledoit-Wolf 收缩、DCC-GARCH）的基础：通常传入两张收益率宽表，
+    /// 传入同一张表即得到滚动协方差矩阵的各元素。两表按时间列内连接，只保留共有的时间。
+    /// 使用样本协方差（自由度 `window - 1`），窗口内须有 `window` 对有效值（非 null、非 NaN），否则为 null。
+    /// 
+    /// # Arguments
+    /// * `other` - 另一张宽表
+    /// * `window` - 滚动窗口长度，至少为 2
+    /// 
+    /// # Returns
+    /// 按时间排序的新 WideTable，时间列名与 `self` 相同，
+    /// 每一对股票列输出一列，列名为 `{self 的列名}:{other 的列名}`
+    /// 
+    /// # Errors
+    /// `window` 小于 2 或两表没有共同的时间时返回 [`WideTableError::InvalidArgument`]
+    pub fn rolling_cov_table(&self, other: &WideTable, window: usize) -> Result<WideTable> {
+        if window < 2 {
+            return Err(WideTableError::InvalidArgument(format!("滚动窗口至少为 2，当前为 {}", window)));
+        }
+        
+        let left_cols = self.stock_cols();
+        let right_cols = other.stock_cols();
+        let mut left_exprs = vec![col(&self.time_col)];
+        left_exprs.extend(left_cols.iter().map(|c| col(c).cast(DataType::Float64).alias(&format!("__left_{}", c))));
+        let mut right_exprs = vec![col(&other.time_col).alias(&self.time_col)];
+        right_exprs.extend(right_cols.iter().map(|c| col(c).cast(DataType::Float64).alias(&format!("__right_{}", c))));
+        let joined = self
+            .lazy()
+            .select(left_exprs)
+            .join(
+                other.lazy().select(right_exprs),
+                [col(&self.time_col)],
+                [col(&self.time_col)],
+                JoinArgs::new(polars::prelude::JoinType::Inner),
+            )
+            .sort([&self.time_col], SortMultipleOptions::default())
+            .collect()?;
+        if joined.height() == 0 {
+            return Err(WideTableError::InvalidArgument("两个宽表没有共同的时间".to_string()));
+        }
+        
+        let column = |name: String| -> Result<Vec<Option<f64>>> {
+            Ok(joined.column(&name)?.f64()?.into_iter().map(|v| v.filter(|x| !x.is_nan())).collect())
+        };
+        let lefts = left_cols.iter().map(|c| column(format!("__left_{}", c))).collect::<Result<Vec<_>>>()?;
+        let rights = right_cols.iter().map(|c| column(format!("__right_{}", c))).collect::<Result<Vec<_>>>()?;
+        
+        let mut columns = vec![joined.column(&self.time_col)?.clone()];
+        let (mut xs, mut ys) = (Vec::with_capacity(window), Vec::with_capacity(window));
+        for (left_name, x) in left_cols.iter().zip(&lefts) {
+            for (right_name, y) in right_cols.iter().zip(&rights) {
+                let cov: Vec<Option<f64>> = (0..joined.height())
+                    .map(|i| {
+                        if i + 1 < window {
+                            return None;
+                        }
+                        xs.clear();
+                        ys.clear();
+                        for (a, b) in x[i + 1 - window..=i].iter().zip(&y[i + 1 - window..=i]) {
+                            if let (Some(a), Some(b)) = (a, b) {
+                                xs.push(*a);
+                                ys.push(*b);
+                            }
+                        }
+                        if xs.len() < window {
+                            return None;
+                        }
+                        stats::covariance(&xs, &ys)
+                    })
+                    .collect();
+                columns.push(Series::new(&format!("{}:{}", left_name, right_name), cov));
+            }
+        }
+        
+        Ok(WideTable {
+            df: DataFrame::new(columns)?,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 对每个时间截面上的股票做排名
+    /// 
+    /// 按值从小到大排名，名次从 1 开始。null 与 NaN 不参与排名，结果保持 null。
+    /// 输出列名与输入一致，只替换数值。
+    /// 
+    /// # Arguments
+    /// * `method` - 相同值的排名方式
+    /// * `percentile` - 为 true 时名次除以该截面有效股票数，缩放到 `(0, 1]`
+    pub fn rank_cross_section(&self, method: RankMethod, percentile: bool) -> Result<WideTable> {
+        self.map_cross_section(|row| {
+            let mut valid: Vec<(usize, f64)> = row
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| v.filter(|x| !x.is_nan()).map(|x| (i, x)))
+                .collect();
+            // 稳定排序保证 Ordinal 下相同值按列顺序排名
+            valid.sort_by(|a, b| a.1.total_cmp(&b.1));
+            
+            let n = valid.len();
+            let mut ranks = vec![None; row.len()];
+            let mut dense = 0.0;
+            let mut start = 0;
+            while start < n {
+                let mut end = start;
+                while end + 1 < n && valid[end + 1].1 == valid[start].1 {
+                    end += 1;
+                }
+                dense += 1.0;
+                for (offset, &(idx, _)) in valid[start..=end].iter().enumerate() {
+                    let rank = match method {
+                        RankMethod::Average => (start + end) as f64 / 2.0 + 1.0,
+                        RankMethod::Min => (start + 1) as f64,
+                        RankMethod::Max => (end + 1) as f64,
+                        RankMethod::Dense => dense,
+                        RankMethod::Ordinal => (start + offset + 1) as f64,
+                    };
+                    ranks[idx] = Some(if percentile { rank / n as f64 } else { rank });
+                }
+                start = end + 1;
+            }
+            ranks
+        })
+    }
+    
+    /// 在每个时间截面上把股票按值分入 `1..=n_buckets` 组
+    /// 
+    /// 按值从小到大排序后，第 `j` 个（从 0 开始）有效股票分入 `floor(j * n_buckets / n) + 1` 组，
+    /// `n` 为该截面有效股票数。null 与 NaN 不分组（结果为 null），相同值按列顺序稳定排序，
+    /// 因此分界点上的相同值可能落入相邻的两组。有效股票数少于 `n_buckets` 时组号不连续
+    /// （如 2 只股票分 5 组得到 1 和 3），需要其他处理方式时使用 [`WideTable::cross_qcut_with`]。
+    /// 
+    /// # Arguments
+    /// * `n_buckets` - 分组数
+    /// 
+    /// # Returns
+    /// 列名与输入一致、值为 Int32 组号的新 WideTable
+    pub fn quantile_buckets(&self, n_buckets: usize) -> Result<WideTable> {
+        self.rank_buckets(n_buckets, TieBreak::ColumnOrder, SparseGroupPolicy::Spread)
+    }
+    
+    /// 在每个时间截面上按排名把股票分入 `1..=n_groups` 组
+    /// 
+    /// 有效股票数少于分组数时报错，其他规则见 [`WideTable::cross_qcut_with`]
+    pub fn cross_qcut(&self, n_groups: usize) -> Result<WideTable> {
+        self.cross_qcut_with(n_groups, SparseGroupPolicy::Error)
+    }
+    
+    /// 在每个时间截面上按排名把股票分入 `1..=n_groups` 组，作为分组回测的基础
+    /// 
+    /// 按值从小到大排序（相同值按列名排序）后，第 `j` 个（从 0 开始）有效股票分入
+    /// `floor(j * n_groups / n) + 1` 组，`n` 为该截面有效股票数。null 与 NaN 不分组（结果为 null），
+    /// 没有任何有效值的截面整行为 null。与 [`WideTable::quantile_buckets`] 的区别在于
+    /// 相同值的先后与列顺序无关，且有效股票数不足时按 `policy` 处理；
+    /// 两者共用同一实现，没有相同值时 `policy` 为 [`SparseGroupPolicy::Spread`] 的结果与其一致。
+    /// 
+    /// # Arguments
+    /// * `n_groups` - 分组数，必须为正数
+    /// * `policy` - 有效股票数少于 `n_groups` 时的处理方式
+    /// 
+    /// # Returns
+    /// 列名与输入一致、值为 Int32 组号的新 WideTable
+    /// 
+    /// # Errors
+    /// `n_groups` 为 0，或 `policy` 为 [`SparseGroupPolicy::Error`] 且某个截面有效股票数不足时
+    /// 返回 [`WideTableError::InvalidArgument`]
+    pub fn cross_qcut_with(&self, n_groups: usize, policy: SparseGroupPolicy) -> Result<WideTable> {
+        self.rank_buckets(n_groups, TieBreak::ColumnName, policy)
+    }
+    
+    /// [`WideTable::quantile_buckets`] 与 [`WideTable::cross_qcut_with`] 的共同实现：
+    /// 每个截面按值排序（相同值按 `tie_break`），第 `j` 个有效股票分入 `floor(j * k / n) + 1` 组，
+    /// `k` 为 `n_groups`，有效股票数不足且 `policy` 为 [`SparseGroupPolicy::Collapse`] 时为 `n`
+    fn rank_buckets(&self, n_groups: usize, tie_break: TieBreak, policy: SparseGroupPolicy) -> Result<WideTable> {
+        if n_groups == 0 {
+            return Err(WideTableError::InvalidArgument("分组数必须为正数".to_string()));
+        }
+        
+        let stock_cols = self.stock_cols();
+        let mut name_order: Vec<usize> = (0..stock_cols.len()).collect();
+        if tie_break == TieBreak::ColumnName {
+            name_order.sort_by(|&a, &b| stock_cols[a].cmp(&stock_cols[b]));
+        }
+        let mut name_rank = vec![0; stock_cols.len()];
+        for (rank, &idx) in name_order.iter().enumerate() {
+            name_rank[idx] = rank;
+        }
+        
+        let mut row_index = 0;
+        let mut sparse_row = None;
+        let groups = self.map_cross_section(|row| {
+            let mut valid: Vec<(usize, f64)> = row
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| v.filter(|x| !x.is_nan()).map(|x| (i, x)))
+                .collect();
+            valid.sort_by(|a, b| a.1.total_cmp(&b.1).then(name_rank[a.0].cmp(&name_rank[b.0])));
+            
+            let n = valid.len();
+            if n > 0 && n < n_groups && sparse_row.is_none() {
+                sparse_row = Some((row_index, n));
+            }
+            row_index += 1;
+            let k = if policy == SparseGroupPolicy::Collapse { n_groups.min(n) } else { n_groups };
+            let mut out = vec![None; row.len()];
+            for (j, (idx, _)) in valid.into_iter().enumerate() {
+                out[idx] = Some((j * k / n + 1) as f64);
+            }
+            out
+        })?;
+        
+        if let (SparseGroupPolicy::Error, Some((i, n))) = (policy, sparse_row) {
+            let time = self.df.column(&self.time_col)?.get(i).map(|t| t.to_string()).unwrap_or_default();
+            return Err(WideTableError::InvalidArgument(format!(
+                "{} 的有效股票数 {} 少于分组数 {}",
+                time, n, n_groups
+            )));
+        }
+        
+        let exprs: Vec<Expr> = stock_cols.iter().map(|name| col(name).cast(DataType::Int32)).collect();
+        let new_df = groups.df.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 截面标准化（z-score）
+    /// 
+    /// 每个时间截面上 `(x - mean) / std`，`std` 为样本标准差。NaN 与 null 不参与计算且结果为 null；
+    /// 截面有效值少于 2 个或没有波动时整行为 null。列名不变。
+    pub fn zscore_cross_section(&self) -> Result<WideTable> {
+        self.map_cross_section(|row| {
+            let valid: Vec<f64> = row.iter().flatten().copied().filter(|x| !x.is_nan()).collect();
+            let n = valid.len();
+            if n < 2 {
+                return vec![None; row.len()];
+            }
+            let mean = valid.iter().sum::<f64>() / n as f64;
+            let std = (valid.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1) as f64).sqrt();
+            if std == 0.0 {
+                return vec![None; row.len()];
+            }
+            row.iter()
+                .map(|v| v.filter(|x| !x.is_nan()).map(|x| (x - mean) / std))
+                .collect()
+        })
+    }
+    
+    /// 截面去均值
+    /// 
+    /// 每个时间截面上各股票的值减去截面均值，比 [`WideTable::zscore_cross_section`] 少一步缩放。
+    /// NaN 与 null 不参与计算且结果为 null。列名不变。
+    pub fn cross_demean(&self) -> Result<WideTable> {
+        self.map_cross_section(|row| {
+            let valid: Vec<f64> = row.iter().flatten().copied().filter(|x| !x.is_nan()).collect();
+            let mean = valid.iter().sum::<f64>() / valid.len() as f64;
+            row.iter().map(|v| v.filter(|x| !x.is_nan()).map(|x| x - mean)).collect()
+        })
+    }
+    
+    /// 截面 min-max 缩放
+    /// 
+    /// 每个时间截面上 `(x - min) / (max - min)`，结果在 `[0, 1]` 内，适合作为有界的机器学习特征。
+    /// 截面所有有效值都相同时结果为 0.5。NaN 与 null 不参与计算且结果为 null。列名不变。
+    pub fn cross_minmax(&self) -> Result<WideTable> {
+        self.map_cross_section(|row| {
+            let valid = row.iter().flatten().copied().filter(|x| !x.is_nan());
+            let (min, max) = valid.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| (lo.min(x), hi.max(x)));
+            row.iter()
+                .map(|v| {
+                    v.filter(|x| !x.is_nan())
+                        .map(|x| if max > min { (x - min) / (max - min) } else { 0.5 })
+                })
+                .collect()
+        })
+    }
+    
+    /// 时间序列标准化（z-score）
+    /// 
+    /// 与 [`WideTable::zscore_cross_section`] 不同，沿时间方向对每只股票做 `(x - mean) / std`，
+    /// `std` 为样本标准差。价格的滚动 z-score 是常用的均值回复因子。
+    /// 标准差为 0 时结果为 null。
+    /// 
+    /// # Arguments
+    /// * `window` - `None` 时使用全部历史；`Some(w)` 时使用过去 `w` 期的滚动窗口，
+    ///   窗口未满时为 null，`w` 至少为 2
+    /// 
+    /// # Returns
+    /// 返回包含 `{code}_zscore` 列的新 WideTable
+    pub fn time_series_zscore(&self, window: Option<usize>) -> Result<WideTable> {
+        if window.is_some_and(|w| w < 2) {
+            return Err(WideTableError::InvalidArgument(format!(
+                "z-score 窗口至少为 2，当前为 {}",
+                window.unwrap_or_default()
+            )));
+        }
+        
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|col_name| {
+                let x = col(col_name).cast(DataType::Float64);
+                let (mean, std) = match window {
+                    Some(w) => {
+                        let options = RollingOptionsFixedWindow {
+                            window_size: w,
+                            min_periods: w,
+                            center: false,
+                        };
+                        (x.clone().rolling_mean(options.clone()), x.clone().rolling_std(options))
+                    }
+                    None => (x.clone().mean(), x.clone().std(1)),
+                };
+                when(std.clone().gt(lit(0.0)))
+                    .then((x - mean) / std)
+                    .otherwise(lit(NULL).cast(DataType::Float64))
+                    .alias(&format!("{}_zscore", col_name))
+            })
+            .collect();
+        
+        let new_df = self.sorted_df()?.lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 截面缩尾（winsorize）
+    /// 
+    /// 每个时间截面上把低于 `lower` 分位数、高于 `upper` 分位数的值分别截断到对应分位数，
+    /// 即 [`WideTable::winsorize`] 的 [`WinsorMethod::Quantile`]。
+    /// 
+    /// # Arguments
+    /// * `lower` - 下分位数，例如 0.01
+    /// * `upper` - 上分位数，例如 0.99，须满足 `0 <= lower <= upper <= 1`
+    pub fn winsorize_cross_section(&self, lower: f64, upper: f64) -> Result<WideTable> {
+        self.winsorize(WinsorMethod::Quantile(lower, upper))
+    }
+    
+    /// 按指定方法做截面去极值
+    /// 
+    /// 每个时间截面上由该截面的有效值计算上下界，超出边界的值截断到边界（而不是置为 null）。
+    /// NaN 与 null 不参与计算且结果为 null。`Mad`、`Sigma` 的离散程度为 0
+    /// （或 `Sigma` 有效值少于 2 个）时该截面原样保留。列名不变。
+    /// 
+    /// # Arguments
+    /// * `method` - 去极值方法，例如 `WinsorMethod::Mad(3.0)`
+    /// 
+    /// # Errors
+    /// 倍数不为正数或分位数不满足 `0 <= lower <= upper <= 1` 时返回 [`WideTableError::InvalidArgument`]
+    pub fn winsorize(&self, method: WinsorMethod) -> Result<WideTable> {
+        check_winsor_method(method)?;
+        self.map_cross_section(|row| {
+            let bounds = winsor_bounds(row, method);
+            row.iter()
+                .map(|v| {
+                    let x = v.filter(|x| !x.is_nan())?;
+                    Some(bounds.map_or(x, |(lo, hi)| x.clamp(lo, hi)))
+                })
+                .collect()
+        })
+    }
+    
+    /// 按指定方法做截面去极值，超出边界的值置为 null
+    /// 
+    /// 与 [`WideTable::mask_outliers_with_counts`] 相同，只返回处理后的宽表
+    pub fn mask_outliers(&self, method: WinsorMethod) -> Result<WideTable> {
+        Ok(self.mask_outliers_with_counts(method)?.0)
+    }
+    
+    /// 按指定方法做截面去极值，超出边界的值置为 null，并返回每只股票被置空的个数
+    /// 
+    /// 边界的计算与 [`WideTable::winsorize`] 相同，区别在于超出边界的值被剔除而不是截断到边界，
+    /// 适合疑似数据错误、不希望保留在边界上的值。边界内的值原样保留，NaN 与 null 结果为 null
+    /// （不计入被置空的个数）。列名不变。
+    /// 
+    /// # Arguments
+    /// * `method` - 去极值方法，例如 `WinsorMethod::Mad(5.0)`
+    /// 
+    /// # Returns
+    /// `(新 WideTable, 股票列名 -> 被置空的值的个数)`，便于监控数据质量
+    /// 
+    /// # Errors
+    /// 与 [`WideTable::winsorize`] 相同
+    pub fn mask_outliers_with_counts(&self, method: WinsorMethod) -> Result<(WideTable, HashMap<String, usize>)> {
+        check_winsor_method(method)?;
+        let stock_cols = self.stock_cols();
+        let mut counts = vec![0; stock_cols.len()];
+        let masked = self.map_cross_section(|row| {
+            let bounds = winsor_bounds(row, method);
+            row.iter()
+                .zip(counts.iter_mut())
+                .map(|(v, count)| {
+                    let x = v.filter(|x| !x.is_nan())?;
+                    match bounds {
+                        Some((lo, hi)) if x < lo || x > hi => {
+                            *count += 1;
+                            None
+                        }
+                        _ => Some(x),
+                    }
+                })
+                .collect()
+        })?;
+        Ok((masked, stock_cols.into_iter().zip(counts).collect()))
+    }
+    
+    /// 分组中性化：每个时间截面上，各股票的值减去所在分组的均值
+    /// 
+    /// 去除因子中的行业等分组偏向。NaN 与 null 不参与分组均值计算且结果保持缺失。
+    /// 没有分组映射的股票原样保留，见 [`WideTable::neutralize_by_group_with`]。
+    /// 
+    /// # Arguments
+    /// * `groups` - 股票代码到分组标签的映射
+    pub fn neutralize_by_group(&self, groups: &HashMap<String, String>) -> Result<WideTable> {
+        self.neutralize_by_group_with(groups, UngroupedPolicy::PassThrough)
+    }
+    
+    /// 分组中性化，可指定没有分组映射的股票的处理方式
+    /// 
+    /// # Arguments
+    /// * `groups` - 股票代码到分组标签的映射
+    /// * `ungrouped` - 没有分组映射的股票的处理方式
+    pub fn neutralize_by_group_with(
+        &self,
+        groups: &HashMap<String, String>,
+        ungrouped: UngroupedPolicy,
+    ) -> Result<WideTable> {
+        self.neutralize_groups(groups, ungrouped, false)
+    }
+    
+    /// 分组标准化：每个时间截面上，各股票的值在所在分组内做 z-score
+    /// 
+    /// `(x - 分组均值) / 分组样本标准差`，同时去除分组间的均值与波动差异。
+    /// NaN 与 null 不参与计算且结果保持缺失；分组内有效值少于 2 个或没有波动时，该分组结果为 null。
+    /// 
+    /// # Arguments
+    /// * `groups` - 股票代码到分组标签的映射
+    /// * `ungrouped` - 没有分组映射的股票的处理方式
+    pub fn neutralize_by_group_zscore(
+        &self,
+        groups: &HashMap<String, String>,
+        ungrouped: UngroupedPolicy,
+    ) -> Result<WideTable> {
+        self.neutralize_groups(groups, ungrouped, true)
+    }
+    
+    /// 分组去均值（`zscore` 为 true 时再除以分组样本标准差）
+    fn neutralize_groups(
+        &self,
+        groups: &HashMap<String, String>,
+        ungrouped: UngroupedPolicy,
+        zscore: bool,
+    ) -> Result<WideTable> {
+        let all_cols = self.stock_cols();
+        let unmapped: Vec<&String> = all_cols.iter().filter(|code| !groups.contains_key(*code)).collect();
+        match (ungrouped, unmapped.first()) {
+            (UngroupedPolicy::Error, Some(missing)) => {
+                return Err(WideTableError::InvalidArgument(format!("股票 '{}' 没有分组映射", missing)));
+            }
+            (UngroupedPolicy::Drop, Some(_)) => {
+                let table = WideTable {
+                    df: self.df.drop_many(&unmapped),
+                    time_col: self.time_col.clone(),
+                    description: None,
+                };
+                return table.neutralize_groups(groups, UngroupedPolicy::PassThrough, zscore);
+            }
+            _ => {}
+        }
+        
+        // 每列对应的分组编号，没有映射（且不归入 UNKNOWN）的列为 None
+        let mut labels: Vec<&str> = Vec::new();
+        let col_groups: Vec<Option<usize>> = all_cols
+            .iter()
+            .map(|code| {
+                let label = match (groups.get(code), ungrouped) {
+                    (Some(label), _) => label.as_str(),
+                    (None, UngroupedPolicy::Unknown) => "UNKNOWN",
+                    (None, _) => return None,
+                };
+                Some(labels.iter().position(|l| *l == label).unwrap_or_else(|| {
+                    labels.push(label);
+                    labels.len() - 1
+                }))
+            })
+            .collect();
+        
+        let mut members: Vec<Vec<f64>> = vec![Vec::new(); labels.len()];
+        let mut params: Vec<Option<(f64, f64)>> = vec![None; labels.len()];
+        self.map_cross_section(|row| {
+            members.iter_mut().for_each(Vec::clear);
+            for (v, g) in row.iter().zip(&col_groups) {
+                if let (Some(x), Some(g)) = (v, g) {
+                    if !x.is_nan() {
+                        members[*g].push(*x);
+                    }
+                }
+            }
+            // 每个分组的 (均值, 除数)，去均值时除数为 1
+            for (p, values) in params.iter_mut().zip(&members) {
+                let n = values.len();
+                let mean = values.iter().sum::<f64>() / n as f64;
+                *p = if !zscore {
+                    (n > 0).then_some((mean, 1.0))
+                } else if n < 2 {
+                    None
+                } else {
+                    let std = (values.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1) as f64).sqrt();
+                    (std > 0.0).then_some((mean, std))
+                };
+            }
+            row.iter()
+                .zip(&col_groups)
+                .map(|(v, g)| match (v, g) {
+                    (Some(x), Some(g)) if !x.is_nan() => params[*g].map(|(mean, scale)| (x - mean) / scale),
+                    _ => *v,
+                })
+                .collect()
+        })
+    }
+    
+    /// 用同一时间截面上其他股票的中位数填充缺失值
+    /// 
+    /// 相比前向填充，不会把停牌股票的旧价格延续下去，避免幸存者偏差。
+    /// NaN 与 null 都视为缺失；整行都缺失时保持原样。时间列不变。
+    pub fn fillna_cross_section_median(&self) -> Result<WideTable> {
+        self.fillna_cross_section(|valid| {
+            valid.sort_by(|a, b| a.total_cmp(b));
+            let mid = valid.len() / 2;
+            if valid.len() % 2 == 0 {
+                (valid[mid - 1] + valid[mid]) / 2.0
+            } else {
+                valid[mid]
+            }
+        })
+    }
+    
+    /// 用同一时间截面上其他股票的均值填充缺失值
+    /// 
+    /// NaN 与 null 都视为缺失；整行都缺失时保持原样。时间列不变。
+    pub fn fillna_cross_section_mean(&self) -> Result<WideTable> {
+        self.fillna_cross_section(|valid| valid.iter().sum::<f64>() / valid.len() as f64)
+    }
+    
+    /// 用固定值填充所有股票列的缺失值（NaN 与 null）
+    /// 
+    /// # Arguments
+    /// * `value` - 填充值
+    pub fn fillna_scalar(&self, value: f64) -> Result<WideTable> {
+        let exprs: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|name| {
+                col(name)
+                    .cast(DataType::Float64)
+                    .fill_nan(lit(value))
+                    .fill_null(lit(value))
+            })
+            .collect();
+        
+        let new_df = self.df.clone().lazy().with_columns(exprs).collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 按时间排序后逐只股票填充缺失值（NaN 与 null）
+    /// 
+    /// 停牌等原因留下的空洞会让之后所有因子都变成 null，可在计算因子前先填充。
+    /// 无法填充的位置（如前向填充时开头的缺失值）保持 null。列名不变，股票列转换为 f64。
+    /// 
+    /// # Arguments
+    /// * `strategy` - 填充方式
+    pub fn fillna(&self, strategy: FillStrategy) -> Result<WideTable> {
+        let mut df = self.sorted_df()?;
+        for name in self.stock_cols() {
+            let values: Vec<Option<f64>> = df
+                .column(&name)?
+                .cast(&DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|v| v.filter(|x| !x.is_nan()))
+                .collect();
+            df.with_column(Series::new(&name, fill_missing(values, strategy)))?;
+        }
+        
+        Ok(WideTable {
+            df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 修正价格序列中的异常跳点（坏报价）
+    /// 
+    /// 与 [`WideTable::clip_price_spikes_with_counts`] 相同，只返回修正后的宽表
+    pub fn clip_price_spikes(&self, max_return: f64) -> Result<WideTable> {
+        Ok(self.clip_price_spikes_with_counts(max_return)?.0)
+    }
+    
+    /// 修正价格序列中的异常跳点（坏报价），并返回每只股票被修正的个数
+    /// 
+    /// 按时间排序后逐只股票扫描：相对上一个被接受价格的涨跌幅绝对值超过 `max_return` 的价格
+    /// 视为跳点，用上一个被接受的价格替换（前向填充）。例如 `10, 1000, 10.1` 中的 1000 会被替换为 10。
+    /// 缺失值（null、NaN）保持原样且不参与比较。
+    /// 
+    /// 判断基准是上一个被接受的价格，真实的持续性跳变（如未复权的拆股）之后的价格会全部被替换，
+    /// 应先做复权再调用本方法。
+    /// 
+    /// # Arguments
+    /// * `max_return` - 允许的单期最大涨跌幅（小数形式，如 0.5 表示 50%），必须为正数
+    /// 
+    /// # Returns
+    /// `(列名不变、按时间排序的新 WideTable, 股票列名 -> 被修正的价格个数)`
+    pub fn clip_price_spikes_with_counts(&self, max_return: f64) -> Result<(WideTable, HashMap<String, usize>)> {
+        if max_return.is_nan() || max_return <= 0.0 {
+            return Err(WideTableError::InvalidArgument(format!("max_return 必须为正数，当前为 {}", max_return)));
+        }
+        
+        let mut df = self.sorted_df()?;
+        let mut counts = HashMap::new();
+        for name in self.stock_cols() {
+            let mut values: Vec<Option<f64>> = df.column(&name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect();
+            let mut last: Option<f64> = None;
+            let mut clipped = 0;
+            for value in values.iter_mut() {
+                let Some(price) = value.filter(|x| !x.is_nan()) else {
+                    continue;
+                };
+                match last {
+                    Some(prev) if prev > 0.0 && (price / prev - 1.0).abs() > max_return => {
+                        *value = Some(prev);
+                        clipped += 1;
+                    }
+                    _ => last = Some(price),
+                }
+            }
+            df.with_column(Series::new(&name, values))?;
+            counts.insert(name, clipped);
+        }
+        
+        Ok((
+            WideTable {
+                df,
+                time_col: self.time_col.clone(),
+                description: None,
+            },
+            counts,
+        ))
+    }
+    
+    /// 用截面统计量填充缺失值，`stat` 接收该行非缺失值（至少一个）
+    fn fillna_cross_section<F>(&self, mut stat: F) -> Result<WideTable>
+    where
+        F: FnMut(&mut Vec<f64>) -> f64,
+    {
+        let mut valid = Vec::new();
+        self.map_cross_section(|row| {
+            valid.clear();
+            valid.extend(row.iter().flatten().filter(|x| !x.is_nan()));
+            if valid.is_empty() {
+                return row.to_vec();
+            }
+            let fill = stat(&mut valid);
+            row.iter()
+                .map(|v| match v {
+                    Some(x) if !x.is_nan() => Some(*x),
+                    _ => Some(fill),
+                })
+                .collect()
+        })
+    }
+    
+    /// 按时间截面逐行变换所有股票列
+    /// 
+    /// `f` 接收一行中各股票的值（按列顺序），返回同样长度的结果。
+    /// 输出只包含时间列和同名的股票列，行顺序不变。
+    pub(crate) fn map_cross_section<F>(&self, mut f: F) -> Result<WideTable>
+    where
+        F: FnMut(&[Option<f64>]) -> Vec<Option<f64>>,
+    {
+        let stock_cols = self.stock_cols();
+        let inputs: Vec<Vec<Option<f64>>> = stock_cols
+            .iter()
+            .map(|name| -> Result<Vec<Option<f64>>> {
+                Ok(self.df.column(name)?.cast(&DataType::Float64)?.f64()?.into_iter().collect())
+            })
+            .collect::<Result<_>>()?;
+        
+        let height = self.df.height();
+        let mut outputs: Vec<Vec<Option<f64>>> = vec![Vec::with_capacity(height); stock_cols.len()];
+        let mut row = vec![None; stock_cols.len()];
+        for i in 0..height {
+            for (j, values) in inputs.iter().enumerate() {
+                row[j] = values[i];
+            }
+            let result = f(&row);
+            if result.len() != row.len() {
+                return Err(WideTableError::InvalidArgument(format!("截面变换结果长度 {} 与股票数 {} 不一致", result.len(), row.len())));
+            }
+            for (j, v) in result.into_iter().enumerate() {
+                outputs[j].push(v);
+            }
+        }
+        
+        let mut columns = vec![self.df.column(&self.time_col)?.clone()];
+        columns.extend(
+            stock_cols
+                .iter()
+                .zip(outputs)
+                .map(|(name, values)| Series::new(name, values)),
+        );
+        
+        Ok(WideTable {
+            df: DataFrame::new(columns)?,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 按时间列横向合并两个宽表
+    /// 
+    /// 同名股票列会给 `other` 中的列加上 `_right` 后缀，不会覆盖数据。
+    /// 
+    /// # Arguments
+    /// * `other` - 另一个宽表
+    /// * `how` - 合并方式
+    /// 
+    /// # Returns
+    /// 返回合并后的新 WideTable，时间列沿用 `self` 的列名
+    pub fn merge(&self, other: &WideTable, how: JoinType) -> Result<WideTable> {
+        self.merge_with_suffix(other, how, "_right")
+    }
+    
+    /// 按时间列横向合并两个宽表，并指定同名列的后缀
+    /// 
+    /// # Arguments
+    /// * `other` - 另一个宽表
+    /// * `how` - 合并方式
+    /// * `suffix` - `other` 中同名列追加的后缀
+    pub fn merge_with_suffix(&self, other: &WideTable, how: JoinType, suffix: &str) -> Result<WideTable> {
+        if suffix.is_empty() {
+            return Err(WideTableError::InvalidArgument("合并后缀不能为空".to_string()));
+        }
+        
+        let args = JoinArgs {
+            how: how.into(),
+            suffix: Some(suffix.to_string()),
+            coalesce: JoinCoalesce::CoalesceColumns,
+            ..Default::default()
+        };
+        
+        let new_df = self.df
+            .clone()
+            .lazy()
+            .join(
+                other.df.clone().lazy(),
+                [col(&self.time_col)],
+                [col(&other.time_col)],
+                args,
+            )
+            .collect()?
+            .sort([&self.time_col], SortOptions::default())?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 将两个宽表对齐到相同的时间与股票网格
+    /// 
+    /// 两个结果都只保留共有的时间和股票列，行按时间排序、股票列按 `self` 中的顺序排列，
+    /// 各自沿用原来的时间列名。需要了解丢弃了多少行列时使用 [`WideTable::align_with_summary`]。
+    /// 
+    /// # Errors
+    /// 没有共有的时间或股票列时返回错误
+    pub fn align(&self, other: &WideTable) -> Result<(WideTable, WideTable)> {
+        let (left, right, _) = self.align_with_summary(other)?;
+        Ok((left, right))
+    }
+    
+    /// 将两个宽表对齐到相同的时间与股票网格，并返回被丢弃的行列数
+    /// 
+    /// # Returns
+    /// `(self 对齐结果, other 对齐结果, 丢弃统计)`
+    pub fn align_with_summary(&self, other: &WideTable) -> Result<(WideTable, WideTable, AlignSummary)> {
+        let left_cols = self.stock_cols();
+        let right_cols = other.stock_cols();
+        let common: Vec<String> = left_cols.iter().filter(|c| right_cols.contains(c)).cloned().collect();
+        if common.is_empty() {
+            return Err(WideTableError::InvalidArgument("两个宽表没有共同的股票列".to_string()));
+        }
+        
+        // 与对方的时间列内连接，只保留共有的时间
+        let restrict = |table: &WideTable, keys: &WideTable| -> Result<WideTable> {
+            let mut exprs = vec![col(&table.time_col)];
+            exprs.extend(common.iter().map(|c| col(c)));
+            let keys = keys
+                .df
+                .clone()
+                .lazy()
+                .select([col(&keys.time_col).alias(&table.time_col)])
+                .unique(None, UniqueKeepStrategy::First);
+            let df = table
+                .df
+                .clone()
+                .lazy()
+                .select(exprs)
+                .join(
+                    keys,
+                    [col(&table.time_col)],
+                    [col(&table.time_col)],
+                    JoinArgs::new(polars::prelude::JoinType::Inner),
+                )
+                .sort([&table.time_col], SortMultipleOptions::default())
+                .collect()?;
+            Ok(WideTable {
+                df,
+                time_col: table.time_col.clone(),
+                description: None,
+            })
+        };
+        
+        let left = restrict(self, other)?;
+        let right = restrict(other, self)?;
+        if left.df.height() == 0 {
+            return Err(WideTableError::InvalidArgument("两个宽表没有共同的时间".to_string()));
+        }
+        
+        let summary = AlignSummary {
+            dropped_rows_left: self.df.height() - left.df.height(),
+            dropped_rows_right: other.df.height() - right.df.height(),
+            dropped_cols_left: left_cols.len() - common.len(),
+            dropped_cols_right: right_cols.len() - common.len(),
+        };
+        Ok((left, right, summary))
+    }
+    
+    /// 把 `other` 的股票列按前向填充对齐到 `self` 的时间轴
+    /// 
+    /// 每个时间点取 `other` 中不晚于该时间的最近一行（`other` 的时间点不必出现在 `self` 中），
+    /// 适合流通股本等低频数据对齐到日频。早于 `other` 第一行的时间为 null。
+    /// 
+    /// # Arguments
+    /// * `other` - 低频宽表
+    /// 
+    /// # Returns
+    /// 时间列与 `self` 相同（按时间排序）、股票列来自 `other` 的新 WideTable
+    pub fn align_ffill(&self, other: &WideTable) -> Result<WideTable> {
+        let other_cols = other.stock_cols();
+        let marker = "__align_ffill_key";
+        
+        let mut other_exprs = vec![col(&other.time_col).alias(&self.time_col)];
+        other_exprs.extend(other_cols.iter().map(|c| col(c)));
+        let keys = self
+            .df
+            .clone()
+            .lazy()
+            .select([col(&self.time_col), lit(true).alias(marker)])
+            .unique(None, UniqueKeepStrategy::First);
+        
+        let args = JoinArgs {
+            how: polars::prelude::JoinType::Outer,
+            coalesce: JoinCoalesce::CoalesceColumns,
+            ..Default::default()
+        };
+        let new_df = other
+            .df
+            .clone()
+            .lazy()
+            .select(other_exprs)
+            .join(keys, [col(&self.time_col)], [col(&self.time_col)], args)
+            .sort([&self.time_col], SortMultipleOptions::default())
+            .with_columns(other_cols.iter().map(|c| col(c).forward_fill(None)).collect::<Vec<_>>())
+            .filter(col(marker).is_not_null())
+            .drop([marker])
+            .collect()?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 将多个因子宽表按时间列横向拼接成一张特征矩阵（内连接）
+    /// 
+    /// # Arguments
+    /// * `others` - 其他因子宽表
+    /// 
+    /// # Errors
+    /// 任意两张表存在同名的非时间列时返回错误
+    pub fn join_factors(&self, others: &[&WideTable]) -> Result<WideTable> {
+        self.join_factors_with(others, JoinType::Inner)
+    }
+    
+    /// 将多个因子宽表按时间列横向拼接，并指定合并方式
+    /// 
+    /// # Arguments
+    /// * `others` - 其他因子宽表
+    /// * `how` - 合并方式，按顺序依次与 `others` 中的表合并
+    pub fn join_factors_with(&self, others: &[&WideTable], how: JoinType) -> Result<WideTable> {
+        let mut seen: HashSet<String> = self.stock_cols().into_iter().collect();
+        for other in others {
+            for col_name in other.stock_cols() {
+                if !seen.insert(col_name.clone()) {
+                    return Err(WideTableError::DuplicateColumn(col_name.clone()));
+                }
+            }
+        }
+        
+        let mut result = self.clone();
+        for other in others {
+            result = result.merge(other, how)?;
+        }
+        
+        Ok(result)
+    }
+    
+    /// 纵向拼接两个列结构相同的宽表
+    /// 
+    /// `other` 的列会按 `self` 的列顺序重排，拼接后按时间排序。
+    /// 
+    /// # Arguments
+    /// * `other` - 另一个宽表，时间列名、列名集合和数据类型须与 `self` 一致
+    pub fn concat_rows(&self, other: &WideTable) -> Result<WideTable> {
+        if self.time_col != other.time_col {
+            return Err(WideTableError::SchemaMismatch(format!(
+                "时间列名不一致: '{}' 与 '{}'",
+                self.time_col,
+                other.time_col
+            )));
+        }
+        
+        let self_schema = self.df.schema();
+        let other_schema = other.df.schema();
+        if self_schema.len() != other_schema.len() {
+            return Err(WideTableError::SchemaMismatch(format!(
+                "列数不一致: {} 与 {}",
+                self_schema.len(),
+                other_schema.len()
+            )));
+        }
+        for (name, dtype) in self_schema.iter() {
+            match other_schema.get(name) {
+                Some(other_dtype) if other_dtype == dtype => {}
+                Some(other_dtype) => {
+                    return Err(WideTableError::SchemaMismatch(format!(
+                        "列 '{}' 类型不一致: {} 与 {}",
+                        name,
+                        dtype,
+                        other_dtype
+                    )));
+                }
+                None => return Err(WideTableError::SchemaMismatch(format!("列 '{}' 不存在于另一个宽表中", name))),
+            }
+        }
+        
+        let other_df = other.df.select(self.df.get_column_names())?;
+        let new_df = self.df
+            .vstack(&other_df)?
+            .sort([&self.time_col], SortOptions::default())?;
+        
+        Ok(WideTable {
+            df: new_df,
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 追加新数据行，用于每日增量更新
+    /// 
+    /// 列结构校验与 [`WideTable::concat_rows`] 相同，追加后按时间排序。
+    /// 
+    /// # Arguments
+    /// * `new_data` - 新数据，时间列名、列名集合和数据类型须与 `self` 一致
+    /// 
+    /// # Errors
+    /// `new_data` 中的时间已存在于 `self` 或在 `new_data` 内重复时返回 [`WideTableError::DuplicateDate`]；
+    /// 需要静默跳过已有时间时请使用 [`WideTable::append_rows_dedup`]
+    pub fn append_rows(&self, new_data: DataFrame) -> Result<WideTable> {
+        let mut seen = self.time_keys()?;
+        for value in new_data.column(&self.time_col)?.iter() {
+            let key = value.to_string();
+            if !seen.insert(key.clone()) {
+                return Err(WideTableError::DuplicateDate(key));
+            }
+        }
+        
+        self.concat_rows(&WideTable::new(new_data, self.time_col.clone())?)
+    }
+    
+    /// 追加新数据行，静默丢弃时间已存在的行
+    /// 
+    /// 与 [`WideTable::append_rows`] 相同，但 `new_data` 中时间已存在于 `self`
+    /// （或在 `new_data` 中已出现过）的行会被丢弃，只保留第一次出现的行。
+    pub fn append_rows_dedup(&self, new_data: DataFrame) -> Result<WideTable> {
+        let mut seen = self.time_keys()?;
+        let mask: BooleanChunked = new_data
+            .column(&self.time_col)?
+            .iter()
+            .map(|value| seen.insert(value.to_string()))
+            .collect();
+        
+        self.concat_rows(&WideTable::new(new_data.filter(&mask)?, self.time_col.clone())?)
+    }
+    
+    /// 按交易日历重建时间索引，补齐缺失的交易日
+    /// 
+    /// 结果的时间为 `cal` 在原表首尾日期之间的全部交易日：原表缺失的交易日补一行，
+    /// 股票列填 NaN。
+    /// 
+    /// 注意：原表中落在非交易日（周末或休市日）的行会被直接丢弃，其中的数据不会保留。
+    /// 需要保留这些行时，请先用 [`TradingCalendar::is_trading_day`] 检查并自行处理。
+    /// 
+    /// # Arguments
+    /// * `cal` - 交易日历
+    /// 
+    /// # Errors
+    /// 时间列不是 `Date` 类型时返回 [`WideTableError::SchemaMismatch`]；
+    /// 原表日期超出日历覆盖的年份时返回 [`WideTableError::CalendarNotCovered`]
+    pub fn reindex_to_calendar(&self, cal: &TradingCalendar) -> Result<WideTable> {
+        let time = self.df.column(&self.time_col)?;
+        let dates = time.date().map_err(|_| WideTableError::SchemaMismatch(format!(
+            "时间列 '{}' 的类型为 {}，需要 Date",
+            self.time_col,
+            time.dtype()
+        )))?;
+        let present: Vec<NaiveDate> = dates.as_date_iter().flatten().collect();
+        let (Some(&start), Some(&end)) = (present.iter().min(), present.iter().max()) else {
+            return Ok(self.clone());
+        };
+        
+        const PRESENT: &str = "__present";
+        let calendar = DataFrame::new(vec![Series::new(&self.time_col, cal.trading_days(start, end)?)])?;
+        let fills: Vec<Expr> = self
+            .stock_cols()
+            .iter()
+            .map(|c| {
+                when(col(PRESENT).is_null())
+                    .then(lit(f64::NAN))
+                    .otherwise(col(c))
+                    .alias(c)
+            })
+            .collect();
+        
+        let df = calendar
+            .lazy()
+            .join(
+                self.df.clone().lazy().with_column(lit(true).alias(PRESENT)),
+                [col(&self.time_col)],
+                [col(&self.time_col)],
+                JoinArgs::new(polars::prelude::JoinType::Left),
+            )
+            .with_columns(fills)
+            .drop([PRESENT])
+            .collect()?;
+        
+        WideTable::new(df, self.time_col.clone())
+    }
+    
+    /// 时间列中所有时间的字符串形式，用于判断重复
+    fn time_keys(&self) -> Result<HashSet<String>> {
+        Ok(self.df.column(&self.time_col)?.iter().map(|value| value.to_string()).collect())
+    }
+    
+    /// 删除开头有效值不足的行
+    /// 
+    /// 按时间排序后从第一行开始删除，直到遇到有效值（非 NaN、非 null）的股票列占比
+    /// 不低于 `threshold` 且至少有一个有效值的行。前向填充或合并宽表后，开头几行常常几乎全部缺失。
+    /// 
+    /// # Arguments
+    /// * `threshold` - 保留一行所需的有效值占比，取值范围 `[0, 1]`，例如 0.5 表示至少一半股票有效
+    pub fn trim_leading_nans(&self, threshold: f64) -> Result<WideTable> {
+        self.trim_rows(threshold, true, false)
+    }
+    
+    /// 删除结尾有效值不足的行，与 [`WideTable::trim_leading_nans`] 对称
+    /// 
+    /// # Arguments
+    /// * `threshold` - 保留一行所需的有效值占比，取值范围 `[0, 1]`
+    pub fn trim_trailing_nans(&self, threshold: f64) -> Result<WideTable> {
+        self.trim_rows(threshold, false, true)
+    }
+    
+    /// 同时删除开头与结尾有效值不足的行，中间的行不受影响
+    /// 
+    /// # Arguments
+    /// * `threshold` - 保留一行所需的有效值占比，取值范围 `[0, 1]`
+    pub fn trim_nans(&self, threshold: f64) -> Result<WideTable> {
+        self.trim_rows(threshold, true, true)
+    }
+    
+    /// 按时间排序后删除开头（`leading`）和/或结尾（`trailing`）有效值不足的连续行；
+    /// 所有行都不满足时结果为空表
+    fn trim_rows(&self, threshold: f64, leading: bool, trailing: bool) -> Result<WideTable> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(WideTableError::InvalidArgument(format!("threshold 必须在 [0, 1] 内，当前为 {}", threshold)));
+        }
+        
+        let df = self.sorted_df()?;
+        let stock_cols = self.stock_cols();
+        let mut valid_counts = vec![0usize; df.height()];
+        for name in &stock_cols {
+            let values = df.column(name)?.cast(&DataType::Float64)?;
+            for (count, v) in valid_counts.iter_mut().zip(values.f64()?) {
+                if v.is_some_and(|x| !x.is_nan()) {
+                    *count += 1;
+                }
+            }
+        }
+        
+        let required = threshold * stock_cols.len() as f64;
+        let keep = |count: &usize| *count > 0 && *count as f64 >= required;
+        let start = if leading { valid_counts.iter().position(keep).unwrap_or(df.height()) } else { 0 };
+        let end = if trailing { valid_counts.iter().rposition(keep).map_or(start, |i| i + 1) } else { df.height() };
+        
+        Ok(WideTable {
+            df: df.slice(start as i64, end.saturating_sub(start)),
+            time_col: self.time_col.clone(),
+            description: None,
+        })
+    }
+    
+    /// 检查所有浮点列都不含 NaN，可作为流水线各阶段之间的断言
+    /// 
+    /// 只读取数据、不复制 DataFrame；null 不视为 NaN。
+    /// 
+    /// # Errors
+    /// 存在 NaN 时返回错误，列出每个含 NaN 的列及其 NaN 行数
+    pub fn assert_no_nans(&self) -> Result<&Self> {
+        let mut offenders = Vec::new();
+        for series in self.df.get_columns() {
+            let count = match series.dtype() {
+                DataType::Float64 => series.f64()?.into_iter().filter(|v| v.is_some_and(f64::is_nan)).count(),
+                DataType::Float32 => series.f32()?.into_iter().filter(|v| v.is_some_and(f32::is_nan)).count(),
+                _ => 0,
+            };
+            if count > 0 {
+                offenders.push(format!("{}（{} 行）", series.name(), count));
+            }
+        }
+        
+        if offenders.is_empty() {
+            Ok(self)
+        } else {
+            Err(WideTableError::ContainsNan(offenders.join(", ")))
+        }
+    }
+    
+    /// 检查指定列都存在且数据类型为 `expected_type`，只读取 schema
+    /// 
+    /// # Arguments
+    /// * `expected_cols` - 需要存在的列名
+    /// * `expected_type` - 这些列的数据类型
+    /// 
+    /// # Errors
+    /// 列缺失或类型不符时返回错误，列出所有不符合的列
+    pub fn validate_schema(&self, expected_cols: &[&str], expected_type: DataType) -> Result<&Self> {
+        let mut problems = Vec::new();
+        for name in expected_cols {
+            match self.df.column(name) {
+                Ok(series) if series.dtype() != &expected_type => {
+                    problems.push(format!("列 '{}' 类型为 {}，期望 {}", name, series.dtype(), expected_type));
+                }
+                Ok(_) => {}
+                Err(_) => problems.push(format!("列 '{}' 不存在", name)),
+            }
+        }
+        
+        if problems.is_empty() {
+            Ok(self)
+        } else {
+            Err(WideTableError::SchemaMismatch(problems.join("; ")))
+        }
+    }
+    
+    /// 检查时间列没有重复的时间，只读取数据、不复制 DataFrame
+    /// 
+    /// # Errors
+    /// 存在重复时返回 [`WideTableError::DuplicateDate`]，包含第一个重复的时间
+    pub fn ensure_unique_dates(&self) -> Result<&Self> {
+        let mut seen = HashSet::new();
+        for value in self.df.column(&self.time_col)?.iter() {
+            let key = value.to_string();
+            if seen.contains(&key) {
+                return Err(WideTableError::DuplicateDate(key));
+            }
+            seen.insert(key);
+        }
+        Ok(self)
+    }
+    
+    /// 启发式检查因子列是否泄露了未来数据
+    /// 
+    /// 只检查本库因子方法输出的列：列名形如 `{base}_{tag}_{N}`，`tag` 为已知的因子后缀
+    /// （如 `sma`、`volatility`、`momentum`、`pct_change`），且基础列 `{base}` 也在表中。
+    /// 从基础列第一个有效值起算，因子列应至少经过与该方法的 `min_periods` 相符的预热行数
+    /// 才出现有效值，例如 `sma_N` 为 `N - 1` 行，`volatility_N` 为 `N` 行（先算收益率），
+    /// 允许窗口未满即出结果的 `momentum_N` 为 1 行。提前出现有效值通常意味着误用了负的 shift。
+    /// 其他列（包括以数字结尾的股票代码，如 `SH_600000`）不做检查。
+    /// 
+    /// 注意：[`WideTable::forward_returns`] 输出的未来收益率列按定义使用未来数据，会被判为泄露。
+    /// 
+    /// # Errors
+    /// 存在可疑列时返回错误，列出所有违规的列名
+    pub fn assert_no_future_leakage(&self) -> Result<()> {
+        let df = self.sorted_df()?;
+        let stock_cols = self.stock_cols();
+        let first_valid = |name: &str| -> Result<Option<usize>> {
+            let values = df.column(name)?.cast(&DataType::Float64)?;
+            let first = values.f64()?.into_iter().position(|v| v.is_some_and(|v| !v.is_nan()));
+            Ok(first)
+        };
+        
+        let mut violations = Vec::new();
+        for name in &stock_cols {
+            let Some((rest, window)) = name.rsplit_once('_').and_then(|(rest, n)| Some((rest, n.parse::<usize>().ok()?))) else {
+                continue;
+            };
+            // 最长的已知后缀优先（如 momentum_decay 先于 momentum）
+            let Some((base, warmup)) = FACTOR_WARMUPS
+                .iter()
+                .filter_map(|(tag, warmup)| {
+                    let base = rest.strip_suffix(tag)?.strip_suffix('_')?;
+                    stock_cols.iter().any(|c| c == base).then_some((base, warmup(window)))
+                })
+                .next()
+            else {
+                continue;
+            };
+            let (Some(first), Some(base_first)) = (first_valid(name)?, first_valid(base)?) else {
+                continue;
+            };
+            if first < base_first + warmup {
+                violations.push(name.clone());
+            }
+        }
+        
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(WideTableError::FutureLeakage(violations.join(", ")))
+        }
+    }
+    
+    /// 数据说明，由 [`WideTable::load_with_meta`] 或 [`WideTable::set_description`] 设置
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+    
+    /// 设置数据说明，随 [`WideTable::save_meta`] 写入元数据
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+    
+    /// 宽表的元数据（时间列名与数据说明）
+    pub fn meta(&self) -> WideTableMeta {
+        WideTableMeta {
+            time_col: self.time_col.clone(),
+            description: self.description.clone(),
+        }
+    }
+    
+    /// 把元数据（时间列名与数据说明）以 JSON 格式写入 `path`，通常与 [`WideTable::to_parquet`] 的数据文件放在一起
+    pub fn save_meta(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.meta().save(path)
+    }
+    
+    /// 保存到 Parquet 文件
+    pub fn to_parquet(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        ParquetWriter::new(file).finish(&mut self.df.clone())?;
+        Ok(())
+    }
+    
+    /// 保存到 CSV 文件
+    pub fn to_csv(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let mut file = std::fs::File::create(path)?;
+        CsvWriter::new(&mut file)
+            .include_header(true)
+            .finish(&mut self.df.clone())?;
+        Ok(())
+    }
+}
+
+/// 按解析选项构建 CSV 的 LazyFrame
+fn csv_reader(path: impl AsRef<std::path::Path>, time_col: &str, options: &CsvOptions) -> Result<LazyFrame> {
+    let dtype_overwrite = options
+        .time_dtype
+        .clone()
+        .map(|dtype| Arc::new(Schema::from_iter([Field::new(time_col, dtype)])));
+    
+    Ok(LazyCsvReader::new(path)
+        .with_separator(options.delimiter)
+        .with_has_header(options.has_header)
+        .with_dtype_overwrite(dtype_overwrite)
+        .finish()?)
+}
+
+/// 校验去极值方法的参数
+fn check_winsor_method(method: WinsorMethod) -> Result<()> {
+    match method {
+        WinsorMethod::Mad(n) | WinsorMethod::Sigma(n) if n.is_nan() || n <= 0.0 => {
+            Err(WideTableError::InvalidArgument(format!("去极值倍数必须为正数，当前为 {}", n)))
+        }
+        WinsorMethod::Quantile(lower, upper) if !(0.0 <= lower && lower <= upper && upper <= 1.0) => {
+            Err(WideTableError::InvalidArgument(format!(
+                "分位数须满足 0 <= lower <= upper <= 1，当前为 {} 与 {}",
+                lower,
+                upper
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 由一个截面的有效值（非 null、非 NaN）计算去极值的上下界
+/// 
+/// 没有有效值，或 `Mad`、`Sigma` 的离散程度为 0（`Sigma` 有效值少于 2 个）时返回 None
+fn winsor_bounds(row: &[Option<f64>], method: WinsorMethod) -> Option<(f64, f64)> {
+    let mut valid: Vec<f64> = row.iter().flatten().copied().filter(|x| !x.is_nan()).collect();
+    if valid.is_empty() {
+        return None;
+    }
+    valid.sort_by(|a, b| a.total_cmp(b));
+    match method {
+        WinsorMethod::Mad(n) => {
+            let median = stats::quantile_sorted(&valid, 0.5);
+            let mut deviations: Vec<f64> = valid.iter().map(|x| (x - median).abs()).collect();
+            deviations.sort_by(|a, b| a.total_cmp(b));
+            let mad = stats::quantile_sorted(&deviations, 0.5);
+            (mad > 0.0).then_some((median - n * mad, median + n * mad))
+        }
+        WinsorMethod::Sigma(n) => {
+            let count = valid.len() as f64;
+            let mean = valid.iter().sum::<f64>() / count;
+            let std = (valid.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (count - 1.0)).sqrt();
+            (valid.len() >= 2 && std > 0.0).then_some((mean - n * std, mean + n * std))
+        }
+        WinsorMethod::Quantile(lower, upper) => {
+            Some((stats::quantile_sorted(&valid, lower), stats::quantile_sorted(&valid, upper)))
+        }
+    }
+}
+
+/// 按 `strategy` 填充单只股票按时间排序的序列
+fn fill_missing(mut values: Vec<Option<f64>>, strategy: FillStrategy) -> Vec<Option<f64>> {
+    match strategy {
+        FillStrategy::ForwardFill { limit } => {
+            let mut last = None;
+            let mut filled = 0;
+            for v in values.iter_mut() {
+                match v {
+                    Some(x) => {
+                        last = Some(*x);
+                        filled = 0;
+                    }
+                    None if limit.is_none_or(|limit| filled < limit) => {
+                        *v = last;
+                        filled += 1;
+                    }
+                    None => {}
+                }
+            }
+        }
+        FillStrategy::BackwardFill => {
+            let mut next = None;
+            for v in values.iter_mut().rev() {
+                match v {
+                    Some(x) => next = Some(*x),
+                    None => *v = next,
+                }
+            }
+        }
+        FillStrategy::Zero => values.iter_mut().for_each(|v| *v = Some(v.unwrap_or(0.0))),
+        FillStrategy::Mean => {
+            let valid: Vec<f64> = values.iter().flatten().copied().collect();
+            if !valid.is_empty() {
+                let mean = valid.iter().sum::<f64>() / valid.len() as f64;
+                values.iter_mut().for_each(|v| *v = Some(v.unwrap_or(mean)));
+            }
+        }
+        FillStrategy::LinearInterpolate => {
+            let mut prev: Option<(usize, f64)> = None;
+            for i in 0..values.len() {
+                let Some(x) = values[i] else { continue };
+                if let Some((j, y)) = prev {
+                    for (k, v) in values.iter_mut().enumerate().take(i).skip(j + 1) {
+                        *v = Some(y + (x - y) * (k - j) as f64 / (i - j) as f64);
+                    }
+                }
+                prev = Some((i, x));
+            }
+        }
+    }
+    values
+}
+
+/// 由窗口参数计算预热行数
+type Warmup = fn(usize) -> usize;
+
+/// 因子列名后缀（`{base}_{tag}_{N}` 中的 `tag`）及其预热行数：
+/// 从基础列第一个有效值起，窗口参数为 `N` 时至少经过多少行才会出现有效值，
+/// 与各方法的 `min_periods` 一致。按后缀长度从长到短排列，避免 `momentum` 抢先匹配 `momentum_decay`
+const FACTOR_WARMUPS: &[(&str, Warmup)] = &[
+    ("momentum_decay_pct", |n| n),
+    ("momentum_decay_bps", |n| n),
+    ("volatility_pct", |n| n),
+    ("volatility_bps", |n| n),
+    ("momentum_decay", |n| n),
+    ("momentum_pct", |_| 1),
+    ("momentum_bps", |_| 1),
+    ("fwd_return_pct", |n| n),
+    ("fwd_return_bps", |n| n),
+    ("fwd_return", |n| n),
+    ("pct_change", |n| n),
+    ("bps_change", |n| n),
+    ("volatility", |n| n),
+    ("momentum", |_| 1),
+    ("rollmax", |n| n.saturating_sub(1)),
+    ("rollmin", |n| n.saturating_sub(1)),
+    ("return", |n| n),
+    ("accel", |n| 2 * n),
+    ("sma", |n| n.saturating_sub(1)),
+    ("ema", |n| n.saturating_sub(1)),
+    ("roc", |n| n),
+];
+
+/// 变化率表达式: (later - earlier) / earlier * scale，输出 `{col_name}_{tag}_{|periods|}` 列
+/// 
+/// `periods` 为正时从 `periods` 期之前到当期，为负时从当期到 `-periods` 期之后（未来收益率）
+fn change_expr(col_name: &str, periods: i32, scale: f64, tag: &str) -> Expr {
+    let shifted = col(col_name).shift(lit(periods));
+    let (earlier, later) = if periods > 0 { (shifted, col(col_name)) } else { (col(col_name), shifted) };
+    ((later - earlier.clone()) / earlier * lit(scale))
+        .alias(&format!("{}_{}_{}", col_name, tag, periods.unsigned_abs()))
+}
+
+/// EMA 表达式：`alpha = 2 / (span + 1)`，不做偏差调整，有效值不足 `span` 个时为 null
+pub(crate) fn ema_expr(e: Expr, span: usize) -> Expr {
+    e.ewm_mean(EWMOptions {
+        alpha: 2.0 / (span as f64 + 1.0),
+        adjust: false,
+        bias: false,
+        min_periods: span,
+        ignore_nulls: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+    
+    #[test]
+    fn test_wide_table_creation() {
+        // 创建测试数据
+        let dates = vec![
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+        ];
+        
+        let stock_a = vec![100.0, 102.0, 104.0];
+        let stock_b = vec![50.0, 51.0, 49.0];
+        
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", stock_a),
+            Series::new("stock_B", stock_b),
+        ]).unwrap();
+        
+        let table = WideTable::new(df, "date").unwrap();
+        assert_eq!(table.time_col(), "date");
+    }
+    
+    fn price_table(prices: Vec<f64>) -> WideTable {
+        let dates: Vec<NaiveDate> = (0..prices.len())
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i as i64))
+            .collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", prices),
+        ]).unwrap();
+        WideTable::new(df, "date").unwrap()
+    }
+    
+    fn last_value(table: &WideTable, col_name: &str) -> f64 {
+        let s = table.df().column(col_name).unwrap();
+        s.f64().unwrap().get(s.len() - 1).unwrap()
+    }
+    
+    #[test]
+    fn test_pct_change_rejects_non_positive_periods() {
+        let table = price_table(vec![100.0, 110.0, 121.0]);
+        assert!(table.pct_change(0).is_err());
+        assert!(table.pct_change(-1).is_err());
+        
+        let fwd = table.forward_returns(1).unwrap();
+        let s = fwd.df().column("stock_A_fwd_return_1").unwrap().f64().unwrap().clone();
+        assert!((s.get(0).unwrap() - 0.1).abs() < 1e-12);
+        assert!(s.get(2).is_none());
+        
+        let fwd = table.forward_returns_with(2, ReturnScale::Percent).unwrap();
+        let s = fwd.df().column("stock_A_fwd_return_pct_2").unwrap().f64().unwrap().clone();
+        assert!((s.get(0).unwrap() - 21.0).abs() < 1e-9);
+        assert_eq!(s.null_count(), 2);
+        assert!(fwd.assert_no_future_leakage().is_err());
+        
+        assert!(table.forward_returns(0).is_err());
+    }
+    
+    #[test]
+    fn test_merge_keeps_overlapping_columns() {
+        let left = price_table(vec![100.0, 110.0, 121.0]);
+        let right = price_table(vec![1.0, 2.0, 3.0]);
+        
+        let merged = left.merge(&right, JoinType::Inner).unwrap();
+        assert_eq!(merged.df().get_column_names(), &["date", "stock_A", "stock_A_right"]);
+        
+        let stacked = left.concat_rows(&right).unwrap();
+        assert_eq!(stacked.df().height(), 6);
+    }
+    
+    #[test]
+    fn test_rank_cross_section_ties() {
+        let df = DataFrame::new(vec![
+            Series::new("date", &[NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()]),
+            Series::new("a", &[Some(3.0)]),
+            Series::new("b", &[Some(1.0)]),
+            Series::new("c", &[Some(3.0)]),
+            Series::new("d", &[None::<f64>]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let row = |method, percentile| -> Vec<Option<f64>> {
+            let ranked = table.rank_cross_section(method, percentile).unwrap();
+            ["a", "b", "c", "d"]
+                .iter()
+                .map(|c| ranked.df().column(c).unwrap().f64().unwrap().get(0))
+                .collect()
+        };
+        assert_eq!(row(RankMethod::Average, false), vec![Some(2.5), Some(1.0), Some(2.5), None]);
+        assert_eq!(row(RankMethod::Min, false), vec![Some(2.0), Some(1.0), Some(2.0), None]);
+        assert_eq!(row(RankMethod::Max, false), vec![Some(3.0), Some(1.0), Some(3.0), None]);
+        assert_eq!(row(RankMethod::Dense, false), vec![Some(2.0), Some(1.0), Some(2.0), None]);
+        assert_eq!(row(RankMethod::Ordinal, false), vec![Some(2.0), Some(1.0), Some(3.0), None]);
+        assert_eq!(row(RankMethod::Max, true), vec![Some(1.0), Some(1.0 / 3.0), Some(1.0), None]);
+    }
+    
+    #[test]
+    fn test_fillna_cross_section_median() {
+        let dates = vec![
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+        ];
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[Some(1.0), None]),
+            Series::new("b", &[Some(f64::NAN), None]),
+            Series::new("c", &[Some(4.0), None]),
+            Series::new("d", &[None, None::<f64>]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let filled = table.fillna_cross_section_median().unwrap();
+        let get = |c: &str, i: usize| filled.df().column(c).unwrap().f64().unwrap().get(i);
+        assert_eq!(get("b", 0), Some(2.5));
+        assert_eq!(get("d", 0), Some(2.5));
+        // 整行缺失时保持原样
+        assert_eq!(get("a", 1), None);
+    }
+    
+    #[test]
+    fn test_neutralize_by_group() {
+        let df = DataFrame::new(vec![
+            Series::new("date", &[NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()]),
+            Series::new("a", &[1.0]),
+            Series::new("b", &[3.0]),
+            Series::new("c", &[10.0]),
+            Series::new("d", &[7.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let groups: HashMap<String, String> = [("a", "bank"), ("b", "bank"), ("c", "tech")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        
+        let neutral = table.neutralize_by_group(&groups).unwrap();
+        let get = |c: &str| neutral.df().column(c).unwrap().f64().unwrap().get(0);
+        assert_eq!(get("a"), Some(-1.0));
+        assert_eq!(get("b"), Some(1.0));
+        assert_eq!(get("c"), Some(0.0));
+        // 没有分组映射的股票原样保留
+        assert_eq!(get("d"), Some(7.0));
+        
+        assert!(table.neutralize_by_group_with(&groups, UngroupedPolicy::Error).is_err());
+        
+        let dropped = table.neutralize_by_group_with(&groups, UngroupedPolicy::Drop).unwrap();
+        assert_eq!(dropped.df().get_column_names(), &["date", "a", "b", "c"]);
+        
+        // 未映射的 d 单独成为 UNKNOWN 组
+        let unknown = table.neutralize_by_group_with(&groups, UngroupedPolicy::Unknown).unwrap();
+        assert_eq!(unknown.df().column("d").unwrap().f64().unwrap().get(0), Some(0.0));
+    }
+    
+    #[test]
+    fn test_neutralize_by_group_two_industries() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("bank_1", &[1.0, 4.0]),
+            Series::new("bank_2", &[2.0, 8.0]),
+            Series::new("bank_3", &[6.0, 3.0]),
+            Series::new("tech_1", &[10.0, -5.0]),
+            Series::new("tech_2", &[30.0, 5.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let groups: HashMap<String, String> = table
+            .stock_cols()
+            .into_iter()
+            .map(|code| (code.clone(), code[..4].to_string()))
+            .collect();
+        let group_sum = |t: &WideTable, prefix: &str, i: usize| -> f64 {
+            t.stock_cols()
+                .iter()
+                .filter(|c| c.starts_with(prefix))
+                .map(|c| t.df().column(c).unwrap().f64().unwrap().get(i).unwrap())
+                .sum()
+        };
+        
+        let demeaned = table.neutralize_by_group(&groups).unwrap();
+        let zscored = table.neutralize_by_group_zscore(&groups, UngroupedPolicy::Error).unwrap();
+        for i in 0..2 {
+            for prefix in ["bank", "tech"] {
+                assert_eq!(group_sum(&demeaned, prefix, i), 0.0);
+                assert!(group_sum(&zscored, prefix, i).abs() < 1e-12);
+            }
+        }
+        // 两只股票的分组 z-score 为 ±1/sqrt(2)
+        let tech = zscored.df().column("tech_2").unwrap().f64().unwrap().get(0).unwrap();
+        assert!((tech - 0.5f64.sqrt()).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_align() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let left = WideTable::new(DataFrame::new(vec![
+            Series::new("date", &[date(3), date(1), date(2)]),
+            Series::new("a", &[3.0, 1.0, 2.0]),
+            Series::new("b", &[30.0, 10.0, 20.0]),
+            Series::new("c", &[0.0, 0.0, 0.0]),
+        ]).unwrap(), "date").unwrap();
+        let right = WideTable::new(DataFrame::new(vec![
+            Series::new("trade_date", &[date(2), date(3), date(4)]),
+            Series::new("b", &[2.0, 3.0, 4.0]),
+            Series::new("a", &[0.2, 0.3, 0.4]),
+        ]).unwrap(), "trade_date").unwrap();
+        
+        let (l, r, summary) = left.align_with_summary(&right).unwrap();
+        assert_eq!(l.df().get_column_names(), &["date", "a", "b"]);
+        assert_eq!(r.df().get_column_names(), &["trade_date", "a", "b"]);
+        assert_eq!(l.df().column("a").unwrap().f64().unwrap().to_vec(), vec![Some(2.0), Some(3.0)]);
+        assert_eq!(r.df().column("a").unwrap().f64().unwrap().to_vec(), vec![Some(0.2), Some(0.3)]);
+        assert_eq!(summary, AlignSummary {
+            dropped_rows_left: 1,
+            dropped_rows_right: 1,
+            dropped_cols_left: 1,
+            dropped_cols_right: 0,
+        });
+        
+        let disjoint = WideTable::new(DataFrame::new(vec![
+            Series::new("date", &[date(9)]),
+            Series::new("a", &[1.0]),
+        ]).unwrap(), "date").unwrap();
+        assert!(left.align(&disjoint).is_err());
+    }
+    
+    #[test]
+    fn test_rolling_cov() {
+        let dates: Vec<NaiveDate> = (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let left = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates.clone()),
+            Series::new("a", &[Some(1.0), Some(2.0), Some(4.0), None, Some(3.0)]),
+            Series::new("b", &[2.0, 4.0, 8.0, 6.0, 6.0]),
+        ]).unwrap(), "date").unwrap();
+        // 时间倒序、列名不同
+        let right = WideTable::new(DataFrame::new(vec![
+            Series::new("t", dates.iter().rev().cloned().collect::<Vec<_>>()),
+            Series::new("m", &[5.0, 4.0, 3.0, 2.0, 1.0]),
+        ]).unwrap(), "t").unwrap();
+        
+        let table = left.rolling_cov_table(&right, 3).unwrap();
+        assert_eq!(table.time_col(), "date");
+        assert_eq!(table.stock_cols(), vec!["a:m", "b:m"]);
+        let pairs = left.rolling_cov(&right, 3).unwrap();
+        assert_eq!(pairs.len(), 2);
+        
+        let a_m: Vec<Option<f64>> = pairs[&("a".to_string(), "m".to_string())].f64().unwrap().into_iter().collect();
+        assert_eq!((a_m[0], a_m[1], a_m[3], a_m[4]), (None, None, None, None));
+        // a = [1, 2, 4]，m = [1, 2, 3]：协方差 1.5
+        assert!((a_m[2].unwrap() - 1.5).abs() < 1e-12);
+        let b_m = pairs[&("b".to_string(), "m".to_string())].f64().unwrap().get(4).unwrap();
+        assert!((b_m + 1.0).abs() < 1e-12);
+        
+        // 与自身的协方差即方差
+        let own = left.rolling_cov_table(&left, 3).unwrap();
+        let var_b = own.df().column("b:b").unwrap().f64().unwrap().get(2).unwrap();
+        assert!((var_b - 28.0 / 3.0).abs() < 1e-12);
+        assert!(left.rolling_cov(&right, 1).is_err());
+    }
+    
+    #[test]
+    fn test_rolling_correlation_to_series() {
+        let table = price_table(vec![1.0, 2.0, 3.0, 5.0, 4.0]);
+        let index = Series::new("index", &[2.0, 4.0, 6.0, 10.0, 8.0]);
+        
+        let corr = table.rolling_correlation_to_series(&index, 3).unwrap();
+        let s = corr.df().column("stock_A_corr_3").unwrap().f64().unwrap().clone();
+        assert_eq!(s.null_count(), 2);
+        assert!((s.get(4).unwrap() - 1.0).abs() < 1e-12);
+        
+        assert!(table.rolling_correlation_to_series(&Series::new("index", &[1.0]), 3).is_err());
+    }
+    
+    #[test]
+    fn test_assert_no_future_leakage() {
+        let table = price_table(vec![100.0, 101.0, 103.0, 102.0, 104.0, 105.0]);
+        
+        assert!(table.sma(3).unwrap().assert_no_future_leakage().is_ok());
+        assert!(table.pct_change(2).unwrap().assert_no_future_leakage().is_ok());
+        
+        let err = table.forward_returns(2).unwrap().assert_no_future_leakage().unwrap_err();
+        assert!(err.to_string().contains("stock_A_fwd_return_2"));
+        
+        // 窗口未满即出结果的动量与波动率都符合各自的 min_periods
+        assert!(table.momentum(3).unwrap().assert_no_future_leakage().is_ok());
+        assert!(table.volatility(3).unwrap().assert_no_future_leakage().is_ok());
+        
+        // 以数字结尾的股票代码不是因子列
+        let tickers = WideTable::new(DataFrame::new(vec![
+            Series::new("date", &[1i64, 2, 3]),
+            Series::new("SH_600000", &[10.0, 10.1, 10.2]),
+            Series::new("SZ_000001", &[Some(1.0), Some(1.1), None]),
+        ]).unwrap(), "date").unwrap();
+        assert!(tickers.assert_no_future_leakage().is_ok());
+        
+        // 用负 shift 伪造的 sma：第一行就有值
+        let leaked = table
+            .sma(3)
+            .unwrap()
+            .lazy()
+            .with_column(col("stock_A").shift(lit(-2)).alias("stock_A_sma_3"))
+            .collect()
+            .unwrap();
+        let err = WideTable::new(leaked, "date").unwrap().assert_no_future_leakage().unwrap_err();
+        assert!(err.to_string().contains("stock_A_sma_3"));
+    }
+    
+    #[test]
+    fn test_sort_and_reorder_columns() {
+        let df = DataFrame::new(vec![
+            Series::new("c", &[3.0]),
+            Series::new("date", &[NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()]),
+            Series::new("a", &[1.0]),
+            Series::new("b", &[2.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        assert_eq!(table.sort_columns().df().get_column_names(), &["date", "a", "b", "c"]);
+        let reordered = table.reorder_columns(&["b"]).unwrap();
+        assert_eq!(reordered.df().get_column_names(), &["date", "b", "c", "a"]);
+        assert!(table.reorder_columns(&["x"]).is_err());
+        assert!(table.reorder_columns(&["a", "a"]).is_err());
+    }
+    
+    #[test]
+    fn test_assert_no_nans_and_validate_schema() {
+        let table = price_table(vec![100.0, f64::NAN, 102.0]);
+        let err = table.assert_no_nans().err().unwrap();
+        assert!(err.to_string().contains("stock_A（1 行）"));
+        assert!(price_table(vec![100.0, 101.0]).assert_no_nans().is_ok());
+        
+        assert!(table.validate_schema(&["stock_A"], DataType::Float64).is_ok());
+        assert!(table.validate_schema(&["stock_A"], DataType::Int64).is_err());
+        assert!(table.validate_schema(&["stock_B"], DataType::Float64).is_err());
+    }
+    
+    #[test]
+    fn test_typed_errors() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let df = DataFrame::new(vec![
+            Series::new("date", &[date, date]),
+            Series::new("stock_A", &[1.0, 2.0]),
+        ]).unwrap();
+        
+        assert!(matches!(
+            WideTable::new(df.clone(), "time"),
+            Err(WideTableError::TimeColumnMissing(name)) if name == "time"
+        ));
+        
+        let table = WideTable::new(df, "date").unwrap();
+        assert!(matches!(
+            table.ensure_unique_dates(),
+            Err(WideTableError::DuplicateDate(d)) if d == "2024-01-01"
+        ));
+        assert!(matches!(table.sma(0), Err(WideTableError::InvalidArgument(_))));
+        assert!(matches!(table.reorder_columns(&["x"]), Err(WideTableError::ColumnNotFound(_))));
+        
+        // 在返回 anyhow::Result 的函数中可以直接用 `?`
+        let as_anyhow = || -> anyhow::Result<WideTable> { Ok(table.sma(0)?) };
+        assert!(as_anyhow().is_err());
+    }
+    
+    #[test]
+    fn test_into_lazy_round_trip() {
+        let table = price_table(vec![100.0, 110.0]);
+        
+        let (lf, time_col) = table.into_lazy();
+        assert_eq!(time_col, "date");
+        let doubled = lf.with_columns([(col("stock_A") * lit(2.0)).alias("stock_A")]);
+        let table = WideTable::from_lazy(doubled, time_col).unwrap();
+        assert_eq!(last_value(&table, "stock_A"), 220.0);
+    }
+    
+    #[test]
+    fn test_batched_columns_match_single_column() {
+        let dates: Vec<NaiveDate> = (0..30)
+            .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i))
+            .collect();
+        let mut columns = vec![Series::new("date", dates.clone())];
+        for c in 0..50 {
+            let prices: Vec<f64> = (0..30).map(|i| 100.0 + ((i * 7 + c * 13) % 29) as f64).collect();
+            columns.push(Series::new(&format!("s{}", c), prices));
+        }
+        let table = WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap();
+        
+        let returns = table.pct_change(2).unwrap();
+        let momentum = table.momentum(5).unwrap();
+        for c in [0, 17, 49] {
+            let name = format!("s{}", c);
+            let single = WideTable::new(
+                DataFrame::new(vec![Series::new("date", dates.clone()), table.df().column(&name).unwrap().clone()]).unwrap(),
+                "date",
+            ).unwrap();
+            for (batched, one, col_name) in [
+                (&returns, single.pct_change(2).unwrap(), format!("{}_pct_change_2", name)),
+                (&momentum, single.momentum(5).unwrap(), format!("{}_momentum_5", name)),
+            ] {
+                let a = batched.df().column(&col_name).unwrap();
+                let b = one.df().column(&col_name).unwrap();
+                assert!(a.equals_missing(b), "{} 不一致", col_name);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_ewm_volatility_recursion() {
+        let table = price_table(vec![100.0, 110.0, 132.0]);
+        assert!(table.ewm_volatility(0.0).is_err());
+        
+        // 收益率 0.1、0.2：sigma^2 = 0.5 * 0.04 + 0.5 * 0.01
+        let vol = table.ewm_volatility(0.5).unwrap();
+        assert!((last_value(&vol, "stock_A_ewm_vol") - 0.025_f64.sqrt()).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_serde_json_round_trip() {
+        let table = price_table(vec![100.0, 110.0, 121.0]);
+        
+        let json = serde_json::to_string(&table).unwrap();
+        let restored: WideTable = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.time_col(), "date");
+        assert!(restored.df().equals_missing(table.df()));
+        
+        let bad = json.replace("\"time_col\":\"date\"", "\"time_col\":\"missing\"");
+        assert!(serde_json::from_str::<WideTable>(&bad).is_err());
+    }
+    
+    #[test]
+    fn test_parquet_with_meta_round_trip() {
+        let mut table = price_table(vec![100.0, 110.0, 121.0]);
+        table.rename_time_column("trade_date").unwrap();
+        let dir = std::env::temp_dir().join(format!("quant_factor_meta_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (data_path, meta_path) = (dir.join("close.parquet"), dir.join("close.meta.json"));
+        
+        table.to_parquet(&data_path).unwrap();
+        table.set_description(Some("收盘价".to_string()));
+        table.save_meta(&meta_path).unwrap();
+        assert_eq!(WideTableMeta::load(&meta_path).unwrap(), table.meta());
+        
+        let restored = WideTable::load_with_meta(&data_path, &meta_path).unwrap();
+        assert_eq!(restored.time_col(), "trade_date");
+        assert_eq!(restored.description(), Some("收盘价"));
+        assert!(restored.df().equals_missing(table.df()));
+        // 派生出的新宽表不继承说明
+        assert_eq!(restored.pct_change(1).unwrap().description(), None);
+        
+        std::fs::write(&meta_path, "{\"time_col\": \"date\"}").unwrap();
+        assert!(matches!(
+            WideTable::load_with_meta(&data_path, &meta_path),
+            Err(WideTableError::TimeColumnMissing(_))
+        ));
+        std::fs::write(&meta_path, "not json").unwrap();
+        assert!(matches!(WideTable::load_with_meta(&data_path, &meta_path), Err(WideTableError::Json(_))));
+        assert!(matches!(
+            WideTable::load_with_meta(&data_path, dir.join("missing.json")),
+            Err(WideTableError::Io(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_momentum_weighting() {
+        // 每期收益率相同：两种加权结果一致
+        let constant = price_table(vec![100.0, 110.0, 121.0, 133.1, 146.41]);
+        let equal = constant.momentum_weighted(3, MomentumWeighting::Equal).unwrap();
+        let decay = constant.momentum_weighted(3, MomentumWeighting::LinearDecay).unwrap();
+        let diff = last_value(&equal, "stock_A_momentum_3") - last_value(&decay, "stock_A_momentum_decay_3");
+        assert!(diff.abs() < 1e-9);
+        
+        // 最近一期大涨：线性衰减的动量更高
+        let jump = price_table(vec![100.0, 100.0, 100.0, 100.0, 120.0]);
+        let equal = jump.momentum_weighted(3, MomentumWeighting::Equal).unwrap();
+        let decay = jump.momentum_weighted(3, MomentumWeighting::LinearDecay).unwrap();
+        assert!(last_value(&decay, "stock_A_momentum_decay_3") > last_value(&equal, "stock_A_momentum_3"));
+    }
+    
+    #[test]
+    fn test_ewm_momentum() {
+        // 收益率恒为 10%：归一化后的加权平均仍为 10%
+        let constant = price_table(vec![100.0, 110.0, 121.0, 133.1, 146.41]);
+        let ewm = constant.ewm_momentum(2.0).unwrap();
+        assert!((last_value(&ewm, "stock_A_ewm_momentum") - 0.1).abs() < 1e-9);
+        
+        // 半衰期为 1 时 alpha = 0.5，权重依次为 1, 0.5, 0.25
+        let table = price_table(vec![100.0, 110.0, 99.0, 108.9]);
+        let ewm = table.ewm_momentum(1.0).unwrap();
+        let expected = (0.1 + 0.5 * -0.1 + 0.25 * 0.1) / 1.75;
+        assert!((last_value(&ewm, "stock_A_ewm_momentum") - expected).abs() < 1e-9);
+        
+        let bps = table.ewm_momentum_with(1.0, ReturnScale::BasisPoints).unwrap();
+        assert!((last_value(&bps, "stock_A_ewm_momentum_bps") - expected * 10_000.0).abs() < 1e-6);
+        
+        assert!(table.ewm_momentum(0.0).is_err());
+        assert!(table.ewm_momentum(f64::NAN).is_err());
+    }
+    
+    #[test]
+    fn test_head_and_tail() {
+        let table = price_table(vec![1.0, 2.0, 3.0, 4.0]);
+        let values = |t: &WideTable| -> Vec<f64> { t.df().column("stock_A").unwrap().f64().unwrap().into_no_null_iter().collect() };
+        
+        assert_eq!(values(&table.head(2)), vec![1.0, 2.0]);
+        assert_eq!(values(&table.tail(3)), vec![2.0, 3.0, 4.0]);
+        assert_eq!(table.head(10).df().height(), 4);
+        assert_eq!(table.tail(1).time_col(), "date");
+    }
+    
+    #[test]
+    fn test_trim_nans() {
+        let dates: Vec<NaiveDate> = (1..=6).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[None, Some(f64::NAN), Some(1.0), None, Some(2.0), None]),
+            Series::new("b", &[None, Some(1.0), Some(1.0), None, Some(2.0), Some(3.0)]),
+            Series::new("c", &[None, None, Some(1.0), None, None, None]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let days = |t: &WideTable| -> Vec<u32> {
+            t.df().column("date").unwrap().date().unwrap().as_date_iter().flatten().map(|d| d.day()).collect()
+        };
+        
+        assert_eq!(days(&table.trim_leading_nans(0.0).unwrap()), vec![2, 3, 4, 5, 6]);
+        assert_eq!(days(&table.trim_trailing_nans(0.5).unwrap()), vec![1, 2, 3, 4, 5]);
+        // 中间全部缺失的第 4 行保留
+        assert_eq!(days(&table.trim_nans(0.5).unwrap()), vec![3, 4, 5]);
+        assert_eq!(table.trim_nans(1.0).unwrap().df().height(), 1);
+        assert!(table.trim_nans(1.5).is_err());
+    }
+    
+    #[test]
+    fn test_rename_time_column() {
+        let mut table = price_table(vec![1.0, 2.0]);
+        table.rename_time_column("trade_date").unwrap();
+        assert_eq!(table.time_col(), "trade_date");
+        assert_eq!(table.df().get_column_names(), &["trade_date", "stock_A"]);
+        assert!(table.pct_change(1).is_ok());
+        
+        assert!(matches!(table.rename_time_column("stock_A"), Err(WideTableError::DuplicateColumn(_))));
+        assert_eq!(table.time_col(), "trade_date");
+        table.rename_time_column("trade_date").unwrap();
+    }
+    
+    #[test]
+    fn test_iter_stocks() {
+        let table = WideTable::new(DataFrame::new(vec![
+            Series::new("a", &[1.0, 2.0]),
+            Series::new("date", &[1i64, 2]),
+            Series::new("b", &[3.0, 4.0]),
+        ]).unwrap(), "date").unwrap();
+        
+        let stocks: Vec<(String, Series)> = table.iter_stocks().collect();
+        let names: Vec<&str> = stocks.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(stocks[1].1.equals(table.df().column("b").unwrap()));
+    }
+    
+    #[test]
+    fn test_describe() {
+        let dates: Vec<NaiveDate> = (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[Some(1.0), Some(2.0), Some(3.0), Some(4.0), Some(f64::NAN)]),
+            Series::new("stock_B", &[None::<f64>; 5]),
+            Series::full_null("stock_C", 5, &DataType::Null),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let summary = table.describe().unwrap();
+        assert_eq!(
+            summary.get_column_names(),
+            &["stock", "count", "mean", "std", "min", "25%", "50%", "75%", "max", "null_count"]
+        );
+        let stocks: Vec<&str> = summary.column("stock").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(stocks, vec!["stock_A", "stock_B", "stock_C"]);
+        
+        let column = |name: &str| -> Vec<Option<f64>> {
+            summary.column(name).unwrap().cast(&DataType::Float64).unwrap().f64().unwrap().into_iter().collect()
+        };
+        assert_eq!(column("count"), vec![Some(4.0), Some(0.0), Some(0.0)]);
+        assert_eq!(column("null_count"), vec![Some(1.0), Some(5.0), Some(5.0)]);
+        assert_eq!(column("mean"), vec![Some(2.5), None, None]);
+        assert_eq!(column("25%"), vec![Some(1.75), None, None]);
+        assert_eq!(column("max"), vec![Some(4.0), None, None]);
+    }
+    
+    #[test]
+    fn test_describe_summary_and_dtypes() {
+        let dates: Vec<NaiveDate> = (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[3.0, 1.0, 5.0, 2.0, 4.0]),
+            Series::new("stock_B", &[10i64, 50, 30, 20, 40]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        assert_eq!(
+            table.dtypes(),
+            vec![
+                ("date".to_string(), DataType::Date),
+                ("stock_A".to_string(), DataType::Float64),
+                ("stock_B".to_string(), DataType::Int64),
+            ]
+        );
+        
+        // 时间列不出现在结果中，整数列按浮点统计
+        let summary = table.describe().unwrap();
+        let stocks: Vec<&str> = summary.column("stock").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(stocks, vec!["stock_A", "stock_B"]);
+        let column = |name: &str| -> Vec<f64> {
+            summary.column(name).unwrap().cast(&DataType::Float64).unwrap().f64().unwrap().into_no_null_iter().collect()
+        };
+        assert_eq!(column("count"), vec![5.0, 5.0]);
+        assert_eq!(column("mean"), vec![3.0, 30.0]);
+        assert!((column("std")[0] - 2.5f64.sqrt()).abs() < 1e-12);
+        assert!((column("std")[1] - 250.0f64.sqrt()).abs() < 1e-9);
+        assert_eq!(column("min"), vec![1.0, 10.0]);
+        assert_eq!(column("25%"), vec![2.0, 20.0]);
+        assert_eq!(column("50%"), vec![3.0, 30.0]);
+        assert_eq!(column("75%"), vec![4.0, 40.0]);
+        assert_eq!(column("max"), vec![5.0, 50.0]);
+        assert_eq!(column("null_count"), vec![0.0, 0.0]);
+    }
+    
+    #[test]
+    fn test_value_weighted_mean() {
+        let dates: Vec<NaiveDate> = (1..=3).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let returns = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates.clone()),
+            Series::new("a", &[Some(0.01), Some(0.02), None]),
+            Series::new("b", &[0.03, f64::NAN, 0.05]),
+            Series::new("only_returns", &[1.0, 1.0, 1.0]),
+        ]).unwrap(), "date").unwrap();
+        // 权重表缺少第 3 期，且多出一只股票
+        let caps = WideTable::new(DataFrame::new(vec![
+            Series::new("d", dates[..2].to_vec()),
+            Series::new("b", &[3.0, 2.0]),
+            Series::new("a", &[1.0, 2.0]),
+            Series::new("only_caps", &[100.0, 100.0]),
+        ]).unwrap(), "d").unwrap();
+        
+        let mean = returns.value_weighted_mean(&caps).unwrap();
+        assert_eq!(mean.name(), "value_weighted_mean");
+        let values: Vec<Option<f64>> = mean.f64().unwrap().into_iter().collect();
+        assert!((values[0].unwrap() - 0.025).abs() < 1e-12);
+        assert!((values[1].unwrap() - 0.02).abs() < 1e-12);
+        assert_eq!(values[2], None);
+        
+        let unrelated = price_table(vec![1.0, 2.0, 3.0]);
+        assert!(matches!(returns.value_weighted_mean(&unrelated), Err(WideTableError::InvalidArgument(_))));
+    }
+    
+    #[test]
+    fn test_clip_price_spikes() {
+        let dates: Vec<NaiveDate> = (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let table = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[Some(10.0), Some(1000.0), None, Some(10.2), Some(10.4)]),
+            Series::new("b", &[20.0, 21.0, 2.0, 22.0, 23.0]),
+        ]).unwrap(), "date").unwrap();
+        
+        let (clipped, counts) = table.clip_price_spikes_with_counts(0.5).unwrap();
+        let values = |name: &str| -> Vec<Option<f64>> {
+            clipped.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        assert_eq!(values("a"), vec![Some(10.0), Some(10.0), None, Some(10.2), Some(10.4)]);
+        assert_eq!(values("b"), vec![Some(20.0), Some(21.0), Some(21.0), Some(22.0), Some(23.0)]);
+        assert_eq!((counts["a"], counts["b"]), (1, 1));
+        
+        assert!(table.clip_price_spikes(100.0).unwrap().df().equals_missing(table.df()));
+        assert!(table.clip_price_spikes(0.0).is_err());
+    }
+    
+    #[test]
+    fn test_quantile_buckets() {
+        let table = WideTable::new(DataFrame::new(vec![
+            Series::new("date", &[1i64, 2]),
+            Series::new("a", &[Some(1.0), Some(5.0)]),
+            Series::new("b", &[Some(2.0), None]),
+            Series::new("c", &[Some(2.0), Some(f64::NAN)]),
+            Series::new("d", &[Some(3.0), Some(4.0)]),
+        ]).unwrap(), "date").unwrap();
+        
+        // 第 1 行 b、c 在分界点上取值相同，按列顺序稳定排序后分别落入两组
+        let buckets = table.quantile_buckets(2).unwrap();
+        let row = |table: &WideTable, i: usize| -> Vec<Option<i32>> {
+            ["a", "b", "c", "d"].iter().map(|c| table.df().column(c).unwrap().i32().unwrap().get(i)).collect()
+        };
+        assert_eq!(row(&buckets, 0), vec![Some(1), Some(1), Some(2), Some(2)]);
+        // 第 2 行 null 与 NaN 不分组
+        assert_eq!(row(&buckets, 1), vec![Some(2), None, None, Some(1)]);
+        
+        // 有效股票数少于分组数：按 floor(j * n_buckets / n) + 1 分配，组号不连续
+        let sparse = table.quantile_buckets(5).unwrap();
+        assert_eq!(row(&sparse, 1), vec![Some(3), None, None, Some(1)]);
+        let spread = table.cross_qcut_with(5, SparseGroupPolicy::Spread).unwrap();
+        assert_eq!(row(&spread, 1), row(&sparse, 1));
+        assert!(table.quantile_buckets(0).is_err());
+    }
+    
+    #[test]
+    fn test_cross_qcut() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        // 列顺序与列名顺序相反；第 1 期 s0 与 s1 取值相同，第 2 期只有 3 只股票有效
+        let mut columns = vec![Series::new("date", dates)];
+        columns.extend((0..10).rev().map(|j| {
+            let first = if j == 0 { 1.0 } else { j as f64 };
+            Series::new(&format!("s{}", j), &[Some(first), (j < 3).then_some(j as f64)])
+        }));
+        let table = WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap();
+        
+        assert!(matches!(table.cross_qcut(5), Err(WideTableError::InvalidArgument(_))));
+        let groups = table.cross_qcut_with(5, SparseGroupPolicy::Collapse).unwrap();
+        let group = |j: usize, i: usize| {
+            groups.df().column(&format!("s{}", j)).unwrap().i32().unwrap().get(i)
+        };
+        let first: Vec<Option<i32>> = (0..10).map(|j| group(j, 0)).collect();
+        assert_eq!(first, [1, 1, 2, 2, 3, 3, 4, 4, 5, 5].map(Some));
+        let second: Vec<Option<i32>> = (0..4).map(|j| group(j, 1)).collect();
+        assert_eq!(second, vec![Some(1), Some(2), Some(3), None]);
+        
+        // 相同值按列名而非列顺序决定先后
+        let tied = WideTable::new(DataFrame::new(vec![
+            Series::new("date", &[1i64]),
+            Series::new("b", &[1.0]),
+            Series::new("a", &[1.0]),
+        ]).unwrap(), "date").unwrap();
+        let tied = tied.cross_qcut(2).unwrap();
+        assert_eq!(tied.df().column("a").unwrap().i32().unwrap().get(0), Some(1));
+        assert_eq!(tied.df().column("b").unwrap().i32().unwrap().get(0), Some(2));
+    }
+    
+    #[test]
+    fn test_rolling_skew_and_kurtosis() {
+        let dates: Vec<NaiveDate> = (1..=6).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[Some(100.0), Some(102.0), Some(99.0), Some(104.0), Some(101.0), Some(108.0)]),
+            Series::new("stock_B", &[Some(100.0), Some(101.0), None, Some(103.0), Some(104.0), Some(105.0)]),
+            // 收益率恒为 10%，只有浮点误差
+            Series::new("stock_C", &[Some(100.0), Some(110.0), Some(121.0), Some(133.1), Some(146.41), Some(161.051)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let skew = table.rolling_skew(4).unwrap();
+        let kurt = table.rolling_kurtosis(4).unwrap();
+        let column = |t: &WideTable, name: &str| -> Vec<Option<f64>> {
+            t.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        let assert_close = |actual: Option<f64>, expected: f64| {
+            assert!((actual.unwrap() - expected).abs() < 1e-9, "{:?} != {}", actual, expected);
+        };
+        
+        // 参考值来自 pandas 的 rolling(4).skew() / rolling(4).kurt()（首个收益率为 null，min_periods 分别为 3、4）
+        let a_skew = column(&skew, "stock_A_skew_4");
+        assert_eq!(a_skew[..3], [None; 3]);
+        assert_close(a_skew[3], -0.6860379172427993);
+        assert_close(a_skew[4], 0.497046558402789);
+        assert_close(a_skew[5], 0.1119854389104388);
+        let a_kurt = column(&kurt, "stock_A_kurt_4");
+        assert_eq!(a_kurt[..4], [None; 4]);
+        assert_close(a_kurt[4], -3.200802829258386);
+        assert_close(a_kurt[5], -5.355828164033536);
+        
+        // 缺失价格前后的收益率都为 null，任何窗口的有效收益率都不足 3 个
+        assert_eq!(column(&skew, "stock_B_skew_4"), vec![None; 6]);
+        assert_eq!(column(&kurt, "stock_B_kurt_4"), vec![None; 6]);
+        
+        // 收益率恒定的窗口没有偏度和峰度
+        assert_eq!(column(&skew, "stock_C_skew_4"), vec![None; 6]);
+        assert_eq!(column(&kurt, "stock_C_kurt_4"), vec![None; 6]);
+        
+        // 单位只影响保留的收益率列，偏度与峰度不变
+        let skew_pct = table.rolling_skew_with(4, ReturnScale::Percent).unwrap();
+        assert_eq!(column(&skew_pct, "stock_A_skew_4"), a_skew);
+        assert!(skew_pct.df().column("stock_A_pct_change_1").is_ok());
+        assert!(skew_pct.df().column("stock_A_return_1").is_err());
+        let kurt_bps = table.rolling_kurtosis_with(4, ReturnScale::BasisPoints).unwrap();
+        assert_eq!(column(&kurt_bps, "stock_C_kurt_4"), vec![None; 6]);
+        
+        assert!(table.rolling_skew(2).is_err());
+        assert!(table.rolling_kurtosis(3).is_err());
+    }
+    
+    #[test]
+    fn test_cross_section_stats() {
+        let dates: Vec<NaiveDate> = (1..=3).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[Some(1.0), Some(-2.0), None]),
+            Series::new("stock_B", &[Some(3.0), Some(f64::NAN), None]),
+            Series::new("stock_C", &[Some(-1.0), Some(4.0), Some(5.0)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let stats = table.cross_section_stats().unwrap();
+        assert_eq!(
+            stats.get_column_names(),
+            &["date", "mean", "std", "min", "max", "count", "positive_count", "median"]
+        );
+        let column = |name: &str| -> Vec<Option<f64>> {
+            stats.column(name).unwrap().cast(&DataType::Float64).unwrap().f64().unwrap().into_iter().collect()
+        };
+        assert_eq!(column("mean"), vec![Some(1.0), Some(1.0), Some(5.0)]);
+        assert_eq!(column("std"), vec![Some(2.0), Some(18.0f64.sqrt()), None]);
+        assert_eq!(column("min"), vec![Some(-1.0), Some(-2.0), Some(5.0)]);
+        assert_eq!(column("max"), vec![Some(3.0), Some(4.0), Some(5.0)]);
+        assert_eq!(column("median"), vec![Some(1.0), Some(1.0), Some(5.0)]);
+        // NaN 不计入有效值
+        assert_eq!(column("count"), vec![Some(3.0), Some(2.0), Some(1.0)]);
+        assert_eq!(column("positive_count"), vec![Some(2.0), Some(1.0), Some(1.0)]);
+        
+        let summary = table.cross_sectional_stats().unwrap();
+        assert_eq!(summary.get_column_names(), &["date", "mean", "std", "median", "count"]);
+        assert!(summary.column("median").unwrap().equals_missing(stats.column("median").unwrap()));
+    }
+    
+    #[test]
+    fn test_pct_change_multi() {
+        let table = price_table(vec![100.0, 110.0, 99.0, 120.0]);
+        
+        let result = table.pct_change_multi(&[1, 2]).unwrap();
+        assert_eq!(
+            result.df().get_column_names(),
+            &["date", "stock_A", "stock_A_pct_change_1", "stock_A_pct_change_2"]
+        );
+        let column = |name: &str| -> Vec<Option<f64>> {
+            result.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        let one = column("stock_A_pct_change_1");
+        assert_eq!(one[0], None);
+        assert!((one[1].unwrap() - 10.0).abs() < 1e-9);
+        assert!((one[2].unwrap() + 10.0).abs() < 1e-9);
+        let two = column("stock_A_pct_change_2");
+        assert_eq!(two[..2], [None, None]);
+        assert!((two[2].unwrap() + 1.0).abs() < 1e-9);
+        assert!((two[3].unwrap() - (120.0 / 110.0 - 1.0) * 100.0).abs() < 1e-9);
+        
+        // 与逐个周期调用 pct_change 的结果一致
+        for p in [1, 2] {
+            let name = format!("stock_A_pct_change_{}", p);
+            let single = table.pct_change(p).unwrap();
+            assert!(result.df().column(&name).unwrap().equals_missing(single.df().column(&name).unwrap()));
+        }
+        
+        assert!(table.pct_change_multi(&[]).is_err());
+        assert!(table.pct_change_multi(&[1, 0]).is_err());
+        assert!(table.pct_change_multi(&[-1]).is_err());
+        assert!(table.pct_change_multi(&[2, 2]).is_err());
+    }
+    
+    #[test]
+    fn test_lazy_round_trip() {
+        let table = price_table(vec![100.0, 110.0, 121.0]);
+        
+        let lf = table.lazy().with_columns([(col("stock_A") * lit(2.0)).alias("stock_A_double")]);
+        let result = WideTable::from_lazy(lf, "date").unwrap();
+        assert_eq!(result.time_col(), "date");
+        assert_eq!(result.stock_cols(), vec!["stock_A", "stock_A_double"]);
+        assert_eq!(last_value(&result, "stock_A_double"), 242.0);
+        // 原表不受影响
+        assert_eq!(table.stock_cols(), vec!["stock_A"]);
+        
+        let (lf, time_col) = table.into_lazy();
+        let result = WideTable::from_lazy(lf, time_col).unwrap();
+        assert_eq!(result.df().get_column_names(), &["date", "stock_A"]);
+        
+        // 自定义表达式删除了时间列
+        let lf = result.lazy().select([col("stock_A")]);
+        assert!(matches!(
+            WideTable::from_lazy(lf, "date"),
+            Err(WideTableError::TimeColumnMissing(name)) if name == "date"
+        ));
+    }
+    
+    #[test]
+    fn test_join_factors() {
+        let table = |days: &[u32], name: &str, values: &[f64]| -> WideTable {
+            let dates: Vec<NaiveDate> = days.iter().map(|&d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+            let df = DataFrame::new(vec![Series::new("date", dates), Series::new(name, values)]).unwrap();
+            WideTable::new(df, "date").unwrap()
+        };
+        let momentum = table(&[1, 2, 3, 4], "A_mom", &[1.0, 2.0, 3.0, 4.0]);
+        let volatility = table(&[2, 3, 4, 5], "A_vol", &[20.0, 30.0, 40.0, 50.0]);
+        let size = table(&[4, 3, 2], "A_size", &[400.0, 300.0, 200.0]);
+        
+        // 内连接只保留三张表共有的时间，各列按时间对齐
+        let joined = momentum.join_factors(&[&volatility, &size]).unwrap();
+        assert_eq!(joined.df().get_column_names(), &["date", "A_mom", "A_vol", "A_size"]);
+        let sorted = joined.sorted_df().unwrap();
+        let column = |name: &str| -> Vec<f64> {
+            sorted.column(name).unwrap().f64().unwrap().into_no_null_iter().collect()
+        };
+        assert_eq!(column("A_mom"), vec![2.0, 3.0, 4.0]);
+        assert_eq!(column("A_vol"), vec![20.0, 30.0, 40.0]);
+        assert_eq!(column("A_size"), vec![200.0, 300.0, 400.0]);
+        
+        let duplicate = table(&[1, 2], "A_vol", &[0.0, 0.0]);
+        assert!(matches!(
+            momentum.join_factors(&[&volatility, &duplicate]),
+            Err(WideTableError::DuplicateColumn(name)) if name == "A_vol"
+        ));
+        assert!(matches!(
+            momentum.join_factors(&[&momentum]),
+            Err(WideTableError::DuplicateColumn(name)) if name == "A_mom"
+        ));
+    }
+    
+    #[test]
+    fn test_pct_change_fraction_and_momentum() {
+        let table = price_table(vec![100.0, 110.0, 99.0, 108.9]);
+        
+        let returns = table.pct_change_fraction(1).unwrap();
+        assert_eq!(returns.df().get_column_names(), &["date", "stock_A", "stock_A_return_1"]);
+        let values: Vec<Option<f64>> = returns.df().column("stock_A_return_1").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values[0], None);
+        for (actual, expected) in values[1..].iter().zip([0.1, -0.1, 0.1]) {
+            assert!((actual.unwrap() - expected).abs() < 1e-12);
+        }
+        let two = table.pct_change_fraction(2).unwrap();
+        assert!((last_value(&two, "stock_A_return_2") - (108.9 / 110.0 - 1.0)).abs() < 1e-12);
+        
+        // 动量累加的是小数收益率，不会被放大 100 倍
+        let momentum = table.momentum(3).unwrap();
+        assert!((last_value(&momentum, "stock_A_momentum_3") - 0.1).abs() < 1e-12);
+        
+        assert!(table.pct_change_fraction(0).is_err());
+        assert!(table.pct_change_fraction(-1).is_err());
+    }
+    
+    #[test]
+    fn test_rolling_apply() {
+        // 时间倒序输入，结果按时间排序
+        let dates: Vec<NaiveDate> = (1..=5).rev().map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[Some(5.0), Some(4.0), None, Some(2.0), Some(1.0)]),
+            Series::new("stock_B", &[Some(1.0), None, None, None, Some(1.0)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let result = table.rolling_apply(3, |w| w.iter().sum::<f64>() / w.len() as f64).unwrap();
+        let column = |name: &str| -> Vec<Option<f64>> {
+            result.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        // 窗口未满为 null，窗口内的 null 被剔除后再求均值
+        assert_eq!(column("stock_A_rolling_apply_3"), vec![None, None, Some(1.5), Some(3.0), Some(4.5)]);
+        // 窗口内全部为 null 时结果为 null
+        assert_eq!(column("stock_B_rolling_apply_3"), vec![None, None, Some(1.0), None, Some(1.0)]);
+        
+        // 传入闭包的只有非 null 值
+        let counts = table.rolling_apply(2, |w| w.len() as f64).unwrap();
+        let counts: Vec<Option<f64>> = counts.df().column("stock_A_rolling_apply_2").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(counts, vec![None, Some(2.0), Some(1.0), Some(1.0), Some(2.0)]);
+        
+        assert!(table.rolling_apply(0, |w| w[0]).is_err());
+    }
+    
+    #[test]
+    fn test_return_scale() {
+        let table = price_table(vec![100.0, 102.0, 99.0, 104.0, 101.0]);
+        
+        let percent = table.pct_change_with(1, ReturnScale::Percent).unwrap();
+        let fraction = table.pct_change_with(1, ReturnScale::Fraction).unwrap();
+        let bps = table.pct_change_with(1, ReturnScale::BasisPoints).unwrap();
+        let percent = last_value(&percent, "stock_A_pct_change_1");
+        let fraction = last_value(&fraction, "stock_A_return_1");
+        let bps = last_value(&bps, "stock_A_bps_change_1");
+        assert!((percent - fraction * 100.0).abs() < 1e-9);
+        assert!((bps - fraction * 10_000.0).abs() < 1e-9);
+        // pct_change 的单位为百分比
+        assert_eq!(last_value(&table.pct_change(1).unwrap(), "stock_A_pct_change_1"), percent);
+        
+        let momentum = |scale, name| {
+            let result = table.momentum_with(3, MomentumWeighting::Equal, scale).unwrap();
+            last_value(&result, name)
+        };
+        let fraction = momentum(ReturnScale::Fraction, "stock_A_momentum_3");
+        assert!((momentum(ReturnScale::Percent, "stock_A_momentum_pct_3") - fraction * 100.0).abs() < 1e-9);
+        assert!((momentum(ReturnScale::BasisPoints, "stock_A_momentum_bps_3") - fraction * 10_000.0).abs() < 1e-9);
+        assert_eq!(fraction, last_value(&table.momentum(3).unwrap(), "stock_A_momentum_3"));
+        let decay = table.momentum_with(3, MomentumWeighting::LinearDecay, ReturnScale::Percent).unwrap();
+        assert!(decay.df().column("stock_A_momentum_decay_pct_3").is_ok());
+        
+        let volatility = |scale, name| last_value(&table.volatility_with(3, scale).unwrap(), name);
+        let fraction = volatility(ReturnScale::Fraction, "stock_A_volatility_3");
+        assert!((volatility(ReturnScale::Percent, "stock_A_volatility_pct_3") - fraction * 100.0).abs() < 1e-9);
+        assert!((volatility(ReturnScale::BasisPoints, "stock_A_volatility_bps_3") - fraction * 10_000.0).abs() < 1e-9);
+        assert_eq!(fraction, last_value(&table.volatility(3).unwrap(), "stock_A_volatility_3"));
+        
+        // 默认单位与 momentum、volatility 一致
+        assert_eq!(ReturnScale::default(), ReturnScale::Fraction);
+    }
+    
+    #[test]
+    fn test_volume_weighted_momentum() {
+        let dates: Vec<NaiveDate> = (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let prices = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates.clone()),
+            Series::new("a", &[100.0, 110.0, 99.0, 99.0, 108.9]),
+            Series::new("b", &[10.0, 11.0, 12.1, 13.31, 14.641]),
+        ]).unwrap(), "date").unwrap();
+        // 成交量表多一只股票、少最后一期
+        let volume = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates[..4].to_vec()),
+            Series::new("a", &[5.0, 1.0, 3.0, 0.0]),
+            Series::new("b", &[2.0, 2.0, 2.0, 2.0]),
+            Series::new("c", &[1.0, 1.0, 1.0, 1.0]),
+        ]).unwrap(), "date").unwrap();
+        
+        let momentum = prices.volume_weighted_momentum(&volume, 2).unwrap();
+        assert_eq!(momentum.df().height(), 4);
+        assert!(momentum.df().column("c_momentum_vw_2").is_err());
+        let values = |name: &str| -> Vec<Option<f64>> {
+            momentum.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        // a：第 3 期 2 * (0.1 * 1 - 0.1 * 3) / 4 = -0.1
+        let a = values("a_momentum_vw_2");
+        assert_eq!((a[0], a[1]), (None, None));
+        assert!((a[2].unwrap() + 0.1).abs() < 1e-12);
+        assert!((a[3].unwrap() + 0.2).abs() < 1e-12);
+        // b：成交量相同，等于等权动量
+        let b = values("b_momentum_vw_2");
+        assert!((b[2].unwrap() - 0.2).abs() < 1e-12);
+        
+        let pct = prices.volume_weighted_momentum_with(&volume, 2, ReturnScale::Percent).unwrap();
+        let a_pct = pct.df().column("a_momentum_vw_pct_2").unwrap().f64().unwrap().get(2).unwrap();
+        assert!((a_pct + 10.0).abs() < 1e-9);
+        
+        // 窗口内成交量之和为 0
+        let idle = WideTable::new(DataFrame::new(vec![
+            Series::new("date", dates[..4].to_vec()),
+            Series::new("a", &[1.0, 1.0, 0.0, 0.0]),
+            Series::new("b", &[1.0, 1.0, 1.0, 1.0]),
+        ]).unwrap(), "date").unwrap();
+        let idle = prices.volume_weighted_momentum(&idle, 2).unwrap();
+        assert_eq!(idle.df().column("a_momentum_vw_2").unwrap().f64().unwrap().get(3), None);
+        
+        let partial = volume.df().select(["date", "a"]).unwrap();
+        let partial = WideTable::new(partial, "date").unwrap();
+        assert!(matches!(prices.volume_weighted_momentum(&partial, 2), Err(WideTableError::InvalidArgument(_))));
+    }
+    
+    #[test]
+    fn test_momentum_skip() {
+        // 最后一期大涨，被跳过后不影响 12-1 动量
+        let mut prices: Vec<f64> = (0..13).map(|i| 100.0 + i as f64).collect();
+        prices.push(200.0);
+        let table = price_table(prices);
+        
+        let momentum = table.momentum_skip(12, 1).unwrap();
+        let values: Vec<Option<f64>> = momentum.df().column("stock_A_momentum_12_1").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values[..12], vec![None; 12]);
+        assert!((values[12].unwrap() - (111.0 / 100.0 - 1.0)).abs() < 1e-12);
+        assert!((values[13].unwrap() - (112.0 / 101.0 - 1.0)).abs() < 1e-12);
+        
+        // skip = 0 即普通的复利动量
+        let plain = table.momentum_skip(1, 0).unwrap();
+        assert!((last_value(&plain, "stock_A_momentum_1_0") - (200.0 / 112.0 - 1.0)).abs() < 1e-12);
+        
+        assert!(table.momentum_skip(3, 3).is_err());
+        assert!(table.momentum_skip(0, 0).is_err());
+    }
+    
+    #[test]
+    fn test_rolling_max_and_min() {
+        let table = price_table(vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0]);
+        let column = |t: &WideTable, name: &str| -> Vec<Option<f64>> {
+            t.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        
+        let max = table.rolling_max(3).unwrap();
+        assert_eq!(
+            column(&max, "stock_A_rollmax_3"),
+            vec![None, None, Some(4.0), Some(4.0), Some(5.0), Some(9.0), Some(9.0)]
+        );
+        let min = table.rolling_min(3).unwrap();
+        assert_eq!(
+            column(&min, "stock_A_rollmin_3"),
+            vec![None, None, Some(1.0), Some(1.0), Some(1.0), Some(1.0), Some(2.0)]
+        );
+        
+        assert!(table.rolling_max(0).is_err());
+    }
+    
+    #[test]
+    fn test_pct_change_rejects_string_column() {
+        let df = DataFrame::new(vec![
+            Series::new("date", (1..=3).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect::<Vec<_>>()),
+            Series::new("stock_A", &[10.0, 11.0, 12.0]),
+            Series::new("stock_B", &["20.0", "n/a", "21.0"]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        for result in [table.pct_change(1), table.momentum(2)] {
+            match result {
+                Err(WideTableError::SchemaMismatch(msg)) => {
+                    assert!(msg.contains("stock_B") && !msg.contains("stock_A"), "{}", msg);
+                }
+                _ => panic!("字符串列应返回 SchemaMismatch"),
+            }
+        }
+    }
+    
+    #[test]
+    fn test_drawdown_series() {
+        let df = DataFrame::new(vec![
+            Series::new("date", (1..=5).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect::<Vec<_>>()),
+            Series::new("stock_A", &[Some(100.0), Some(120.0), None, Some(90.0), Some(130.0)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        
+        let drawdown = table.drawdown_series().unwrap();
+        let values: Vec<Option<f64>> = drawdown.df().column("stock_A_drawdown").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(0.0), Some(0.0), None, Some(-0.25), Some(0.0)]);
+    }
+    
+    #[test]
+    fn test_pct_change_from_base() {
+        let table = price_table(vec![50.0, 100.0, 110.0, 90.0]);
+        let base = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        
+        let indexed = table.pct_change_from_base(base).unwrap();
+        assert_eq!(indexed.df().get_column_names(), &["date", "stock_A"]);
+        let values: Vec<f64> = indexed.df().column("stock_A").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(values[..2], [-0.5, 0.0]);
+        assert!((values[2] - 0.1).abs() < 1e-12);
+        assert!((values[3] + 0.1).abs() < 1e-12);
+        
+        let missing = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        assert!(matches!(table.pct_change_from_base(missing), Err(WideTableError::InvalidArgument(_))));
+    }
+    
+    #[test]
+    fn test_time_series_zscore() {
+        let table = price_table(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        
+        // 全样本：均值 3，样本标准差 sqrt(2.5)
+        let full = table.time_series_zscore(None).unwrap();
+        let values: Vec<Option<f64>> = full.df().column("stock_A_zscore").unwrap().f64().unwrap().into_iter().collect();
+        assert!((values[0].unwrap() + 2.0 / 2.5_f64.sqrt()).abs() < 1e-12);
+        assert_eq!(values[2], Some(0.0));
+        
+        // 滚动窗口 3：每个窗口均值为中间值、标准差为 1，最新值的 z-score 恒为 1
+        let rolling = table.time_series_zscore(Some(3)).unwrap();
+        let values: Vec<Option<f64>> = rolling.df().column("stock_A_zscore").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values, vec![None, None, Some(1.0), Some(1.0), Some(1.0)]);
+        
+        let flat = price_table(vec![2.0, 2.0, 2.0]).time_series_zscore(Some(2)).unwrap();
+        assert_eq!(flat.df().column("stock_A_zscore").unwrap().null_count(), 3);
+        assert!(table.time_series_zscore(Some(1)).is_err());
+    }
+    
+    #[test]
+    fn test_reindex_to_calendar() {
+        // 2024-09-27（周五）至 2024-10-09，缺少 10-08；09-28 为周六
+        let day = |m: u32, d: u32| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let df = DataFrame::new(vec![
+            Series::new("date", &[day(9, 27), day(9, 28), day(9, 30), day(10, 9)]),
+            Series::new("stock_A", &[1.0, 2.0, 3.0, 4.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date".to_string()).unwrap();
+        
+        let cal = TradingCalendar::new("CN").unwrap();
+        let reindexed = table.reindex_to_calendar(&cal).unwrap();
+        let dates: Vec<NaiveDate> = reindexed.df().column("date").unwrap().date().unwrap().as_date_iter().flatten().collect();
+        // 补上 10-08，丢弃落在周六的 09-28
+        assert_eq!(dates, vec![day(9, 27), day(9, 30), day(10, 8), day(10, 9)]);
+        let values: Vec<f64> = reindexed.df().column("stock_A").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(values[..2], [1.0, 3.0]);
+        assert!(values[2].is_nan());
+        assert_eq!(values[3], 4.0);
+        
+        // 2026 年不在休市日数据覆盖范围内
+        let df = DataFrame::new(vec![
+            Series::new("date", &[day(12, 31), NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()]),
+            Series::new("stock_A", &[1.0, 2.0]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        assert!(matches!(table.reindex_to_calendar(&cal), Err(WideTableError::CalendarNotCovered(_))));
+    }
+    
+    #[test]
+    fn test_append_rows() {
+        let table = price_table(vec![100.0, 101.0, 102.0]);
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        
+        // 新数据乱序，追加后按时间排序
+        let new_data = DataFrame::new(vec![
+            Series::new("date", &[day(5), day(4)]),
+            Series::new("stock_A", &[105.0, 104.0]),
+        ]).unwrap();
+        let appended = table.append_rows(new_data).unwrap();
+        assert_eq!(appended.df().height(), 5);
+        assert_eq!(last_value(&appended, "stock_A"), 105.0);
+        
+        let overlapping = DataFrame::new(vec![
+            Series::new("date", &[day(3), day(4)]),
+            Series::new("stock_A", &[999.0, 104.0]),
+        ]).unwrap();
+        assert!(matches!(
+            table.append_rows(overlapping.clone()),
+            Err(WideTableError::DuplicateDate(_))
+        ));
+        
+        let deduped = table.append_rows_dedup(overlapping).unwrap();
+        let values: Vec<Option<f64>> = deduped.df().column("stock_A").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(100.0), Some(101.0), Some(102.0), Some(104.0)]);
+        
+        let wrong_schema = DataFrame::new(vec![
+            Series::new("date", &[day(6)]),
+            Series::new("stock_B", &[1.0]),
+        ]).unwrap();
+        assert!(matches!(table.append_rows(wrong_schema), Err(WideTableError::SchemaMismatch(_))));
+    }
+    
+    #[test]
+    fn test_roc_and_acceleration() {
+        let table = price_table(vec![100.0, 110.0, 121.0, 145.2, 174.24, 226.512, 294.4656]);
+        
+        let roc = table.roc(2).unwrap();
+        assert!((last_value(&roc, "stock_A_roc_2") - 69.0).abs() < 1e-9);
+        
+        let accel = table.acceleration(2).unwrap();
+        let values: Vec<Option<f64>> = accel.df().column("stock_A_accel_2").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values[..4], [None; 4]);
+        // 单期收益率 10%, 10%, 20%, 20%, 30%, 30%：ROC(2) 依次为 21%, 44%, 69%
+        assert!((values[4].unwrap() - (44.0 - 21.0)).abs() < 1e-9);
+        assert!((values[6].unwrap() - (69.0 - 44.0)).abs() < 1e-9);
+        
+        assert!(table.acceleration(0).is_err());
+    }
+    
+    #[test]
+    fn test_zscore_and_winsorize_cross_section() {
+        let df = DataFrame::new(vec![
+            Series::new("date", &[NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()]),
+            Series::new("a", &[Some(1.0)]),
+            Series::new("b", &[Some(2.0)]),
+            Series::new("c", &[Some(3.0)]),
+            Series::new("d", &[Some(100.0)]),
+            Series::new("e", &[None::<f64>]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let row = |t: &WideTable| -> Vec<Option<f64>> {
+            ["a", "b", "c", "d", "e"]
+                .iter()
+                .map(|c| t.df().column(c).unwrap().f64().unwrap().get(0))
+                .collect()
+        };
+        
+        // 有效值 [1, 2, 3, 100]：0.25 与 0.75 分位数分别为 1.75 与 27.25
+        let winsorized = table.winsorize_cross_section(0.25, 0.75).unwrap();
+        assert_eq!(row(&winsorized), vec![Some(1.75), Some(2.0), Some(3.0), Some(27.25), None]);
+        
+        let zscore = table.winsorize_cross_section(0.0, 1.0).unwrap().zscore_cross_section().unwrap();
+        let values: Vec<f64> = row(&zscore).into_iter().flatten().collect();
+        assert_eq!(values.len(), 4);
+        assert!(values.iter().sum::<f64>().abs() < 1e-9);
+        assert!(values[3] > 1.0);
+        
+        assert!(table.winsorize_cross_section(0.9, 0.1).is_err());
+    }
+    
+    #[test]
+    fn test_kalman_trend_converges() {
+        let constant = price_table(vec![10.0; 5]).kalman_trend(0.01, 1.0).unwrap();
+        let values: Vec<Option<f64>> = constant.df().column("stock_A_kalman_trend").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(10.0); 5]);
+        
+        // 价格从 0 跳到 10 后保持不变，趋势逐步收敛到 10
+        let mut prices = vec![0.0];
+        prices.extend(vec![10.0; 60]);
+        let step = price_table(prices).kalman_trend(0.1, 1.0).unwrap();
+        let trend: Vec<f64> = step.df().column("stock_A_kalman_trend").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert!(trend.windows(2).all(|w| w[1] >= w[0]));
+        assert!(trend[1] < 10.0);
+        assert!((trend[60] - 10.0).abs() < 1e-3);
+        
+        assert!(price_table(vec![1.0]).kalman_trend(0.01, 0.0).is_err());
+    }
+    
+    #[test]
+    fn test_fillna_strategies() {
+        let dates: Vec<NaiveDate> = (1..=7).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("stock_A", &[None, Some(1.0), None, None, None, Some(5.0), None]),
+            Series::new("stock_B", &[Some(2.0), Some(f64::NAN), Some(4.0), None, None, None, None]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let column = |t: &WideTable, name: &str| -> Vec<Option<f64>> {
+            t.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        
+        let ffill = table.fillna(FillStrategy::ForwardFill { limit: None }).unwrap();
+        assert_eq!(column(&ffill, "stock_A"), vec![None, Some(1.0), Some(1.0), Some(1.0), Some(1.0), Some(5.0), Some(5.0)]);
+        let limited = table.fillna(FillStrategy::ForwardFill { limit: Some(2) }).unwrap();
+        assert_eq!(column(&limited, "stock_A"), vec![None, Some(1.0), Some(1.0), Some(1.0), None, Some(5.0), Some(5.0)]);
+        assert_eq!(column(&limited, "stock_B"), vec![Some(2.0), Some(2.0), Some(4.0), Some(4.0), Some(4.0), None, None]);
+        
+        let bfill = table.fillna(FillStrategy::BackwardFill).unwrap();
+        assert_eq!(column(&bfill, "stock_A"), vec![Some(1.0), Some(1.0), Some(5.0), Some(5.0), Some(5.0), Some(5.0), None]);
+        
+        let zero = table.fillna(FillStrategy::Zero).unwrap();
+        assert_eq!(column(&zero, "stock_B"), vec![Some(2.0), Some(0.0), Some(4.0), Some(0.0), Some(0.0), Some(0.0), Some(0.0)]);
+        
+        let mean = table.fillna(FillStrategy::Mean).unwrap();
+        assert_eq!(column(&mean, "stock_A"), vec![Some(3.0), Some(1.0), Some(3.0), Some(3.0), Some(3.0), Some(5.0), Some(3.0)]);
+        
+        let interpolated = table.fillna(FillStrategy::LinearInterpolate).unwrap();
+        assert_eq!(column(&interpolated, "stock_A"), vec![None, Some(1.0), Some(2.0), Some(3.0), Some(4.0), Some(5.0), None]);
+        assert_eq!(column(&interpolated, "stock_B")[..3], [Some(2.0), Some(3.0), Some(4.0)]);
+    }
+    
+    #[test]
+    fn test_cross_demean_and_minmax() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let df = DataFrame::new(vec![
+            Series::new("date", dates),
+            Series::new("a", &[Some(1.0), Some(3.0)]),
+            Series::new("b", &[Some(3.0), Some(3.0)]),
+            Series::new("c", &[Some(5.0), None]),
+            Series::new("d", &[Some(11.0), Some(3.0)]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let row = |t: &WideTable, i: usize| -> Vec<Option<f64>> {
+            ["a", "b", "c", "d"]
+                .iter()
+                .map(|c| t.df().column(c).unwrap().f64().unwrap().get(i))
+                .collect()
+        };
+        
+        let demeaned = table.cross_demean().unwrap();
+        assert_eq!(row(&demeaned, 0), vec![Some(-4.0), Some(-2.0), Some(0.0), Some(6.0)]);
+        assert_eq!(row(&demeaned, 1), vec![Some(0.0), Some(0.0), None, Some(0.0)]);
+        
+        let scaled = table.cross_minmax().unwrap();
+        assert_eq!(row(&scaled, 0), vec![Some(0.0), Some(0.2), Some(0.4), Some(1.0)]);
+        // 所有有效值相同的截面映射为 0.5
+        assert_eq!(row(&scaled, 1), vec![Some(0.5), Some(0.5), None, Some(0.5)]);
+    }
+    
+    #[test]
+    fn test_winsorize_methods() {
+        let df = DataFrame::new(vec![
+            Series::new("date", &[NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()]),
+            Series::new("a", &[Some(1.0)]),
+            Series::new("b", &[Some(2.0)]),
+            Series::new("c", &[Some(3.0)]),
+            Series::new("d", &[Some(4.0)]),
+            Series::new("e", &[Some(100.0)]),
+            Series::new("f", &[None::<f64>]),
+        ]).unwrap();
+        let table = WideTable::new(df, "date").unwrap();
+        let row = |t: &WideTable| -> Vec<Option<f64>> {
+            ["a", "b", "c", "d", "e", "f"]
+                .iter()
+                .map(|c| t.df().column(c).unwrap().f64().unwrap().get(0))
+                .collect()
+        };
+        
+        // 中位数 3，MAD 为 1：上下界 [0, 6]，离群值截断到边界
+        let mad = table.winsorize(WinsorMethod::Mad(3.0)).unwrap();
+        assert_eq!(row(&mad), vec![Some(1.0), Some(2.0), Some(3.0), Some(4.0), Some(6.0), None]);
+        
+        // 均值 22，样本标准差 sqrt(7610 / 4)
+        let sigma = table.winsorize(WinsorMethod::Sigma(1.0)).unwrap();
+        let upper = 22.0 + (7610.0f64 / 4.0).sqrt();
+        assert!((row(&sigma)[4].unwrap() - upper).abs() < 1e-9);
+        assert_eq!(row(&sigma)[..4], row(&table)[..4]);
+        
+        let quantile = table.winsorize(WinsorMethod::Quantile(0.0, 0.75)).unwrap();
+        assert_eq!(row(&quantile)[4], Some(4.0));
+        
+        assert!(table.winsorize(WinsorMethod::Mad(0.0)).is_err());
+        assert!(table.winsorize(WinsorMethod::Quantile(0.5, 0.4)).is_err());
+    }
+    
+    #[test]
+    fn test_mask_outliers() {
+        let dates: Vec<NaiveDate> = (1..=2).map(|d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap()).collect();
+        let mut columns = vec![Series::new("date", dates)];
+        let base = [0.1, -0.2, 0.3, 0.15, -0.05, 0.25, 0.05, -0.1];
+        columns.extend(base.iter().enumerate().map(|(j, &v)| {
+            let first = if j == 2 { 100.0 } else { v };
+            let second = match j {
+                0 => None,
+                5 => Some(-50.0),
+                _ => Some(v * 0.7),
+            };
+            Series::new(&format!("s{}", j), &[Some(first), second])
+        }));
+        let table = WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap();
+        
+        let (masked, counts) = table.mask_outliers_with_counts(WinsorMethod::Mad(5.0)).unwrap();
+        let value = |j: usize, i: usize| masked.df().column(&format!("s{}", j)).unwrap().f64().unwrap().get(i);
+        assert_eq!((value(2, 0), value(5, 1), value(0, 1)), (None, None, None));
+        for j in 0..8 {
+            for i in 0..2 {
+                let original = table.df().column(&format!("s{}", j)).unwrap().f64().unwrap().get(i);
+                if (j, i) != (2, 0) && (j, i) != (5, 1) {
+                    assert_eq!(value(j, i).map(f64::to_bits), original.map(f64::to_bits));
+                }
+            }
+        }
+        assert_eq!((counts["s2"], counts["s5"], counts["s0"]), (1, 1, 0));
+        assert_eq!(counts.values().sum::<usize>(), 2);
+        
+        assert!(table.mask_outliers(WinsorMethod::Sigma(0.0)).is_err());
+    }
+    
+    #[test]
+    fn test_rolling_rank() {
+        let table = price_table(vec![1.0, 2.0, 3.0, 2.0, 0.5, 2.0]);
+        let ranked = table.rolling_rank(3).unwrap();
+        let values: Vec<Option<f64>> = ranked.df().column("stock_A_ts_rank_3").unwrap().f64().unwrap().into_iter().collect();
+        // [1, 2, 3] 中 3 最大；[2, 3, 2] 中 2 低于 3、与 2 相等；[3, 2, 0.5] 中 0.5 最小；[2, 0.5, 2] 高于 0.5、与 2 相等
+        assert_eq!(values, vec![None, None, Some(1.0), Some(0.25), Some(0.0), Some(0.75)]);
+        
+        assert!(table.rolling_rank(1).is_err());
+    }
+    
+    #[test]
+    fn test_rolling_quantile() {
+        let table = price_table(vec![4.0, 1.0, 3.0, 2.0, 10.0]);
+        let column = |t: &WideTable, name: &str| -> Vec<Option<f64>> {
+            t.df().column(name).unwrap().f64().unwrap().into_iter().collect()
+        };
+        
+        let median = table.rolling_quantile(3, 0.5).unwrap();
+        assert_eq!(column(&median, "stock_A_roll_q0.5_3"), vec![None, None, Some(3.0), Some(2.0), Some(3.0)]);
+        // 窗口 [3, 2, 10] 的 0.25 分位数：2 + (3 - 2) * 0.5
+        let lower = table.rolling_quantile(3, 0.25).unwrap();
+        assert_eq!(column(&lower, "stock_A_roll_q0.25_3")[4], Some(2.5));
+        
+        assert!(table.rolling_quantile(3, 1.5).is_err());
+        assert!(table.rolling_quantile(0, 0.5).is_err());
     }
 }
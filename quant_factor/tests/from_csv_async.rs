@@ -0,0 +1,12 @@
+#![cfg(feature = "tokio")]
+
+use quant_factor::WideTable;
+
+#[tokio::test]
+async fn test_from_csv_async_loads_fixture() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/prices.csv");
+    let table = WideTable::from_csv_async(path, "date").await.unwrap();
+    
+    assert_eq!(table.time_col(), "date");
+    assert_eq!(table.df().shape(), (3, 4));
+}
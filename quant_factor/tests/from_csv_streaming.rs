@@ -0,0 +1,24 @@
+use polars::prelude::*;
+use quant_factor::WideTable;
+
+#[test]
+fn test_from_csv_streaming_matches_from_csv() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/prices.csv");
+    let eager = WideTable::from_csv(path, "date").unwrap();
+    
+    // 只加载部分股票，与一次性加载后再选列的结果一致
+    let streamed = WideTable::from_csv_streaming(path, "date", &["000001", "600000"], None).unwrap();
+    let expected = eager.df().select(["date", "000001", "600000"]).unwrap();
+    assert!(streamed.df().equals_missing(&expected));
+    
+    // 过滤条件在扫描时应用
+    let filter = col("000001").gt(lit(10.0));
+    let filtered = WideTable::from_csv_streaming(path, "date", &["000001"], Some(filter.clone())).unwrap();
+    let expected = eager.df().clone().lazy().select([col("date"), col("000001")]).filter(filter).collect().unwrap();
+    assert!(filtered.df().equals_missing(&expected));
+    assert!(filtered.df().height() < eager.df().height());
+    
+    assert!(WideTable::from_csv_streaming(path, "missing", &["000001"], None).is_err());
+    assert!(WideTable::from_csv_streaming(path, "date", &["999999"], None).is_err());
+    assert!(WideTable::from_csv_streaming(path, "date", &[], None).is_err());
+}
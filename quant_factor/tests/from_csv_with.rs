@@ -0,0 +1,17 @@
+use polars::prelude::DataType;
+use quant_factor::{CsvOptions, WideTable};
+
+#[test]
+fn test_from_csv_with_semicolon_no_header() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/prices_semicolon_no_header.csv");
+    let options = CsvOptions {
+        delimiter: b';',
+        has_header: false,
+        time_dtype: Some(DataType::Date),
+    };
+    let table = WideTable::from_csv_with(path, "column_1", options).unwrap();
+    
+    assert_eq!(table.df().shape(), (3, 3));
+    assert_eq!(table.df().column("column_1").unwrap().dtype(), &DataType::Date);
+    assert_eq!(table.df().column("column_3").unwrap().f64().unwrap().get(2), Some(21.0));
+}
@@ -0,0 +1,65 @@
+use chrono::NaiveDate;
+use polars::prelude::*;
+use quant_factor::{FactorCalculator, SeasonGroup, WideTable};
+
+const PRICES_A: [f64; 8] = [10.0, 10.5, 10.2, 10.8, 11.0, 10.6, 11.3, 11.5];
+const PRICES_B: [f64; 8] = [20.0, 19.5, 19.8, 20.4, 20.1, 20.9, 21.0, 20.7];
+
+/// 按 `order` 的顺序排列行，时间列为 `index`
+fn table_with_index(index: Series, order: &[usize]) -> WideTable {
+    let take = |values: &[f64]| order.iter().map(|&i| values[i]).collect::<Vec<_>>();
+    let index = index.take_slice(&order.iter().map(|&i| i as IdxSize).collect::<Vec<_>>()).unwrap();
+    let df = DataFrame::new(vec![
+        index,
+        Series::new("stock_A", take(&PRICES_A)),
+        Series::new("stock_B", take(&PRICES_B)),
+    ]).unwrap();
+    WideTable::new(df, "t").unwrap()
+}
+
+fn bars(order: &[usize]) -> WideTable {
+    table_with_index(Series::new("t", (0..8).map(|i| 1000 + i as i64).collect::<Vec<_>>()), order)
+}
+
+fn daily(order: &[usize]) -> WideTable {
+    let dates: Vec<NaiveDate> = (0..8)
+        .map(|i| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i))
+        .collect();
+    table_with_index(Series::new("t", dates), order)
+}
+
+/// 除时间列外的所有列
+fn values(table: &WideTable) -> DataFrame {
+    table.df().drop("t").unwrap()
+}
+
+#[test]
+fn test_integer_index_matches_date_index() {
+    // 行顺序打乱，结果应按 bar 编号排序
+    let order = [3, 0, 7, 5, 1, 6, 2, 4];
+    let (bars, daily) = (bars(&order), daily(&order));
+    
+    let momentum = bars.momentum(2).unwrap();
+    let index: Vec<i64> = momentum.df().column("t").unwrap().i64().unwrap().into_no_null_iter().collect();
+    assert_eq!(index, (1000..1008).collect::<Vec<_>>());
+    assert!(values(&momentum).equals_missing(&values(&daily.momentum(2).unwrap())));
+    
+    assert!(values(&bars.pct_change(1).unwrap()).equals_missing(&values(&daily.pct_change(1).unwrap())));
+    assert!(values(&bars.sma(3).unwrap()).equals_missing(&values(&daily.sma(3).unwrap())));
+    assert!(values(&bars.volatility(3).unwrap()).equals_missing(&values(&daily.volatility(3).unwrap())));
+    assert!(values(&bars.rolling_rank(4).unwrap()).equals_missing(&values(&daily.rolling_rank(4).unwrap())));
+    assert!(values(&bars.time_series_zscore(Some(3)).unwrap())
+        .equals_missing(&values(&daily.time_series_zscore(Some(3)).unwrap())));
+    
+    let bars_consist = FactorCalculator::calculate_consistency(&bars, 4, 2).unwrap();
+    let daily_consist = FactorCalculator::calculate_consistency(&daily, 4, 2).unwrap();
+    assert!(values(&bars_consist).equals_missing(&values(&daily_consist)));
+}
+
+#[test]
+fn test_integer_index_rejects_calendar_operations() {
+    let bars = bars(&[0, 1, 2, 3, 4, 5, 6, 7]);
+    assert!(bars.ensure_unique_dates().is_ok());
+    assert!(bars.pct_change_from_base(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()).is_err());
+    assert!(FactorCalculator::calculate_seasonality(&bars, SeasonGroup::Weekday).is_err());
+}
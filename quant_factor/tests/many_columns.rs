@@ -0,0 +1,42 @@
+use chrono::NaiveDate;
+use polars::prelude::*;
+use quant_factor::{FactorCalculator, WideTable};
+
+const N_ROWS: usize = 60;
+const N_COLS: usize = 5000;
+
+/// 60 行 x 5000 列的价格表，列数与全市场 A 股相当
+fn wide_table() -> WideTable {
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let dates: Vec<NaiveDate> = (0..N_ROWS).map(|i| start + chrono::Duration::days(i as i64)).collect();
+    let mut columns = vec![Series::new("date", dates)];
+    for c in 0..N_COLS {
+        let prices: Vec<f64> = (0..N_ROWS)
+            .map(|i| 100.0 + ((i * 7 + c * 13) % 29) as f64 - 14.0 + i as f64 * 0.05)
+            .collect();
+        columns.push(Series::new(&format!("{:06}", c), prices));
+    }
+    WideTable::new(DataFrame::new(columns).unwrap(), "date").unwrap()
+}
+
+/// 逐列链式调用 with_columns 会生成与列数等深的查询计划，列数上千时栈溢出；
+/// 这里覆盖所有按股票列生成表达式的方法，确保它们都只调用一次 with_columns
+#[test]
+fn test_per_column_factors_on_5000_columns() {
+    let table = wide_table();
+    let check = |name: &str, result: WideTable, suffix: &str| {
+        let expected = format!("{:06}{}", N_COLS - 1, suffix);
+        assert!(result.df().column(&expected).is_ok(), "{} 缺少列 {}", name, expected);
+        assert_eq!(result.df().height(), N_ROWS, "{}", name);
+    };
+    
+    check("pct_change", table.pct_change(1).unwrap(), "_pct_change_1");
+    check("momentum", table.momentum(5).unwrap(), "_momentum_5");
+    check("forward_returns", table.forward_returns(1).unwrap(), "_fwd_return_1");
+    check("sma", table.sma(5).unwrap(), "_sma_5");
+    check("ewm_volatility", table.ewm_volatility(0.06).unwrap(), "_ewm_vol");
+    check("momentum_accel", FactorCalculator::calculate_momentum_accel(&table, 5).unwrap(), "_mom_accel_5");
+    check("vov", FactorCalculator::calculate_vov(&table, 5, 5).unwrap(), "_vov_5_5");
+    check("bias", FactorCalculator::calculate_bias(&table, 5).unwrap(), "_bias_5");
+    check("cmo", FactorCalculator::calculate_cmo(&table, 5).unwrap(), "_cmo_5");
+}